@@ -1,36 +1,80 @@
+mod completion;
+mod decorations;
 mod file;
+mod gutter;
 mod highlighter;
+mod hover;
+mod indent;
+mod outline;
 mod validation;
 
-use crate::editor::highlighter::Highlighter;
-use crate::preferences::Preferences;
+use crate::editor::completion::Completions;
+use crate::editor::decorations::{Annotation, Decorations, ANNOTATION_GAP};
+use crate::editor::gutter::{Metrics, PADDING};
+use crate::editor::highlighter::{Highlighter, DARK_THEMES};
+use crate::keymap;
+use crate::language::{self, Analysis, Hover, SignatureHelp};
+use crate::preferences::{Indentation, Preferences};
+use crate::viewer::device::{Device, Profile, Target};
+use crate::viewer::probe;
+use crate::viewer::settings::Settings;
 use crate::widget::text_editor::TextEditor;
 use crate::widget::{text_editor, Element};
-use crate::{preferences, theme, FragmentShader, JETBRAINS_MONO};
+use crate::{format, preferences, theme, FragmentShader, JETBRAINS_MONO};
 use iced::alignment::Horizontal;
-use iced::widget::text_editor::Action;
-use iced::widget::{button, checkbox, column, container, row, scrollable, text, tooltip};
-use iced::{alignment, keyboard, Alignment, Command, Font, Length};
+use iced::keyboard::{KeyCode, Modifiers};
+use iced::widget::text_editor::{Action, Edit, Motion};
+use iced::widget::{
+    button, checkbox, column, container, pick_list, responsive, row, scrollable, text, tooltip,
+};
+use iced::{alignment, Alignment, Command, Font, Length, Size, Vector};
+use once_cell::sync::Lazy;
+use std::cell::Cell;
 use std::ops::Range;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+static SCROLLABLE: Lazy<scrollable::Id> = Lazy::new(scrollable::Id::unique);
+
+/// How many characters a tab is drawn as, at most.
+const TAB_WIDTH: usize = 8;
+
 #[derive(Clone, Debug)]
 pub enum Message {
     Init(Result<(Preferences, Arc<FragmentShader>), preferences::Error>),
     Action(Action),
+    Scrolled(scrollable::Viewport),
     Validate,
     Validated(Result<Arc<FragmentShader>, validation::Error>),
     AutoValidate(bool),
+    ProfileSelected(Profile),
+    IndentationSelected(Indentation),
+    ThemeSelected(iced::highlighter::Theme),
+    DeviceDetected(Device),
+    RenderSettingsChanged(Settings),
+    Probed(Vec<probe::Reading>),
     New,
     Open,
     Opened(Result<(PathBuf, Arc<FragmentShader>), file::Error>),
     Save,
     Saved(Result<PathBuf, file::Error>),
+    Format,
+    FormatOnSave(bool),
+    /// The commands run from the command palette changed, to be saved with the preferences.
+    RecentCommands(Vec<keymap::Command>),
     Undo,
     Redo,
     Search,
     Indent,
+    Outdent,
+    SelectCompletion(usize),
+    Complete(usize),
+    DismissCompletions,
+    GoToDefinition,
+    /// Moves the cursor to a byte offset in the shader.
+    GoTo(usize),
+    ToggleOutline,
+    ModifiersChanged(Modifiers),
     PreferencesSaved(Result<(), preferences::Error>),
 }
 
@@ -45,7 +89,34 @@ pub struct Editor {
     shader_path: Option<PathBuf>,
     validation_status: validation::Status,
     auto_validate: bool,
+    profile: Profile,
+    indentation: Indentation,
+    format_on_save: bool,
+    /// The commands run from the command palette, which lists them, kept to be saved with the
+    /// other preferences.
+    recent_commands: Vec<keymap::Command>,
+    device: Option<Device>,
     is_loading: bool,
+    /// The values of the shader's probes at the pixel inspected in the viewer.
+    probes: Vec<probe::Reading>,
+    /// The shader's symbols, from the last time it was validated.
+    analysis: Option<Analysis>,
+    completions: Completions,
+    /// The documentation of what's at the cursor.
+    hover: Option<Hover>,
+    signature_help: Option<SignatureHelp>,
+    /// The uses of the symbol at the cursor.
+    references: Vec<Range<usize>>,
+    show_outline: bool,
+    /// The modifiers held, for cmd+click.
+    modifiers: Modifiers,
+    /// How far the editor & its gutter are scrolled.
+    scroll: Vector,
+    /// The size of the editor's scrollable when it was last laid out. iced only reports it once
+    /// the scrollable is scrolled, so it's measured in `view` instead.
+    bounds: Cell<Size>,
+    /// The size of the editor's lines & characters, measured when it's laid out.
+    metrics: Cell<Metrics>,
 }
 
 impl Default for Editor {
@@ -58,24 +129,70 @@ impl Default for Editor {
             shader_path: None,
             validation_status: validation::Status::default(),
             auto_validate: true,
+            profile: Profile::default(),
+            indentation: Indentation::default(),
+            format_on_save: false,
+            recent_commands: vec![],
+            device: None,
             is_loading: true,
+            probes: vec![],
+            analysis: None,
+            completions: Completions::default(),
+            hover: None,
+            signature_help: None,
+            references: vec![],
+            show_outline: false,
+            modifiers: Modifiers::default(),
+            scroll: Vector::new(0.0, 0.0),
+            bounds: Cell::new(Size::ZERO),
+            metrics: Cell::default(),
         }
     }
 }
 
 impl Editor {
-    pub fn keypress(
-        &self,
-        key: keyboard::KeyCode,
-        modifiers: keyboard::Modifiers,
-    ) -> Option<Message> {
+    /// The message of a keyboard shortcut, if it's one of the editor's.
+    pub fn shortcut(&self, command: keymap::Command) -> Option<Message> {
+        match command {
+            keymap::Command::New => Some(Message::New),
+            keymap::Command::Open => Some(Message::Open),
+            keymap::Command::Save => Some(Message::Save),
+            keymap::Command::FormatDocument => Some(Message::Format),
+            keymap::Command::ToggleFormatOnSave => {
+                Some(Message::FormatOnSave(!self.format_on_save))
+            }
+            keymap::Command::Validate => Some(Message::Validate),
+            keymap::Command::Undo => Some(Message::Undo),
+            keymap::Command::Redo => Some(Message::Redo),
+            keymap::Command::Search => Some(Message::Search),
+            keymap::Command::Indent => Some(Message::Indent),
+            keymap::Command::Outdent => Some(Message::Outdent),
+            keymap::Command::GoToDefinition => Some(Message::GoToDefinition),
+            keymap::Command::ToggleOutline => Some(Message::ToggleOutline),
+            keymap::Command::ToggleAutoValidate => Some(Message::AutoValidate(!self.auto_validate)),
+            keymap::Command::SwitchTheme => {
+                let current = DARK_THEMES.iter().position(|theme| *theme == self.theme);
+                let next = current.map_or(0, |i| (i + 1) % DARK_THEMES.len());
+
+                Some(Message::ThemeSelected(DARK_THEMES[next]))
+            }
+            _ => None,
+        }
+    }
+
+    /// The message of a key press that picks a completion while they're listed.
+    pub fn key_press(&self, key: KeyCode, modifiers: Modifiers) -> Option<Message> {
+        if self.completions.is_empty() || !modifiers.is_empty() {
+            return None;
+        }
+
+        let selected = self.completions.selected();
+
         match key {
-            keyboard::KeyCode::Enter if modifiers.control() => Some(Message::Validate),
-            keyboard::KeyCode::S if modifiers.command() => Some(Message::Save),
-            keyboard::KeyCode::Z if modifiers.command() => Some(Message::Undo),
-            keyboard::KeyCode::Y if modifiers.command() => Some(Message::Redo),
-            keyboard::KeyCode::F if modifiers.command() => Some(Message::Search),
-            keyboard::KeyCode::Tab => Some(Message::Indent),
+            KeyCode::Up => Some(Message::SelectCompletion(selected.saturating_sub(1))),
+            KeyCode::Down => Some(Message::SelectCompletion(selected + 1)),
+            KeyCode::Enter | KeyCode::Tab => Some(Message::Complete(selected)),
+            KeyCode::Escape => Some(Message::DismissCompletions),
             _ => None,
         }
     }
@@ -86,9 +203,17 @@ impl Editor {
                 let cmd = match result {
                     Ok((prefs, shader)) => {
                         self.auto_validate = prefs.auto_validate;
+                        self.profile = prefs.target_profile;
+                        self.indentation = prefs.indentation;
+                        self.format_on_save = prefs.format_on_save;
+                        self.recent_commands = prefs.recent_commands;
                         self.shader_path = prefs.last_shader_path;
                         self.content = text_editor::Content::with_text(&shader);
-                        Command::perform(validation::validate(shader), Message::Validated)
+                        self.analyse();
+                        Command::perform(
+                            validation::validate(shader, self.target()),
+                            Message::Validated,
+                        )
                     }
                     Err(e) => {
                         println!("Error loading prefs: {e:?}");
@@ -99,24 +224,111 @@ impl Editor {
                 self.is_loading = false;
                 return (Event::None, cmd);
             }
+            Message::Action(Action::Scroll { lines }) => {
+                // the editor is laid out at its full size, so the scrollable around it scrolls
+                // instead of the editor itself
+                let line_height = self.metrics.get().line_height;
+                let offset = self.scroll + Vector::new(0.0, lines as f32 * line_height);
+
+                return (Event::None, self.scroll_to(offset));
+            }
             Message::Action(action) => {
-                //TODO fix not being able to use hotkeys while text editor is focused
-                let should_validate = action.is_edit() && self.auto_validate;
-                self.content.perform(action);
+                // keep completing while a word is typed or corrected
+                let is_completing = match &action {
+                    Action::Edit(Edit::Insert(c)) => c.is_alphanumeric() || matches!(c, '_' | '.'),
+                    Action::Edit(Edit::Backspace) => !self.completions.is_empty(),
+                    _ => false,
+                };
+                // cmd+click jumps to the definition of what's clicked
+                let is_go_to = matches!(action, Action::Click(_)) && self.modifiers.command();
 
-                if should_validate {
-                    return self.update(Message::Validate);
+                let actions = match action {
+                    Action::Edit(edit) => indent::edit(&self.content, edit, self.indentation),
+                    action => vec![action],
+                };
+
+                let result = self.perform(actions);
+
+                self.completions = if is_completing {
+                    Completions::new(&self.content, self.analysis.as_ref())
+                } else {
+                    Completions::default()
+                };
+                self.look_up_cursor();
+
+                if is_go_to {
+                    return self.update(Message::GoToDefinition);
+                }
+
+                return result;
+            }
+            Message::SelectCompletion(i) => {
+                self.completions.select(i);
+            }
+            Message::Complete(i) => {
+                let actions = self.completions.accept(i);
+                self.completions = Completions::default();
+
+                let result = self.perform(actions);
+                self.look_up_cursor();
+
+                return result;
+            }
+            Message::DismissCompletions => {
+                self.completions = Completions::default();
+            }
+            Message::GoToDefinition => {
+                let shader = self.content.text();
+                let offset = cursor_offset(&self.content);
+
+                if let Some(definition) =
+                    language::definition(self.analysis.as_ref(), &shader, offset)
+                {
+                    return self.update(Message::GoTo(definition.start));
                 }
             }
+            Message::GoTo(offset) => {
+                let (line, column) = self.position(offset);
+
+                let actions = [Action::Move(Motion::DocumentStart)]
+                    .into_iter()
+                    .chain(std::iter::repeat(Action::Move(Motion::Down)).take(line))
+                    .chain(std::iter::repeat(Action::Move(Motion::Right)).take(column))
+                    .collect();
+
+                let result = self.perform(actions);
+
+                self.completions = Completions::default();
+                self.look_up_cursor();
+
+                return result;
+            }
+            Message::ToggleOutline => {
+                self.show_outline = !self.show_outline;
+            }
+            Message::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+            }
+            Message::Scrolled(viewport) => {
+                let offset = viewport.absolute_offset();
+
+                self.scroll = Vector::new(offset.x, offset.y);
+            }
             Message::New => {
                 let empty_shader = include_str!("viewer/shaders/empty_frag.wgsl");
 
                 self.shader_path = None;
                 self.content = text_editor::Content::with_text(empty_shader);
+                self.analysis = None;
+                self.analyse();
+                self.completions = Completions::default();
+                self.hover = None;
+                self.signature_help = None;
+                self.references = vec![];
 
                 return (
                     Event::UpdatePipeline(Arc::new(empty_shader.to_string())),
-                    Command::none(),
+                    self.scroll_to(Vector::new(0.0, 0.0)),
                 );
             }
             Message::Open => {
@@ -133,10 +345,17 @@ impl Editor {
                 let cmds = if let Ok((path, shader)) = result {
                     self.shader_path = Some(path);
                     self.content = text_editor::Content::with_text(&shader);
+                    self.analysis = None;
+                    self.analyse();
+                    self.completions = Completions::default();
 
                     Command::batch(vec![
+                        self.scroll_to(Vector::new(0.0, 0.0)),
                         self.save_prefs(),
-                        Command::perform(validation::validate(shader), Message::Validated),
+                        Command::perform(
+                            validation::validate(shader, self.target()),
+                            Message::Validated,
+                        ),
                     ])
                 } else {
                     Command::none()
@@ -151,15 +370,20 @@ impl Editor {
                 return if self.is_loading {
                     (Event::None, Command::none())
                 } else {
+                    let is_formatted = self.format_on_save && self.format();
                     let shader = self.content.text();
+                    let save = Command::perform(
+                        file::save(self.shader_path.clone(), shader),
+                        Message::Saved,
+                    );
 
-                    (
-                        Event::None,
-                        Command::perform(
-                            file::save(self.shader_path.clone(), shader),
-                            Message::Saved,
-                        ),
-                    )
+                    if is_formatted && self.auto_validate {
+                        let (event, validate) = self.update(Message::Validate);
+
+                        (event, Command::batch(vec![save, validate]))
+                    } else {
+                        (Event::None, save)
+                    }
                 }
             }
             Message::Saved(result) => {
@@ -169,18 +393,37 @@ impl Editor {
                 //TODO handle error
                 return (Event::None, self.save_prefs());
             }
+            Message::Format => {
+                if self.format() && self.auto_validate {
+                    return self.update(Message::Validate);
+                }
+            }
+            Message::FormatOnSave(checked) => {
+                self.format_on_save = checked;
+
+                return (Event::None, self.save_prefs());
+            }
+            Message::RecentCommands(commands) => {
+                self.recent_commands = commands;
+
+                return (Event::None, self.save_prefs());
+            }
             Message::Validate => {
                 self.validation_status = validation::Status::Validating;
                 let shader = Arc::new(self.content.text());
 
                 return (
                     Event::None,
-                    Command::perform(validation::validate(shader), Message::Validated),
+                    Command::perform(
+                        validation::validate(shader, self.target()),
+                        Message::Validated,
+                    ),
                 );
             }
             Message::Validated(result) => match result {
                 Ok(shader) => {
                     self.validation_status = validation::Status::Validated;
+
                     return (Event::UpdatePipeline(shader), Command::none());
                 }
                 Err(error) => {
@@ -192,6 +435,30 @@ impl Editor {
                 self.auto_validate = checked;
                 return (Event::None, self.save_prefs());
             }
+            Message::ProfileSelected(profile) => {
+                self.profile = profile;
+                let (event, cmd) = self.update(Message::Validate);
+
+                return (event, Command::batch(vec![cmd, self.save_prefs()]));
+            }
+            Message::DeviceDetected(device) => {
+                self.device = Some(device);
+
+                if self.profile == Profile::Device {
+                    return self.update(Message::Validate);
+                }
+            }
+            Message::RenderSettingsChanged(settings) => {
+                let text = settings.write(&self.content.text());
+                self.set_text(&text);
+
+                if self.auto_validate {
+                    return self.update(Message::Validate);
+                }
+            }
+            Message::Probed(readings) => {
+                self.probes = readings;
+            }
             Message::Undo => {
                 //TODO!
             }
@@ -199,7 +466,18 @@ impl Editor {
                 //TODO!
             }
             Message::Indent => {
-                //TODO!
+                return self.perform(indent::indent(&self.content, self.indentation));
+            }
+            Message::Outdent => {
+                return self.perform(indent::outdent(&self.content, self.indentation));
+            }
+            Message::IndentationSelected(indentation) => {
+                self.indentation = indentation;
+
+                return (Event::None, self.save_prefs());
+            }
+            Message::ThemeSelected(theme) => {
+                self.theme = theme;
             }
             Message::Search => {
                 //TODO!
@@ -212,15 +490,303 @@ impl Editor {
         (Event::None, Command::none())
     }
 
+    /// Replaces the text of the editor, keeping the cursor where it was relative to the text
+    /// around it when lines were added or removed above it.
+    fn set_text(&mut self, text: &str) {
+        let (line, column) = self.content.cursor_position();
+        // the cursor's column is in bytes, but moving right moves by a character
+        let column = self.content.line(line).map_or(0, |text| {
+            text.get(..column.min(text.len()))
+                .map_or(0, |before| before.chars().count())
+        });
+        let line =
+            (line + text.lines().count()).saturating_sub(self.content.text().lines().count());
+
+        self.content = text_editor::Content::with_text(text);
+
+        for _ in 0..line {
+            self.content.perform(Action::Move(Motion::Down));
+        }
+
+        let (line, _) = self.content.cursor_position();
+        let length = self
+            .content
+            .line(line)
+            .map_or(0, |text| text.chars().count());
+
+        for _ in 0..column.min(length) {
+            self.content.perform(Action::Move(Motion::Right));
+        }
+    }
+
+    /// Formats the shader with the editor's indentation, returning whether that changed it.
+    fn format(&mut self) -> bool {
+        let shader = self.content.text();
+        let formatted = format::format(&shader, &self.indentation.unit());
+
+        if formatted == shader {
+            return false;
+        }
+
+        self.set_text(&formatted);
+        self.analyse();
+        self.completions = Completions::default();
+        self.look_up_cursor();
+
+        true
+    }
+
+    /// Performs each of the `actions` on the editor's content, validating it if any were edits.
+    fn perform(&mut self, actions: Vec<Action>) -> (Event, Command<Message>) {
+        let is_edit = actions.iter().any(Action::is_edit);
+
+        for action in actions {
+            self.content.perform(action);
+        }
+
+        if is_edit {
+            self.analyse();
+        }
+
+        let should_validate = is_edit && self.auto_validate;
+
+        let scroll = self.scroll_to_cursor();
+
+        if should_validate {
+            let (event, validate) = self.update(Message::Validate);
+
+            return (event, Command::batch(vec![scroll, validate]));
+        }
+
+        (Event::None, scroll)
+    }
+
+    /// Analyses the shader's symbols if it parses, keeping the last analysis while it doesn't so
+    /// completions & hovers keep working mid-edit.
+    fn analyse(&mut self) {
+        self.analysis = Analysis::new(&self.content.text())
+            .ok()
+            .or(self.analysis.take());
+    }
+
+    /// Finds the documentation of what's at the cursor, the signature of the call it's in & the
+    /// uses of the symbol there.
+    fn look_up_cursor(&mut self) {
+        let shader = self.content.text();
+        let offset = cursor_offset(&self.content);
+
+        self.hover = language::hover(self.analysis.as_ref(), &shader, offset);
+        self.signature_help = language::signature_help(self.analysis.as_ref(), &shader, offset);
+        self.references = language::references(self.analysis.as_ref(), &shader, offset);
+    }
+
+    /// The line & the column in characters of a byte `offset` in the shader, counted from 0.
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let mut start = 0;
+
+        for (i, line) in self.content.lines().enumerate() {
+            if offset <= start + line.len() {
+                let column = line
+                    .get(..offset - start)
+                    .map_or(0, |before| before.chars().count());
+
+                return (i, column);
+            }
+
+            start += line.len() + 1;
+        }
+
+        (self.content.line_count().saturating_sub(1), 0)
+    }
+
+    /// The references to the symbol at the cursor, as the line they're on & their range in it.
+    fn reference_lines(&self) -> Vec<(usize, Range<usize>)> {
+        let mut start = 0;
+
+        self.content
+            .lines()
+            .enumerate()
+            .flat_map(|(i, line)| {
+                let line_start = start;
+                let end = start + line.len();
+
+                start = end + 1;
+
+                self.references
+                    .iter()
+                    .filter(move |range| line_start <= range.start && range.end <= end)
+                    .map(move |range| (i, range.start - line_start..range.end - line_start))
+            })
+            .collect()
+    }
+
+    /// Scrolls the editor & its gutter to `offset`, within the bounds of its content.
+    fn scroll_to(&mut self, offset: Vector) -> Command<Message> {
+        let bounds = self.bounds.get();
+        let content = self.content_size(bounds.width);
+
+        let max_x = (content.width - bounds.width).max(0.0);
+        let max_y = (content.height - bounds.height).max(0.0);
+        let offset = Vector::new(offset.x.clamp(0.0, max_x), offset.y.clamp(0.0, max_y));
+
+        if offset == self.scroll {
+            return Command::none();
+        }
+
+        self.scroll = offset;
+
+        scrollable::scroll_to(
+            SCROLLABLE.clone(),
+            scrollable::AbsoluteOffset {
+                x: offset.x,
+                y: offset.y,
+            },
+        )
+    }
+
+    /// Scrolls just far enough to show the cursor, along with the gutter when scrolling left.
+    fn scroll_to_cursor(&mut self) -> Command<Message> {
+        let (line, column) = self.content.cursor_position();
+        let bounds = self.bounds.get();
+        let metrics = self.metrics.get();
+        let gutter = gutter::width(self.content.line_count(), metrics);
+
+        let x = gutter + PADDING + column as f32 * metrics.character_width;
+        let y = metrics.line_y(line);
+
+        let follow = |offset: f32, start: f32, end: f32, size: f32| {
+            if start < offset {
+                start
+            } else if end > offset + size {
+                end - size
+            } else {
+                offset
+            }
+        };
+
+        self.scroll_to(Vector::new(
+            follow(
+                self.scroll.x,
+                x - gutter - PADDING,
+                x + metrics.character_width + PADDING,
+                bounds.width,
+            ),
+            follow(
+                self.scroll.y,
+                y - PADDING,
+                y + metrics.line_height + PADDING,
+                bounds.height,
+            ),
+        ))
+    }
+
+    /// The size of the gutter & the editor when laid out in a scrollable `width` wide. The editor
+    /// is made wide enough to fit its longest line, so it never wraps & the gutter stays aligned.
+    fn content_size(&self, width: f32) -> Size {
+        let lines = self.content.line_count();
+        let metrics = self.metrics.get();
+        let gutter = gutter::width(lines, metrics);
+
+        let longest = self
+            .content
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+                    .sum::<usize>()
+            })
+            .chain(self.annotations().iter().map(|annotation| {
+                annotation.column + ANNOTATION_GAP + annotation.text.chars().count()
+            }))
+            .max()
+            .unwrap_or_default();
+
+        // with room for the cursor after the longest line
+        let text_width = (longest + 1) as f32 * metrics.character_width + 2.0 * PADDING;
+
+        Size::new(
+            gutter + text_width.max(width - gutter),
+            metrics.line_y(lines) + PADDING,
+        )
+    }
+
+    /// The value of each probe at the inspected pixel, after the line it's on.
+    fn annotations(&self) -> Vec<Annotation> {
+        let mut annotations: Vec<Annotation> = vec![];
+
+        for probe in &self.probes {
+            let value = probe.value.as_deref().unwrap_or("not reached");
+            let text = format!("{} = {value}", probe.expression);
+            let line = probe.line.saturating_sub(1);
+
+            match annotations.last_mut() {
+                Some(last) if last.line == line => {
+                    last.text.push_str(", ");
+                    last.text.push_str(&text);
+                }
+                _ => annotations.push(Annotation {
+                    line,
+                    column: self.content.line(line).map_or(0, |text| columns(&text)),
+                    text,
+                }),
+            }
+        }
+
+        annotations
+    }
+
+    /// The lines of the shader with parse errors, counted from 0.
+    fn error_lines(&self) -> Vec<usize> {
+        let validation::Status::Invalid(validation::Error::Parse { errors, .. }) =
+            &self.validation_status
+        else {
+            return vec![];
+        };
+
+        let mut start = 0;
+
+        self.content
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let end = start + line.len() + 1;
+                let has_error = errors
+                    .iter()
+                    .any(|(range, _)| (start..end).contains(&range.start));
+
+                start = end;
+
+                has_error.then_some(i)
+            })
+            .collect()
+    }
+
+    /// The number of characters in the shader, counted without copying it.
+    fn len(&self) -> usize {
+        self.content
+            .lines()
+            .map(|line| line.chars().count() + 1)
+            .sum::<usize>()
+            .saturating_sub(1)
+    }
+
     fn save_prefs(&self) -> Command<Message> {
         let prefs = Preferences {
             last_shader_path: self.shader_path.clone(),
             auto_validate: self.auto_validate,
+            target_profile: self.profile,
+            indentation: self.indentation,
+            format_on_save: self.format_on_save,
+            recent_commands: self.recent_commands.clone(),
         };
 
         Command::perform(preferences::save(prefs), Message::PreferencesSaved)
     }
 
+    fn target(&self) -> Target {
+        self.profile.target(self.device.as_ref())
+    }
+
     pub fn view(&self) -> Element<Message> {
         let errors =
             if let validation::Status::Invalid(validation::Error::Parse { message, errors }) =
@@ -235,17 +801,52 @@ impl Editor {
                 vec![]
             };
 
-        let text_editor = TextEditor::new(&self.content)
-            .font(JETBRAINS_MONO)
-            .padding(10)
-            .highlight::<Highlighter>(
-                highlighter::Settings {
-                    theme: iced::highlighter::Theme::Base16Mocha,
-                    errors,
-                },
-                |highlight, _theme| highlight.to_format(),
-            )
-            .on_action(Message::Action);
+        let lines = self.content.line_count();
+        let (line, column) = self.content.cursor_position();
+        let error_lines = self.error_lines();
+        let references = self.reference_lines();
+        let annotations = self.annotations();
+
+        let editor = responsive(move |size| -> Element<Message> {
+            self.bounds.set(size);
+
+            let text_editor = TextEditor::new(&self.content)
+                .font(JETBRAINS_MONO)
+                .padding(PADDING)
+                .highlight::<Highlighter>(
+                    highlighter::Settings {
+                        theme: self.theme,
+                        errors: errors.clone(),
+                        references: references.clone(),
+                    },
+                    |highlight, theme| highlight.to_format(theme),
+                )
+                .on_action(Message::Action);
+
+            let content = self.content_size(size.width);
+            let gutter_width = gutter::width(lines, self.metrics.get());
+
+            scrollable(row![
+                gutter::view(lines, line, &error_lines, &self.metrics),
+                container(Decorations::new(
+                    text_editor,
+                    line,
+                    annotations.clone(),
+                    &self.metrics
+                ))
+                .width(content.width - gutter_width)
+                .style(theme::Container::Editor),
+            ])
+            .id(SCROLLABLE.clone())
+            .direction(scrollable::Direction::Both {
+                vertical: scrollable::Properties::default(),
+                horizontal: scrollable::Properties::default(),
+            })
+            .on_scroll(Message::Scrolled)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+        });
 
         let path = container(text(
             self.shader_path
@@ -255,28 +856,50 @@ impl Editor {
         .align_x(Horizontal::Left)
         .width(Length::Fill);
 
-        let char_count = container(
-            //TODO expose a len() function from iced editor to avoid extra allocation
-            text(self.content.text().len()),
+        let selection = self
+            .content
+            .selection()
+            .map(|selection| format!(" ({} selected)", selection.chars().count()))
+            .unwrap_or_default();
+
+        let position = container(
+            row![
+                text(format!("Ln {}, Col {}{selection}", line + 1, column + 1)),
+                text(format!("{} characters", self.len())).style(theme::Text::Disabled),
+            ]
+            .spacing(20),
         )
         .align_x(Horizontal::Right);
 
-        let info = row![path, char_count]
+        let info = row![path, position]
             .width(Length::Fill)
             .padding([5, 10, 5, 10]);
 
-        let content =
-            if let validation::Status::Invalid(validation::Error::Parse { message, errors }) =
-                &self.validation_status
-            {
-                column![
-                    text_editor,
-                    tmp_error_view(message, &errors, &self.content.text()),
-                    info,
-                ]
-            } else {
-                column![text_editor, info]
-            };
+        let editor: Element<Message> = if self.show_outline {
+            let items = self.analysis.as_ref().map_or(&[][..], Analysis::outline);
+
+            row![editor, outline::view(items, cursor_offset(&self.content))].into()
+        } else {
+            editor.into()
+        };
+
+        let mut content = column![editor];
+
+        if let validation::Status::Invalid(validation::Error::Parse { message, errors }) =
+            &self.validation_status
+        {
+            content = content.push(tmp_error_view(message, &errors, &self.content.text()));
+        }
+
+        if !self.completions.is_empty() {
+            content = content.push(self.completions.view());
+        } else if let Some(help) = &self.signature_help {
+            content = content.push(hover::signature_view(help));
+        } else if let Some(hover) = &self.hover {
+            content = content.push(hover::view(hover));
+        }
+
+        let content = content.push(info);
 
         container(content)
             .width(Length::Fill)
@@ -296,6 +919,18 @@ impl Editor {
                     .height(24)
                     .center_y(),
                 checkbox("Auto", self.auto_validate, Message::AutoValidate),
+                tooltip(
+                    pick_list(
+                        &Profile::ALL[..],
+                        Some(self.profile),
+                        Message::ProfileSelected
+                    )
+                    .text_size(14),
+                    "Validate against the capabilities of..",
+                    tooltip::Position::Bottom,
+                )
+                .padding(10)
+                .style(theme::Container::Tooltip),
             ]
             .spacing(10)
             .align_items(Alignment::Center),
@@ -305,6 +940,19 @@ impl Editor {
 
         let file_controls = container(
             row![
+                checkbox("Format on save", self.format_on_save, Message::FormatOnSave),
+                tooltip(
+                    pick_list(
+                        &Indentation::ALL[..],
+                        Some(self.indentation),
+                        Message::IndentationSelected
+                    )
+                    .text_size(14),
+                    "Indent with..",
+                    tooltip::Position::Bottom,
+                )
+                .padding(10)
+                .style(theme::Container::Tooltip),
                 control_button(new_icon, "Create a new shader", Message::New),
                 control_button(open_icon, "Open a shader file", Message::Open),
                 control_button(save_icon, "Save current shader", Message::Save),
@@ -327,6 +975,31 @@ impl Editor {
     }
 }
 
+/// The width of a line in columns, with tabs advancing to the next multiple of [`TAB_WIDTH`].
+fn columns(line: &str) -> usize {
+    line.chars().fold(0, |column, c| {
+        if c == '\t' {
+            (column / TAB_WIDTH + 1) * TAB_WIDTH
+        } else {
+            column + 1
+        }
+    })
+}
+
+/// The byte offset of the cursor in the shader.
+fn cursor_offset(content: &text_editor::Content) -> usize {
+    let (line, column) = content.cursor_position();
+
+    let before = content
+        .lines()
+        .take(line)
+        .map(|line| line.len() + 1)
+        .sum::<usize>();
+    let column = content.line(line).map_or(0, |text| column.min(text.len()));
+
+    before + column
+}
+
 fn control_button<'a>(
     content: impl Into<Element<'a, Message>>,
     label: &'a str,
@@ -0,0 +1,110 @@
+//! Suggestions for the word being typed, listed below the editor as they can't float over it.
+use crate::editor::{cursor_offset, Message};
+use crate::language::{self, Analysis, Completion};
+use crate::theme;
+use crate::widget::text_editor::Content;
+use crate::widget::Element;
+use iced::widget::text_editor::{Action, Edit};
+use iced::widget::{button, column, container, row, text};
+use iced::Length;
+use std::sync::Arc;
+
+/// How many completions are listed at once, around the selected one.
+const MAX_VISIBLE: usize = 8;
+
+#[derive(Default)]
+pub struct Completions {
+    completions: Vec<Completion>,
+    selected: usize,
+    /// The number of characters of the word being completed.
+    typed: usize,
+}
+
+impl Completions {
+    /// The completions of the word before the cursor.
+    pub fn new(content: &Content, analysis: Option<&Analysis>) -> Self {
+        let (line, column) = content.cursor_position();
+        let text = content
+            .line(line)
+            .map(|text| text.to_string())
+            .unwrap_or_default();
+        let before = &text[..column.min(text.len())];
+
+        Self {
+            completions: language::complete(analysis, before, cursor_offset(content)),
+            selected: 0,
+            typed: language::prefix(before).chars().count(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.completions.is_empty()
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, i: usize) {
+        self.selected = i.min(self.completions.len().saturating_sub(1));
+    }
+
+    /// The actions which replace the word being completed with the `i`th completion.
+    pub fn accept(&self, i: usize) -> Vec<Action> {
+        let Some(completion) = self.completions.get(i) else {
+            return vec![];
+        };
+
+        std::iter::repeat(Action::Edit(Edit::Backspace))
+            .take(self.typed)
+            .chain([Action::Edit(Edit::Paste(Arc::new(
+                completion.label.clone(),
+            )))])
+            .collect()
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let first = self
+            .selected
+            .saturating_sub(MAX_VISIBLE / 2)
+            .min(self.completions.len().saturating_sub(MAX_VISIBLE));
+
+        let entries = self
+            .completions
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(MAX_VISIBLE)
+            .map(|(i, completion)| {
+                let entry = row![
+                    text(&completion.label).size(14).width(Length::Fill),
+                    text(&completion.detail)
+                        .size(14)
+                        .style(theme::Text::Disabled),
+                    text(completion.kind)
+                        .size(12)
+                        .style(theme::Text::Disabled)
+                        .width(70),
+                ]
+                .spacing(10);
+
+                button(entry)
+                    .width(Length::Fill)
+                    .padding([2, 8])
+                    .style(if i == self.selected {
+                        theme::Button::SelectedEntry
+                    } else {
+                        theme::Button::Entry
+                    })
+                    .on_press(Message::Complete(i))
+                    .into()
+            })
+            .collect::<Vec<Element<Message>>>();
+
+        container(column(entries).width(Length::Fill))
+            .width(Length::Fill)
+            .padding([5, 10, 5, 10])
+            .style(theme::Container::Controls)
+            .into()
+    }
+}
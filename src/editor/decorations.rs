@@ -0,0 +1,218 @@
+//! What's drawn on the editor's lines besides their text: the current line's highlight under
+//! them & the annotations after them.
+use crate::editor::gutter::{self, Metrics, PADDING};
+use crate::widget::{Element, Renderer};
+use crate::{theme, JETBRAINS_MONO};
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::text::{self, Renderer as _, Text};
+use iced::advanced::widget::{tree, Operation, Tree};
+use iced::advanced::{overlay, renderer, Clipboard, Renderer as _, Shell, Widget};
+use iced::alignment::{Horizontal, Vertical};
+use iced::widget::container;
+use iced::{event, mouse, Color, Event, Length, Point, Rectangle, Size};
+use std::cell::Cell;
+
+const ANNOTATION_SIZE: f32 = 14.0;
+
+/// How many columns are left between the end of a line & its annotation.
+pub const ANNOTATION_GAP: usize = 2;
+
+/// Text drawn after the end of a line of the editor, like the value of a probe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    /// The line, counted from 0.
+    pub line: usize,
+    /// The width of the line in columns, after which the annotation is drawn.
+    pub column: usize,
+    pub text: String,
+}
+
+/// Highlights the `current` line of the editor it wraps & draws `annotations` after its lines,
+/// measuring the editor's metrics into `metrics` when it's laid out. The editor's background has
+/// to be transparent for the highlight to show through.
+pub struct Decorations<'a, Message> {
+    content: Element<'a, Message>,
+    current: usize,
+    annotations: Vec<Annotation>,
+    metrics: &'a Cell<Metrics>,
+}
+
+impl<'a, Message> Decorations<'a, Message> {
+    pub fn new(
+        content: impl Into<Element<'a, Message>>,
+        current: usize,
+        annotations: Vec<Annotation>,
+        metrics: &'a Cell<Metrics>,
+    ) -> Self {
+        Self {
+            content: content.into(),
+            current,
+            annotations,
+            metrics,
+        }
+    }
+}
+
+impl<'a, Message> Widget<Message, Renderer> for Decorations<'a, Message> {
+    fn width(&self) -> Length {
+        self.content.as_widget().width()
+    }
+
+    fn height(&self) -> Length {
+        self.content.as_widget().height()
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::stateless()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.metrics.set(Metrics::measure(renderer));
+
+        self.content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &crate::theme::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let metrics = self.metrics.get();
+
+        renderer.fill_quad(
+            gutter::quad(gutter::line_bounds(bounds, metrics, self.current)),
+            container::StyleSheet::appearance(theme, &theme::Container::CurrentLine)
+                .background
+                .unwrap_or(Color::TRANSPARENT.into()),
+        );
+
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+
+        let color = iced::widget::text::StyleSheet::appearance(theme, theme::Text::Disabled)
+            .color
+            .unwrap_or(style.text_color);
+
+        for annotation in &self.annotations {
+            let position = Point::new(
+                bounds.x
+                    + PADDING
+                    + (annotation.column + ANNOTATION_GAP) as f32 * metrics.character_width,
+                bounds.y + metrics.line_y(annotation.line) + metrics.line_height / 2.0,
+            );
+
+            renderer.fill_text(
+                Text {
+                    content: &annotation.text,
+                    bounds: Size::new(f32::INFINITY, metrics.line_height),
+                    size: ANNOTATION_SIZE.into(),
+                    line_height: text::LineHeight::default(),
+                    font: JETBRAINS_MONO,
+                    horizontal_alignment: Horizontal::Left,
+                    vertical_alignment: Vertical::Center,
+                    shaping: text::Shaping::Basic,
+                },
+                position,
+                color,
+                *viewport,
+            );
+        }
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+    ) -> Option<overlay::Element<'b, Message, Renderer>> {
+        self.content
+            .as_widget_mut()
+            .overlay(&mut tree.children[0], layout, renderer)
+    }
+}
+
+impl<'a, Message: 'a> From<Decorations<'a, Message>> for Element<'a, Message> {
+    fn from(decorations: Decorations<'a, Message>) -> Self {
+        Element::new(decorations)
+    }
+}
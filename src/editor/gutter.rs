@@ -0,0 +1,244 @@
+//! The line numbers beside the editor, marking the current line & lines with errors.
+//!
+//! The gutter is laid out in the same scrollable as the editor, so its rows have to match the
+//! editor's lines exactly: the editor's text is never wrapped & each row is as tall as a line of
+//! the editor's font, as [`Metrics`] measures it.
+use crate::widget::{Element, Renderer};
+use crate::{theme, JETBRAINS_MONO};
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::text::{self, Paragraph as _, Renderer as _, Text};
+use iced::advanced::widget::Tree;
+use iced::advanced::{renderer, Renderer as _, Widget};
+use iced::alignment::{Horizontal, Vertical};
+use iced::widget::container;
+use iced::{mouse, Color, Length, Point, Rectangle, Size};
+use std::cell::Cell;
+
+/// The padding around the editor's text.
+pub const PADDING: f32 = 10.0;
+
+const NUMBER_SIZE: f32 = 14.0;
+const MARKER_WIDTH: f32 = 12.0;
+
+/// Measured to find the advance of a character, which is the same for each in a monospace font.
+const SAMPLE: &str = "0123456789";
+
+/// The size of the editor's lines & characters, from its font at the renderer's text size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metrics {
+    pub text_size: f32,
+    pub line_height: f32,
+    pub character_width: f32,
+}
+
+impl Metrics {
+    /// Measures the editor's font with the settings it's drawn with: iced's default text size &
+    /// line height.
+    pub fn measure(renderer: &Renderer) -> Self {
+        let size = renderer.default_size();
+        let line_height = text::LineHeight::default();
+
+        let paragraph = <Renderer as text::Renderer>::Paragraph::with_text(Text {
+            content: SAMPLE,
+            bounds: Size::INFINITY,
+            size,
+            line_height,
+            font: JETBRAINS_MONO,
+            horizontal_alignment: Horizontal::Left,
+            vertical_alignment: Vertical::Top,
+            shaping: text::Shaping::Basic,
+        });
+
+        Self {
+            text_size: size.0,
+            line_height: line_height.to_absolute(size).0,
+            character_width: paragraph.min_width() / SAMPLE.len() as f32,
+        }
+    }
+
+    /// The top of `line`, counted from 0, below the top of the editor.
+    pub fn line_y(&self, line: usize) -> f32 {
+        PADDING + line as f32 * self.line_height
+    }
+}
+
+impl Default for Metrics {
+    /// What the metrics are expected to be until they're measured, from iced's default text size
+    /// & JetBrains Mono's glyphs being 0.6 em wide.
+    fn default() -> Self {
+        Self {
+            text_size: 16.0,
+            line_height: 16.0 * 1.3,
+            character_width: 16.0 * 0.6,
+        }
+    }
+}
+
+/// The width of the gutter of a shader with `lines` lines.
+pub fn width(lines: usize, metrics: Metrics) -> f32 {
+    let digits = lines.max(1).ilog10() + 1;
+    let digit_width = metrics.character_width * NUMBER_SIZE / metrics.text_size;
+
+    MARKER_WIDTH + digits.max(2) as f32 * digit_width + PADDING
+}
+
+struct Gutter<'a> {
+    lines: usize,
+    current: usize,
+    errors: Vec<usize>,
+    metrics: &'a Cell<Metrics>,
+}
+
+/// A row for each of the `lines` of the shader, highlighting the `current` line & marking the
+/// lines in `errors`, all counted from 0. The editor's metrics are measured into `metrics` when
+/// it's laid out.
+pub fn view<'a, Message: 'a>(
+    lines: usize,
+    current: usize,
+    errors: &[usize],
+    metrics: &'a Cell<Metrics>,
+) -> Element<'a, Message> {
+    Element::new(Gutter {
+        lines,
+        current,
+        errors: errors.to_vec(),
+        metrics,
+    })
+}
+
+impl<'a, Message> Widget<Message, Renderer> for Gutter<'a> {
+    fn width(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn height(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn layout(
+        &self,
+        _tree: &mut Tree,
+        renderer: &Renderer,
+        _limits: &layout::Limits,
+    ) -> layout::Node {
+        let metrics = Metrics::measure(renderer);
+        self.metrics.set(metrics);
+
+        layout::Node::new(Size::new(
+            width(self.lines, metrics),
+            metrics.line_y(self.lines) + PADDING,
+        ))
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &crate::theme::Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let metrics = self.metrics.get();
+
+        let background = |style: theme::Container| {
+            container::StyleSheet::appearance(theme, &style)
+                .background
+                .unwrap_or(Color::TRANSPARENT.into())
+        };
+        let color = |style: theme::Text| {
+            iced::widget::text::StyleSheet::appearance(theme, style)
+                .color
+                .unwrap_or(Color::WHITE)
+        };
+
+        renderer.fill_quad(quad(bounds), background(theme::Container::Gutter));
+        renderer.fill_quad(
+            quad(line_bounds(bounds, metrics, self.current)),
+            background(theme::Container::CurrentLine),
+        );
+
+        // only the rows in view are drawn, as the gutter is as tall as the whole shader
+        let first = ((viewport.y - bounds.y - PADDING) / metrics.line_height).max(0.0) as usize;
+        let count = (viewport.height / metrics.line_height).ceil() as usize + 1;
+
+        for line in (first..self.lines).take(count) {
+            let has_error = self.errors.contains(&line);
+            let row = line_bounds(bounds, metrics, line);
+
+            let number_color = if has_error {
+                color(theme::Text::Error)
+            } else if line == self.current {
+                color(theme::Text::Primary)
+            } else {
+                color(theme::Text::Disabled)
+            };
+
+            if has_error {
+                fill_text(
+                    renderer,
+                    "●",
+                    Point::new(row.x, row.center_y()),
+                    Horizontal::Left,
+                    color(theme::Text::Error),
+                    *viewport,
+                );
+            }
+
+            fill_text(
+                renderer,
+                &(line + 1).to_string(),
+                Point::new(row.x + row.width - PADDING, row.center_y()),
+                Horizontal::Right,
+                number_color,
+                *viewport,
+            );
+        }
+    }
+}
+
+/// The row of `line` across the `bounds` of the gutter or editor.
+pub fn line_bounds(bounds: Rectangle, metrics: Metrics, line: usize) -> Rectangle {
+    Rectangle {
+        x: bounds.x,
+        y: bounds.y + metrics.line_y(line),
+        width: bounds.width,
+        height: metrics.line_height,
+    }
+}
+
+pub fn quad(bounds: Rectangle) -> renderer::Quad {
+    renderer::Quad {
+        bounds,
+        border_radius: Default::default(),
+        border_width: 0.0,
+        border_color: Color::TRANSPARENT,
+    }
+}
+
+fn fill_text(
+    renderer: &mut Renderer,
+    content: &str,
+    position: Point,
+    alignment: Horizontal,
+    color: Color,
+    clip_bounds: Rectangle,
+) {
+    renderer.fill_text(
+        Text {
+            content,
+            bounds: Size::INFINITY,
+            size: NUMBER_SIZE.into(),
+            line_height: text::LineHeight::default(),
+            font: JETBRAINS_MONO,
+            horizontal_alignment: alignment,
+            vertical_alignment: Vertical::Center,
+            shaping: text::Shaping::Advanced,
+        },
+        position,
+        color,
+        clip_bounds,
+    );
+}
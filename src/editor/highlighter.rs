@@ -1,3 +1,4 @@
+use crate::theme::Theme;
 use iced::advanced::text::highlighter::Format;
 use iced::{Color, Font};
 use once_cell::sync::Lazy;
@@ -15,37 +16,58 @@ static THEMES: Lazy<syntect::highlighting::ThemeSet> =
 
 const LINES_PER_SNAPSHOT: usize = 50;
 
+/// The themes the editor can switch between, which are only the dark ones as the editor's
+/// background is always dark.
+pub const DARK_THEMES: [iced::highlighter::Theme; 4] = [
+    iced::highlighter::Theme::Base16Mocha,
+    iced::highlighter::Theme::Base16Ocean,
+    iced::highlighter::Theme::Base16Eighties,
+    iced::highlighter::Theme::SolarizedDark,
+];
+
 pub struct Highlighter {
     syntax: &'static parsing::SyntaxReference,
     highlighter: syntect::highlighting::Highlighter<'static>,
+    theme: iced::highlighter::Theme,
     //TODO wut
     caches: Vec<(parsing::ParseState, parsing::ScopeStack)>,
     current_line: usize,
     errors: Vec<Range<usize>>,
+    references: Vec<(usize, Range<usize>)>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Settings {
     pub theme: iced::highlighter::Theme,
     pub errors: Vec<Range<usize>>,
+    /// The uses of the symbol at the cursor, as the line they're on & their range in it.
+    pub references: Vec<(usize, Range<usize>)>,
 }
 
-pub struct Highlight(StyleModifier);
+#[derive(Clone, Copy)]
+pub enum Highlight {
+    Syntax(StyleModifier),
+    /// A use of the symbol at the cursor.
+    Reference,
+}
 
 impl Highlight {
-    pub fn color(&self) -> Option<Color> {
-        self.0
-            .foreground
-            .map(|color| Color::from_rgba8(color.r, color.g, color.b, color.a as f32 / 255.0))
+    pub fn color(&self, theme: &Theme) -> Option<Color> {
+        match self {
+            Highlight::Syntax(style) => style
+                .foreground
+                .map(|color| Color::from_rgba8(color.r, color.g, color.b, color.a as f32 / 255.0)),
+            Highlight::Reference => Some(theme.palette().accent_secondary),
+        }
     }
 
     pub fn font(&self) -> Option<Font> {
         None
     }
 
-    pub fn to_format(&self) -> Format<Font> {
+    pub fn to_format(&self, theme: &Theme) -> Format<Font> {
         Format {
-            color: self.color(),
+            color: self.color(theme),
             font: self.font(),
         }
     }
@@ -63,23 +85,28 @@ impl iced::advanced::text::Highlighter for Highlighter {
             .find_syntax_by_extension("wgsl")
             .unwrap_or_else(|| WGSL_SYNTAX.find_syntax_plain_text());
 
-        let highlighter =
-            syntect::highlighting::Highlighter::new(&THEMES.themes["base16-mocha.dark"]);
-
         let parser = parsing::ParseState::new(syntax);
         let stack = parsing::ScopeStack::new();
 
         Self {
             syntax,
-            highlighter,
+            highlighter: highlighter(settings.theme),
+            theme: settings.theme,
             caches: vec![(parser, stack)],
             current_line: 0,
             errors: settings.errors.clone(),
+            references: settings.references.clone(),
         }
     }
 
     fn update(&mut self, new_settings: &Self::Settings) {
+        if new_settings.theme != self.theme {
+            self.highlighter = highlighter(new_settings.theme);
+            self.theme = new_settings.theme;
+        }
+
         self.errors = new_settings.errors.clone();
+        self.references = new_settings.references.clone();
         self.current_line = 0;
     }
 
@@ -113,6 +140,13 @@ impl iced::advanced::text::Highlighter for Highlighter {
             self.caches.push((parser.clone(), stack.clone()));
         }
 
+        let references = self
+            .references
+            .iter()
+            .filter(|(line, _)| *line == self.current_line)
+            .map(|(_, range)| range.clone())
+            .collect::<Vec<_>>();
+
         self.current_line += 1;
 
         let (parser, stack) = self.caches.last_mut().expect("Caches must not be empty");
@@ -122,28 +156,28 @@ impl iced::advanced::text::Highlighter for Highlighter {
 
         let highlighter = &self.highlighter;
 
-        Box::new(
-            ScopeRangeIterator {
-                ops,
-                line_length: line.len(),
-                index: 0,
-                last_str_index: 0,
+        let highlights = ScopeRangeIterator {
+            ops,
+            line_length: line.len(),
+            index: 0,
+            last_str_index: 0,
+        }
+        .filter_map(move |(index, range, scope)| {
+            let _ = stack.apply(&scope);
+
+            if range.is_empty() {
+                None
+            } else {
+                let modifier = highlighter.style_mod_for_stack(&stack.scopes);
+
+                Some((
+                    range,
+                    Highlight::Syntax(highlighter.style_mod_for_stack(&stack.scopes)),
+                ))
             }
-            .filter_map(move |(index, range, scope)| {
-                let _ = stack.apply(&scope);
-
-                if range.is_empty() {
-                    None
-                } else {
-                    let modifier = highlighter.style_mod_for_stack(&stack.scopes);
-
-                    Some((
-                        range,
-                        Highlight(highlighter.style_mod_for_stack(&stack.scopes)),
-                    ))
-                }
-            }),
-        )
+        });
+
+        Box::new(with_references(highlights, references))
     }
 
     fn current_line(&self) -> usize {
@@ -185,3 +219,47 @@ impl Iterator for ScopeRangeIterator {
         Some((self.index, range, op))
     }
 }
+
+/// Splits the `highlights` of a line around the `references` in it, which are highlighted instead.
+fn with_references<'a>(
+    highlights: impl Iterator<Item = (Range<usize>, Highlight)> + 'a,
+    references: Vec<Range<usize>>,
+) -> impl Iterator<Item = (Range<usize>, Highlight)> + 'a {
+    highlights.flat_map(move |(range, highlight)| {
+        let mut parts = vec![];
+        let mut start = range.start;
+
+        for reference in references
+            .iter()
+            .filter(|reference| reference.start < range.end && reference.end > range.start)
+        {
+            if reference.start > start {
+                parts.push((start..reference.start, highlight));
+            }
+
+            parts.push((
+                reference.start.max(range.start)..reference.end.min(range.end),
+                Highlight::Reference,
+            ));
+            start = reference.end;
+        }
+
+        if start < range.end {
+            parts.push((start..range.end, highlight));
+        }
+
+        parts
+    })
+}
+
+fn highlighter(theme: iced::highlighter::Theme) -> syntect::highlighting::Highlighter<'static> {
+    let key = match theme {
+        iced::highlighter::Theme::SolarizedDark => "Solarized (dark)",
+        iced::highlighter::Theme::Base16Mocha => "base16-mocha.dark",
+        iced::highlighter::Theme::Base16Ocean => "base16-ocean.dark",
+        iced::highlighter::Theme::Base16Eighties => "base16-eighties.dark",
+        iced::highlighter::Theme::InspiredGitHub => "InspiredGitHub",
+    };
+
+    syntect::highlighting::Highlighter::new(&THEMES.themes[key])
+}
@@ -0,0 +1,56 @@
+//! The documentation of what's at the cursor & the signature of the call it's in, shown below the
+//! editor.
+use crate::editor::Message;
+use crate::language::{Hover, SignatureHelp};
+use crate::theme;
+use crate::widget::Element;
+use iced::widget::{column, container, row, text};
+use iced::Length;
+
+const TEXT_SIZE: f32 = 14.0;
+
+pub fn view(hover: &Hover) -> Element<Message> {
+    let mut content = column![text(&hover.signature).size(TEXT_SIZE)].spacing(5);
+
+    if let Some(description) = &hover.description {
+        content = content.push(
+            text(description)
+                .size(TEXT_SIZE)
+                .style(theme::Text::Disabled),
+        );
+    }
+
+    panel(content)
+}
+
+/// The signature of the call, with the parameter being typed highlighted.
+pub fn signature_view(help: &SignatureHelp) -> Element<Message> {
+    let signature = &help.signature;
+
+    let content: Element<Message> = match help.active_parameter() {
+        Some(active) => row![
+            text(&signature[..active.start])
+                .size(TEXT_SIZE)
+                .style(theme::Text::Disabled),
+            text(&signature[active.clone()]).size(TEXT_SIZE),
+            text(&signature[active.end..])
+                .size(TEXT_SIZE)
+                .style(theme::Text::Disabled),
+        ]
+        .into(),
+        None => text(signature)
+            .size(TEXT_SIZE)
+            .style(theme::Text::Disabled)
+            .into(),
+    };
+
+    panel(content)
+}
+
+fn panel<'a>(content: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    container(content)
+        .width(Length::Fill)
+        .padding([5, 20, 5, 20])
+        .style(theme::Container::Controls)
+        .into()
+}
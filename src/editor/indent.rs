@@ -0,0 +1,503 @@
+//! Smart indentation & auto-closing brackets.
+//!
+//! Each is performed as a sequence of the editor's own actions, so the cursor & selection move
+//! along with the text as if it had been typed.
+use crate::preferences::Indentation;
+use crate::widget::text_editor::Content;
+use iced::widget::text_editor::{Action, Edit, Motion};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// The actions to perform for an `edit`, which is the edit itself unless it's one that
+/// indentation or bracket matching changes.
+pub fn edit(content: &Content, edit: Edit, indentation: Indentation) -> Vec<Action> {
+    let (line, column) = content.cursor_position();
+    let text = line_text(content, line);
+    let (before, after) = text.split_at(column.min(text.len()));
+    let next = after.chars().next();
+    let selection = content
+        .selection()
+        .filter(|selection| !selection.is_empty());
+
+    match edit {
+        // keep the line's indentation, adding a level after an opening bracket
+        Edit::Enter => {
+            let indent = leading_whitespace(&text);
+            let opener = before.trim_end().chars().last().and_then(closer);
+
+            let Some(close) = opener else {
+                return with_paste(vec![Action::Edit(Edit::Enter)], indent);
+            };
+
+            let mut actions = with_paste(
+                vec![Action::Edit(Edit::Enter)],
+                &format!("{indent}{}", indentation.unit()),
+            );
+
+            // move the closing bracket onto a line of its own
+            if after.trim_start().starts_with(close) {
+                actions = with_paste(actions, &format!("\n{indent}"));
+                actions.extend([Action::Move(Motion::Up), Action::Move(Motion::End)]);
+            }
+
+            actions
+        }
+        // type over a closing bracket that was auto-closed
+        Edit::Insert(c) if selection.is_none() && is_closer(c) && next == Some(c) => {
+            vec![Action::Move(Motion::Right)]
+        }
+        // dedent a closing brace typed at the start of a line
+        Edit::Insert('}') if selection.is_none() && before.trim().is_empty() => {
+            let outdent = outdent_len(before, indentation);
+
+            std::iter::repeat(Action::Edit(Edit::Backspace))
+                .take(outdent)
+                .chain([Action::Edit(Edit::Insert('}'))])
+                .collect()
+        }
+        Edit::Insert(c) => match (closer(c), selection) {
+            // surround the selection with the brackets
+            (Some(close), Some(selection)) => {
+                vec![Action::Edit(Edit::Paste(Arc::new(format!(
+                    "{c}{selection}{close}"
+                ))))]
+            }
+            (Some(close), None) if can_close_before(next) => {
+                vec![
+                    Action::Edit(Edit::Insert(c)),
+                    Action::Edit(Edit::Insert(close)),
+                    Action::Move(Motion::Left),
+                ]
+            }
+            _ => vec![Action::Edit(edit)],
+        },
+        // delete both brackets of an empty pair
+        Edit::Backspace
+            if selection.is_none()
+                && before
+                    .chars()
+                    .last()
+                    .and_then(closer)
+                    .is_some_and(|close| Some(close) == next) =>
+        {
+            vec![Action::Edit(Edit::Backspace), Action::Edit(Edit::Delete)]
+        }
+        edit => vec![Action::Edit(edit)],
+    }
+}
+
+/// Indents the selected lines, or inserts a level of indentation at the cursor.
+pub fn indent(content: &Content, indentation: Indentation) -> Vec<Action> {
+    let Some(lines) = selected_lines(content) else {
+        let (line, column) = content.cursor_position();
+        let text = line_text(content, line);
+
+        // align to the next tab stop
+        let unit = if indentation.spaces {
+            let width = text[..column.min(text.len())].chars().count();
+
+            " ".repeat(indentation.width - width % indentation.width)
+        } else {
+            indentation.unit()
+        };
+
+        return vec![Action::Edit(Edit::Paste(Arc::new(unit)))];
+    };
+
+    let unit = indentation.unit();
+
+    for_each_line(lines.clone(), |_| with_paste(vec![], &unit))
+        .into_iter()
+        .chain(select(lines))
+        .collect()
+}
+
+/// Removes a level of indentation from the selected lines, or the cursor's line.
+pub fn outdent(content: &Content, indentation: Indentation) -> Vec<Action> {
+    let remove = |line| {
+        let outdent = outdent_len(&line_text(content, line), indentation);
+
+        vec![Action::Edit(Edit::Delete); outdent]
+    };
+
+    let Some(lines) = selected_lines(content) else {
+        let (line, column) = content.cursor_position();
+        let text = line_text(content, line);
+        let outdent = outdent_len(&text, indentation);
+
+        // keep the cursor on the same character
+        let column = text[..column.min(text.len())]
+            .chars()
+            .count()
+            .saturating_sub(outdent);
+
+        return for_each_line(line..=line, remove)
+            .into_iter()
+            .chain([Action::Move(Motion::Home)])
+            .chain(vec![Action::Move(Motion::Right); column])
+            .collect();
+    };
+
+    for_each_line(lines.clone(), remove)
+        .into_iter()
+        .chain(select(lines))
+        .collect()
+}
+
+/// The text of the `line`th line.
+fn line_text(content: &Content, line: usize) -> String {
+    content
+        .line(line)
+        .map(|text| text.to_string())
+        .unwrap_or_default()
+}
+
+fn leading_whitespace(text: &str) -> &str {
+    &text[..text.len() - text.trim_start().len()]
+}
+
+/// How many characters of whitespace at the start of `text` make up a level of indentation.
+fn outdent_len(text: &str, indentation: Indentation) -> usize {
+    if text.starts_with('\t') {
+        1
+    } else {
+        text.chars()
+            .take(indentation.width)
+            .take_while(|c| *c == ' ')
+            .count()
+    }
+}
+
+fn closer(opener: char) -> Option<char> {
+    PAIRS
+        .iter()
+        .find(|(open, _)| *open == opener)
+        .map(|(_, close)| *close)
+}
+
+fn is_closer(c: char) -> bool {
+    PAIRS.iter().any(|(_, close)| *close == c)
+}
+
+/// Whether a bracket opened before `next` should be closed, which is only when it doesn't start
+/// an expression that's already there.
+fn can_close_before(next: Option<char>) -> bool {
+    match next {
+        None => true,
+        Some(next) => next.is_whitespace() || is_closer(next) || matches!(next, ';' | ','),
+    }
+}
+
+fn with_paste(mut actions: Vec<Action>, text: &str) -> Vec<Action> {
+    if !text.is_empty() {
+        actions.push(Action::Edit(Edit::Paste(Arc::new(text.to_string()))));
+    }
+
+    actions
+}
+
+/// The lines the selection covers, if there is one.
+///
+/// The editor only exposes the selected text & the cursor, which is at whichever end of the
+/// selection it was last moved to. The selection ends at the cursor if it's the text right before
+/// it, & starts there otherwise.
+fn selected_lines(content: &Content) -> Option<RangeInclusive<usize>> {
+    let selection = content
+        .selection()
+        .filter(|selection| !selection.is_empty())?;
+    let (line, column) = content.cursor_position();
+    let span = selection.matches('\n').count();
+    // a selection ending at the start of a line doesn't cover it
+    let past_end = usize::from(selection.ends_with('\n'));
+
+    let text = content.text();
+    let offset = content
+        .lines()
+        .take(line)
+        .map(|line| line.len() + 1)
+        .sum::<usize>()
+        + column.min(line_text(content, line).len());
+    let before = offset
+        .checked_sub(selection.len())
+        .and_then(|start| text.get(start..offset));
+
+    if before == Some(selection.as_str()) {
+        Some(line - span..=line - past_end)
+    } else {
+        Some(line..=line + span - past_end)
+    }
+}
+
+/// Moves to the start of each of the `lines` in turn, performing the actions of `edit` there.
+fn for_each_line(lines: RangeInclusive<usize>, edit: impl Fn(usize) -> Vec<Action>) -> Vec<Action> {
+    let mut actions = vec![Action::Move(Motion::DocumentStart)];
+    actions.extend(vec![Action::Move(Motion::Down); *lines.start()]);
+
+    for line in lines.clone() {
+        actions.push(Action::Move(Motion::Home));
+        actions.extend(edit(line));
+
+        if line < *lines.end() {
+            actions.push(Action::Move(Motion::Down));
+        }
+    }
+
+    actions
+}
+
+/// Selects the whole of the `lines`, from the last one.
+fn select(lines: RangeInclusive<usize>) -> Vec<Action> {
+    let count = lines.end() - lines.start();
+
+    std::iter::repeat(Action::Move(Motion::Up))
+        .take(count)
+        .chain([Action::Move(Motion::Home)])
+        .chain(vec![Action::Select(Motion::Down); count])
+        .chain([Action::Select(Motion::End)])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPACES: Indentation = Indentation {
+        width: 4,
+        spaces: true,
+    };
+    const TABS: Indentation = Indentation {
+        width: 4,
+        spaces: false,
+    };
+
+    /// The content of `text` with the cursor at `line` & `column`.
+    fn content(text: &str, line: usize, column: usize) -> Content {
+        let mut content = Content::with_text(text);
+
+        perform(
+            &mut content,
+            std::iter::once(Action::Move(Motion::DocumentStart))
+                .chain(vec![Action::Move(Motion::Down); line])
+                .chain([Action::Move(Motion::Home)])
+                .chain(vec![Action::Move(Motion::Right); column])
+                .collect(),
+        );
+
+        content
+    }
+
+    fn perform(content: &mut Content, actions: Vec<Action>) {
+        for action in actions {
+            content.perform(action);
+        }
+    }
+
+    fn lines(content: &Content) -> Vec<String> {
+        content.lines().map(|line| line.to_string()).collect()
+    }
+
+    fn type_edit(content: &mut Content, edit: Edit, indentation: Indentation) {
+        let actions = super::edit(content, edit, indentation);
+        perform(content, actions);
+    }
+
+    #[test]
+    fn enter_keeps_the_indentation() {
+        let mut content = content("    let x = 1;", 0, 14);
+
+        type_edit(&mut content, Edit::Enter, SPACES);
+
+        assert_eq!(lines(&content), ["    let x = 1;", "    "]);
+        assert_eq!(content.cursor_position(), (1, 4));
+    }
+
+    #[test]
+    fn enter_between_braces_indents_with_spaces() {
+        let mut content = content("fn f() {}", 0, 8);
+
+        type_edit(&mut content, Edit::Enter, SPACES);
+
+        assert_eq!(lines(&content), ["fn f() {", "    ", "}"]);
+        assert_eq!(content.cursor_position(), (1, 4));
+    }
+
+    #[test]
+    fn enter_between_braces_indents_with_tabs() {
+        let mut content = content("fn f() {}", 0, 8);
+
+        type_edit(&mut content, Edit::Enter, TABS);
+
+        assert_eq!(lines(&content), ["fn f() {", "\t", "}"]);
+        assert_eq!(content.cursor_position(), (1, 1));
+    }
+
+    #[test]
+    fn closing_brace_dedents_spaces() {
+        let mut content = content("{\n        ", 1, 8);
+
+        type_edit(&mut content, Edit::Insert('}'), SPACES);
+
+        assert_eq!(lines(&content), ["{", "    }"]);
+    }
+
+    #[test]
+    fn closing_brace_dedents_tabs() {
+        let mut content = content("{\n\t\t", 1, 2);
+
+        type_edit(&mut content, Edit::Insert('}'), TABS);
+
+        assert_eq!(lines(&content), ["{", "\t}"]);
+    }
+
+    #[test]
+    fn closing_brace_after_code_is_not_dedented() {
+        let mut content = content("    let x = f(", 0, 14);
+
+        type_edit(&mut content, Edit::Insert('}'), SPACES);
+
+        assert_eq!(lines(&content), ["    let x = f(}"]);
+    }
+
+    #[test]
+    fn brackets_are_closed() {
+        let mut content = content("", 0, 0);
+
+        type_edit(&mut content, Edit::Insert('('), SPACES);
+
+        assert_eq!(lines(&content), ["()"]);
+        assert_eq!(content.cursor_position(), (0, 1));
+    }
+
+    #[test]
+    fn brackets_are_closed_before_closing_brackets() {
+        let mut content = content("f()", 0, 2);
+
+        type_edit(&mut content, Edit::Insert('['), SPACES);
+
+        assert_eq!(lines(&content), ["f([])"]);
+        assert_eq!(content.cursor_position(), (0, 3));
+    }
+
+    #[test]
+    fn brackets_are_not_closed_before_expressions() {
+        let mut content = content("x + 1", 0, 0);
+
+        type_edit(&mut content, Edit::Insert('('), SPACES);
+
+        assert_eq!(lines(&content), ["(x + 1"]);
+    }
+
+    #[test]
+    fn closing_brackets_are_typed_over() {
+        let mut content = content("f()", 0, 2);
+
+        type_edit(&mut content, Edit::Insert(')'), SPACES);
+
+        assert_eq!(lines(&content), ["f()"]);
+        assert_eq!(content.cursor_position(), (0, 3));
+    }
+
+    #[test]
+    fn backspace_deletes_empty_pairs() {
+        let mut content = content("f()", 0, 2);
+
+        type_edit(&mut content, Edit::Backspace, SPACES);
+
+        assert_eq!(lines(&content), ["f"]);
+    }
+
+    #[test]
+    fn brackets_surround_the_selection() {
+        let mut content = content("a + b", 0, 0);
+        content.perform(Action::Select(Motion::End));
+
+        type_edit(&mut content, Edit::Insert('('), SPACES);
+
+        assert_eq!(lines(&content), ["(a + b)"]);
+    }
+
+    #[test]
+    fn indent_aligns_to_the_next_tab_stop() {
+        let mut spaces = content("abx", 0, 2);
+        let mut tabs = content("abx", 0, 2);
+
+        perform(&mut spaces, indent(&spaces, SPACES));
+        perform(&mut tabs, indent(&tabs, TABS));
+
+        assert_eq!(lines(&spaces), ["ab  x"]);
+        assert_eq!(lines(&tabs), ["ab\tx"]);
+    }
+
+    #[test]
+    fn indent_selected_lines() {
+        let mut content = content("a\nb\nc", 0, 0);
+        perform(
+            &mut content,
+            vec![Action::Select(Motion::Down), Action::Select(Motion::End)],
+        );
+
+        perform(&mut content, indent(&content, SPACES));
+
+        assert_eq!(lines(&content), ["    a", "    b", "c"]);
+        assert_eq!(content.selection().as_deref(), Some("    a\n    b"));
+    }
+
+    #[test]
+    fn indent_lines_selected_upwards_with_tabs() {
+        let mut content = content("a\nb\nc", 1, 1);
+        content.perform(Action::Select(Motion::Up));
+
+        perform(&mut content, indent(&content, TABS));
+
+        assert_eq!(lines(&content), ["\ta", "\tb", "c"]);
+    }
+
+    #[test]
+    fn indent_lines_selected_upwards_over_a_duplicated_line() {
+        // the text before the cursor on its line is also the end of the selection
+        let mut content = content("x\nfoo\nfoo", 2, 1);
+        content.perform(Action::Select(Motion::Up));
+
+        perform(&mut content, indent(&content, SPACES));
+
+        assert_eq!(lines(&content), ["x", "    foo", "    foo"]);
+    }
+
+    #[test]
+    fn indent_whole_lines_selected_upwards() {
+        let mut content = content("a\nb\nc", 2, 0);
+        perform(
+            &mut content,
+            vec![Action::Select(Motion::Up), Action::Select(Motion::Up)],
+        );
+
+        perform(&mut content, indent(&content, SPACES));
+
+        assert_eq!(lines(&content), ["    a", "    b", "c"]);
+    }
+
+    #[test]
+    fn outdent_selected_lines() {
+        let mut content = content("\ta\n    b\nc", 0, 0);
+        perform(
+            &mut content,
+            vec![Action::Select(Motion::Down), Action::Select(Motion::End)],
+        );
+
+        perform(&mut content, outdent(&content, SPACES));
+
+        assert_eq!(lines(&content), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn outdent_keeps_the_cursor_on_its_character() {
+        let mut content = content("        x", 0, 8);
+
+        perform(&mut content, outdent(&content, SPACES));
+
+        assert_eq!(lines(&content), ["    x"]);
+        assert_eq!(content.cursor_position(), (0, 4));
+    }
+}
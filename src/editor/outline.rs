@@ -0,0 +1,58 @@
+//! The symbols the shader declares, listed beside the editor to jump to.
+use crate::editor::Message;
+use crate::language::Item;
+use crate::theme;
+use crate::widget::Element;
+use iced::widget::{button, column, container, row, scrollable, text};
+use iced::Length;
+
+const WIDTH: f32 = 220.0;
+const TEXT_SIZE: f32 = 14.0;
+
+/// The `items` of the outline, highlighting the one the cursor is in, which is the last declared
+/// before the `cursor`.
+pub fn view(items: &[Item], cursor: usize) -> Element<Message> {
+    let current = items.iter().rposition(|item| item.offset <= cursor);
+
+    let entries = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let entry = row![
+                text(item.kind)
+                    .size(12)
+                    .style(theme::Text::Disabled)
+                    .width(45),
+                text(&item.name).size(TEXT_SIZE),
+            ]
+            .spacing(5);
+
+            button(entry)
+                .width(Length::Fill)
+                .padding([2, 8])
+                .style(if Some(i) == current {
+                    theme::Button::SelectedEntry
+                } else {
+                    theme::Button::Entry
+                })
+                .on_press(Message::GoTo(item.offset))
+                .into()
+        })
+        .collect::<Vec<Element<Message>>>();
+
+    let content: Element<Message> = if entries.is_empty() {
+        text("No symbols")
+            .size(TEXT_SIZE)
+            .style(theme::Text::Disabled)
+            .into()
+    } else {
+        scrollable(column(entries).width(Length::Fill)).into()
+    };
+
+    container(content)
+        .width(WIDTH)
+        .height(Length::Fill)
+        .padding([5, 5, 5, 5])
+        .style(theme::Container::Controls)
+        .into()
+}
@@ -0,0 +1,386 @@
+//! Formats WGSL with consistent indentation, spacing & line breaks.
+//!
+//! Only the whitespace between tokens is changed, so comments & directives are kept as they are
+//! & a shader that doesn't parse is still formatted as well as it can be. Blocks are indented a
+//! level each, statements & struct members are put on their own lines, & lines broken inside
+//! parentheses are indented a level further. Other line breaks are kept, with at most one blank
+//! line in a row.
+//!
+//! ```
+//! let formatted = halo::format::format("fn f()->f32{return 1.0*2.0;}", "    ");
+//!
+//! assert_eq!(formatted, "fn f() -> f32 {\n    return 1.0 * 2.0;\n}\n");
+//! ```
+
+/// Formats a shader, indenting each level with `indent`.
+pub fn format(shader: &str, indent: &str) -> String {
+    let mut formatter = Formatter {
+        output: String::with_capacity(shader.len()),
+        indent,
+        brackets: vec![],
+        previous: None,
+        is_case: false,
+    };
+
+    for (newlines, token) in tokenize(shader) {
+        formatter.write(token, newlines);
+    }
+
+    let mut output = formatter.output;
+
+    if !output.is_empty() {
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Operators of more than one character, longest first.
+const OPERATORS: [&str; 21] = [
+    "<<=", ">>=", "->", "<=", ">=", "==", "!=", "&&", "||", "<<", ">>", "+=", "-=", "*=", "/=",
+    "%=", "&=", "|=", "^=", "++", "--",
+];
+
+/// Keywords which can come before an expression, so aren't the end of one.
+const KEYWORDS: [&str; 9] = [
+    "return", "case", "if", "else", "for", "while", "switch", "let", "var",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    /// An identifier, keyword or number.
+    Word(&'a str),
+    Punctuation(&'a str),
+    LineComment(&'a str),
+    BlockComment(&'a str),
+}
+
+impl<'a> Token<'a> {
+    fn text(&self) -> &'a str {
+        match self {
+            Token::Word(text)
+            | Token::Punctuation(text)
+            | Token::LineComment(text)
+            | Token::BlockComment(text) => text,
+        }
+    }
+
+    fn is_comment(&self) -> bool {
+        matches!(self, Token::LineComment(_) | Token::BlockComment(_))
+    }
+}
+
+/// What a token does, where that depends on the tokens around it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Role {
+    Plain,
+    /// A prefix operator, like the `-` of `-x`.
+    Unary,
+    /// The `<` of a type's template list, like `vec2<f32>`.
+    TemplateStart,
+    /// The `>` of a type's template list, or the `>>` closing two.
+    TemplateEnd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bracket {
+    Brace,
+    Parenthesis,
+    Square,
+    Template,
+}
+
+struct Formatter<'a> {
+    output: String,
+    indent: &'a str,
+    /// The brackets opened & not yet closed, innermost last.
+    brackets: Vec<Bracket>,
+    previous: Option<(Token<'a>, Role)>,
+    /// Whether the selectors of a `case` are being written, whose commas don't break lines.
+    is_case: bool,
+}
+
+impl<'a> Formatter<'a> {
+    /// Writes a token found after `newlines` line breaks in the shader.
+    fn write(&mut self, token: Token<'a>, newlines: usize) {
+        let role = self.role(token);
+        let breaks = self
+            .previous
+            .map_or(0, |previous| self.line_breaks(previous.0, token, newlines));
+
+        self.close(token, role);
+
+        if breaks > 0 {
+            self.output.push_str(&"\n".repeat(breaks));
+            self.output.push_str(&self.indent.repeat(self.level()));
+        } else if self
+            .previous
+            .is_some_and(|previous| needs_space(previous, (token, role)))
+        {
+            self.output.push(' ');
+        }
+
+        self.output.push_str(token.text());
+        self.open(token, role);
+
+        match token {
+            Token::Word("case") => self.is_case = true,
+            Token::Punctuation(":") => self.is_case = false,
+            _ => {}
+        }
+
+        self.previous = Some((token, role));
+    }
+
+    fn role(&self, token: Token) -> Role {
+        let previous = self.previous;
+        let is_after_type = previous.is_some_and(|(previous, _)| is_templated(previous));
+
+        match token {
+            Token::Punctuation("<") if is_after_type => Role::TemplateStart,
+            Token::Punctuation(">" | ">>") if self.brackets.last() == Some(&Bracket::Template) => {
+                Role::TemplateEnd
+            }
+            Token::Punctuation("!" | "~") => Role::Unary,
+            Token::Punctuation("-" | "&" | "*") if !previous.is_some_and(ends_expression) => {
+                Role::Unary
+            }
+            _ => Role::Plain,
+        }
+    }
+
+    /// The number of line breaks to write between the `previous` token & the next `token`, which
+    /// came after `newlines` line breaks in the shader.
+    fn line_breaks(&self, previous: Token, token: Token, newlines: usize) -> usize {
+        let is_in_block = matches!(self.brackets.last(), None | Some(Bracket::Brace));
+
+        // comments at the end of a line stay there
+        let is_trailing_comment = token.is_comment() && newlines == 0;
+
+        let is_block_end = token == Token::Punctuation("}") && previous != Token::Punctuation("{");
+
+        let is_broken = is_block_end
+            || match previous {
+                Token::LineComment(_) => true,
+                Token::Punctuation("{") => token != Token::Punctuation("}"),
+                Token::Punctuation("}") => !matches!(
+                    token,
+                    Token::Word("else") | Token::Punctuation(";" | "," | ")")
+                ),
+                Token::Punctuation(";") => is_in_block,
+                Token::Punctuation(",") => is_in_block && !self.is_case,
+                _ => false,
+            };
+
+        let is_joined = match token {
+            Token::Punctuation("{") => !previous.is_comment(),
+            Token::Word("else") => previous == Token::Punctuation("}"),
+            _ => false,
+        };
+
+        let breaks = if is_broken && !is_trailing_comment {
+            newlines.clamp(1, 2)
+        } else if is_joined {
+            0
+        } else {
+            newlines.min(2)
+        };
+
+        // no blank lines at the start or end of a block
+        if previous == Token::Punctuation("{") || token == Token::Punctuation("}") {
+            breaks.min(1)
+        } else {
+            breaks
+        }
+    }
+
+    /// The indentation level of a line starting inside the open brackets, which is a level for
+    /// each block & another for a line broken inside parentheses.
+    fn level(&self) -> usize {
+        let blocks = self
+            .brackets
+            .iter()
+            .filter(|bracket| **bracket == Bracket::Brace)
+            .count();
+        let is_continued = !matches!(self.brackets.last(), None | Some(Bracket::Brace));
+
+        blocks + usize::from(is_continued)
+    }
+
+    fn open(&mut self, token: Token, role: Role) {
+        let bracket = match (token, role) {
+            (_, Role::TemplateStart) => Bracket::Template,
+            (Token::Punctuation("{"), _) => Bracket::Brace,
+            (Token::Punctuation("("), _) => Bracket::Parenthesis,
+            (Token::Punctuation("["), _) => Bracket::Square,
+            _ => return,
+        };
+
+        self.brackets.push(bracket);
+    }
+
+    /// Closes the innermost bracket `token` closes, & any left open inside it.
+    fn close(&mut self, token: Token, role: Role) {
+        let (bracket, count) = match (token, role) {
+            (Token::Punctuation(">>"), Role::TemplateEnd) => (Bracket::Template, 2),
+            (_, Role::TemplateEnd) => (Bracket::Template, 1),
+            (Token::Punctuation("}"), _) => (Bracket::Brace, 1),
+            (Token::Punctuation(")"), _) => (Bracket::Parenthesis, 1),
+            (Token::Punctuation("]"), _) => (Bracket::Square, 1),
+            _ => return,
+        };
+
+        for _ in 0..count {
+            if let Some(i) = self.brackets.iter().rposition(|open| *open == bracket) {
+                self.brackets.truncate(i);
+            }
+        }
+    }
+}
+
+/// Whether a space goes between two tokens on the same line.
+fn needs_space(previous: (Token, Role), next: (Token, Role)) -> bool {
+    match (previous, next) {
+        ((previous, _), (next, _)) if previous.is_comment() || next.is_comment() => true,
+        ((_, Role::Unary | Role::TemplateStart), _) => false,
+        (_, (_, Role::TemplateStart | Role::TemplateEnd)) => false,
+        (_, (Token::Punctuation("," | ";" | ":" | "." | ")" | "]" | "++" | "--"), _)) => false,
+        ((Token::Punctuation("(" | "[" | "." | "@"), _), _) => false,
+        ((Token::Punctuation("{"), _), (Token::Punctuation("}"), _)) => false,
+        (_, (Token::Punctuation("(" | "["), _)) => !ends_expression(previous),
+        _ => true,
+    }
+}
+
+/// Whether a token can end an expression, so the operator after it is binary.
+fn ends_expression((token, role): (Token, Role)) -> bool {
+    match token {
+        _ if role == Role::TemplateEnd => true,
+        Token::Word(word) => !KEYWORDS.contains(&word),
+        Token::Punctuation(punctuation) => matches!(punctuation, ")" | "]" | "++" | "--"),
+        _ => false,
+    }
+}
+
+/// Whether a `<` after the token starts a template list rather than comparing.
+fn is_templated(token: Token) -> bool {
+    let Token::Word(word) = token else {
+        return false;
+    };
+
+    let is_vector = word.len() == 4 && word.starts_with("vec");
+    let is_matrix = word.len() == 6 && word.starts_with("mat");
+
+    is_vector
+        || is_matrix
+        || word.starts_with("texture_")
+        || matches!(
+            word,
+            "array" | "ptr" | "atomic" | "var" | "binding_array" | "bitcast"
+        )
+}
+
+/// The tokens of a shader, each with the number of line breaks before it.
+fn tokenize(shader: &str) -> Vec<(usize, Token<'_>)> {
+    let mut tokens = vec![];
+    let mut newlines = 0;
+    let mut rest = shader;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            if c == '\n' {
+                newlines += 1;
+            }
+
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+
+        let (len, token) = if rest.starts_with("//") {
+            let len = rest.find('\n').unwrap_or(rest.len());
+
+            (len, Token::LineComment(rest[..len].trim_end()))
+        } else if rest.starts_with("/*") {
+            let len = block_comment_len(rest);
+
+            (len, Token::BlockComment(&rest[..len]))
+        } else if c.is_alphanumeric() || c == '_' || is_number(rest) {
+            let len = word_len(rest);
+
+            (len, Token::Word(&rest[..len]))
+        } else {
+            let len = OPERATORS
+                .iter()
+                .find(|operator| rest.starts_with(**operator))
+                .map_or(c.len_utf8(), |operator| operator.len());
+
+            (len, Token::Punctuation(&rest[..len]))
+        };
+
+        tokens.push((newlines, token));
+        newlines = 0;
+        rest = &rest[len..];
+    }
+
+    tokens
+}
+
+/// Whether `text` starts with a number, including those like `.5`.
+fn is_number(text: &str) -> bool {
+    let mut chars = text.chars();
+
+    match chars.next() {
+        Some('.') => chars.next().is_some_and(|c| c.is_ascii_digit()),
+        Some(c) => c.is_ascii_digit(),
+        None => false,
+    }
+}
+
+/// The length of the identifier, keyword or number `text` starts with.
+fn word_len(text: &str) -> usize {
+    let is_number = is_number(text);
+    let is_hex = text.starts_with("0x") || text.starts_with("0X");
+    let mut previous = ' ';
+
+    text.char_indices()
+        .find(|(_, c)| {
+            // the sign of an exponent, like in `1e-3` or `0x1p+4`
+            let is_exponent_sign = is_number
+                && matches!(c, '+' | '-')
+                && if is_hex {
+                    matches!(previous, 'p' | 'P')
+                } else {
+                    matches!(previous, 'e' | 'E')
+                };
+            let is_word = c.is_alphanumeric() || *c == '_' || (is_number && *c == '.');
+
+            previous = *c;
+
+            !is_word && !is_exponent_sign
+        })
+        .map_or(text.len(), |(i, _)| i)
+}
+
+/// The length of the block comment `text` starts with, which may contain others.
+fn block_comment_len(text: &str) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+
+    while i < text.len() {
+        if text[i..].starts_with("/*") {
+            depth += 1;
+            i += 2;
+        } else if text[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += text[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    text.len()
+}
@@ -0,0 +1,531 @@
+//! Keyboard shortcuts for every editor & viewer command, intercepted before the focused widget
+//! sees them. Those editing the shader only run while the editor is focused, so the viewer gets
+//! keys like tab otherwise.
+//!
+//! Bindings can be changed in `keybindings.json` beside the preferences, which maps commands to
+//! the keys bound to them, replacing their defaults:
+//!
+//! ```json
+//! {
+//!     "validate": ["ctrl+enter", "f5"],
+//!     "toggle_stats": []
+//! }
+//! ```
+//!
+//! `cmd` is Command on macOS & Ctrl elsewhere.
+use iced::keyboard::{KeyCode, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+const PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/keybindings.json");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    New,
+    Open,
+    Save,
+    Validate,
+    Undo,
+    Redo,
+    Search,
+    Indent,
+    Outdent,
+    OpenMesh,
+    OpenAudio,
+    ToggleInspector,
+    CopySample,
+    ToggleRangeCheck,
+    ToggleStats,
+    ExportStats,
+    ToggleAutoValidate,
+    SwitchTheme,
+    GoToDefinition,
+    ToggleOutline,
+    FormatDocument,
+    ToggleFormatOnSave,
+    CommandPalette,
+}
+
+impl Command {
+    /// Every command, in the order the command palette lists them.
+    pub const ALL: [Command; 23] = [
+        Command::New,
+        Command::Open,
+        Command::Save,
+        Command::FormatDocument,
+        Command::ToggleFormatOnSave,
+        Command::Validate,
+        Command::ToggleAutoValidate,
+        Command::Undo,
+        Command::Redo,
+        Command::Search,
+        Command::Indent,
+        Command::Outdent,
+        Command::GoToDefinition,
+        Command::ToggleOutline,
+        Command::SwitchTheme,
+        Command::OpenMesh,
+        Command::OpenAudio,
+        Command::ToggleInspector,
+        Command::CopySample,
+        Command::ToggleRangeCheck,
+        Command::ToggleStats,
+        Command::ExportStats,
+        Command::CommandPalette,
+    ];
+
+    /// Whether the command edits the shader or moves through it, so its keys only run it while
+    /// the editor is focused & reach the viewer otherwise.
+    pub fn is_editing(&self) -> bool {
+        matches!(
+            self,
+            Command::Undo
+                | Command::Redo
+                | Command::Search
+                | Command::Indent
+                | Command::Outdent
+                | Command::GoToDefinition
+        )
+    }
+
+    /// The bindings of each command unless they're changed in the keybindings file.
+    fn defaults() -> Vec<(Command, &'static str)> {
+        vec![
+            (Command::New, "cmd+n"),
+            (Command::Open, "cmd+o"),
+            (Command::Save, "cmd+s"),
+            (Command::Validate, "ctrl+enter"),
+            (Command::Undo, "cmd+z"),
+            (Command::Redo, "cmd+y"),
+            (Command::Redo, "cmd+shift+z"),
+            (Command::Search, "cmd+f"),
+            (Command::Indent, "tab"),
+            (Command::Outdent, "shift+tab"),
+            (Command::GoToDefinition, "f12"),
+            (Command::ToggleOutline, "cmd+shift+o"),
+            (Command::FormatDocument, "alt+shift+f"),
+            (Command::OpenMesh, "cmd+shift+m"),
+            (Command::OpenAudio, "cmd+shift+a"),
+            (Command::ToggleInspector, "cmd+i"),
+            (Command::CopySample, "cmd+shift+c"),
+            (Command::ToggleRangeCheck, "cmd+shift+r"),
+            (Command::ToggleStats, "f3"),
+            (Command::ExportStats, "cmd+shift+e"),
+            (Command::CommandPalette, "cmd+shift+p"),
+        ]
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Command::New => "New shader",
+            Command::Open => "Open shader",
+            Command::Save => "Save shader",
+            Command::Validate => "Validate",
+            Command::Undo => "Undo",
+            Command::Redo => "Redo",
+            Command::Search => "Search",
+            Command::Indent => "Indent",
+            Command::Outdent => "Outdent",
+            Command::OpenMesh => "Open mesh",
+            Command::OpenAudio => "Open audio",
+            Command::ToggleInspector => "Toggle pixel inspector",
+            Command::CopySample => "Copy inspected pixel",
+            Command::ToggleRangeCheck => "Toggle range check",
+            Command::ToggleStats => "Toggle frame stats",
+            Command::ExportStats => "Export frame stats",
+            Command::ToggleAutoValidate => "Toggle auto-validate",
+            Command::SwitchTheme => "Switch editor theme",
+            Command::GoToDefinition => "Go to definition",
+            Command::ToggleOutline => "Toggle outline",
+            Command::FormatDocument => "Format document",
+            Command::ToggleFormatOnSave => "Toggle format on save",
+            Command::CommandPalette => "Command palette",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// A key pressed with exactly a set of modifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Binding {
+    key: KeyCode,
+    modifiers: Modifiers,
+}
+
+impl Binding {
+    /// Parses a binding like `ctrl+shift+s`.
+    pub fn parse(binding: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidBinding(binding.to_string());
+
+        let mut parts = binding.split('+').map(str::trim).collect::<Vec<_>>();
+        let key = parts.pop().ok_or_else(invalid)?;
+
+        let modifiers = parts
+            .into_iter()
+            .try_fold(Modifiers::empty(), |modifiers, part| {
+                let modifier = match part.to_lowercase().as_str() {
+                    "shift" => Modifiers::SHIFT,
+                    "ctrl" | "control" => Modifiers::CTRL,
+                    "alt" | "option" => Modifiers::ALT,
+                    "logo" | "super" | "meta" => Modifiers::LOGO,
+                    "cmd" | "command" => Modifiers::COMMAND,
+                    _ => return Err(invalid()),
+                };
+
+                Ok(modifiers | modifier)
+            })?;
+
+        let key = KEYS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, key)| *key)
+            .ok_or_else(invalid)?;
+
+        Ok(Self { key, modifiers })
+    }
+}
+
+impl Display for Binding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let modifiers = [
+            (Modifiers::CTRL, "Ctrl"),
+            (Modifiers::ALT, "Alt"),
+            (Modifiers::LOGO, "Logo"),
+            (Modifiers::SHIFT, "Shift"),
+        ];
+
+        for (modifier, name) in modifiers {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+
+        let key = KEYS
+            .iter()
+            .find(|(_, key)| *key == self.key)
+            .map_or("?", |(name, _)| name);
+
+        write!(f, "{}", key.to_uppercase())
+    }
+}
+
+/// Two or more commands bound to the same keys. The first is the one that runs.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub binding: Binding,
+    pub commands: Vec<Command>,
+}
+
+impl Conflict {
+    /// Whether the binding doesn't run `command`, because another command comes before it.
+    pub fn shadows(&self, command: Command) -> bool {
+        self.commands
+            .iter()
+            .skip(1)
+            .any(|shadowed| *shadowed == command)
+    }
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let commands = self
+            .commands
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" & ");
+
+        write!(f, "{} is bound to {commands}", self.binding)?;
+
+        match self.commands.first() {
+            Some(first) => write!(f, ", so only {first} runs"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    /// Searched in order, so rebound commands come before the defaults they override.
+    bindings: Vec<(Binding, Command)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(BTreeMap::new()).expect("Default key bindings must be valid")
+    }
+}
+
+impl Keymap {
+    /// The default bindings, with those of each command in `rebound` replaced.
+    pub fn new(rebound: BTreeMap<Command, Vec<String>>) -> Result<Self, Error> {
+        let mut bindings = vec![];
+
+        for (command, keys) in &rebound {
+            for key in keys {
+                bindings.push((Binding::parse(key)?, *command));
+            }
+        }
+
+        for (command, key) in Command::defaults() {
+            if !rebound.contains_key(&command) {
+                bindings.push((Binding::parse(key)?, command));
+            }
+        }
+
+        Ok(Self { bindings })
+    }
+
+    /// The command bound to a key press, if any.
+    pub fn command(&self, key: KeyCode, modifiers: Modifiers) -> Option<Command> {
+        let pressed = Binding { key, modifiers };
+
+        self.bindings
+            .iter()
+            .find(|(binding, _)| *binding == pressed)
+            .map(|(_, command)| *command)
+    }
+
+    /// The keys bound to a command.
+    pub fn bindings(&self, command: Command) -> impl Iterator<Item = Binding> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, bound)| *bound == command)
+            .map(|(binding, _)| *binding)
+    }
+
+    /// Every binding shared by more than one command.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts: Vec<Conflict> = vec![];
+
+        for (binding, command) in &self.bindings {
+            match conflicts.iter_mut().find(|c| c.binding == *binding) {
+                Some(conflict) if !conflict.commands.contains(command) => {
+                    conflict.commands.push(*command);
+                }
+                Some(_) => {}
+                None => conflicts.push(Conflict {
+                    binding: *binding,
+                    commands: vec![*command],
+                }),
+            }
+        }
+
+        conflicts.retain(|conflict| conflict.commands.len() > 1);
+        conflicts
+    }
+}
+
+/// Loads the keymap with the bindings of the keybindings file, or the defaults without one.
+pub async fn load() -> Result<Keymap, Error> {
+    let file = match tokio::fs::read_to_string(PATH).await {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(Keymap::default());
+        }
+        Err(error) => return Err(Error::Io(error.to_string())),
+    };
+
+    let rebound = serde_json::from_str(&file).map_err(|e| Error::Deserialize(e.to_string()))?;
+
+    Keymap::new(rebound)
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum Error {
+    #[error("Couldn't read the keybindings: {0}")]
+    Io(String),
+    #[error("Invalid keybindings: {0}")]
+    Deserialize(String),
+    #[error("Invalid key binding `{0}`")]
+    InvalidBinding(String),
+}
+
+/// The name of each key that can be bound.
+const KEYS: [(&str, KeyCode); 77] = [
+    ("a", KeyCode::A),
+    ("b", KeyCode::B),
+    ("c", KeyCode::C),
+    ("d", KeyCode::D),
+    ("e", KeyCode::E),
+    ("f", KeyCode::F),
+    ("g", KeyCode::G),
+    ("h", KeyCode::H),
+    ("i", KeyCode::I),
+    ("j", KeyCode::J),
+    ("k", KeyCode::K),
+    ("l", KeyCode::L),
+    ("m", KeyCode::M),
+    ("n", KeyCode::N),
+    ("o", KeyCode::O),
+    ("p", KeyCode::P),
+    ("q", KeyCode::Q),
+    ("r", KeyCode::R),
+    ("s", KeyCode::S),
+    ("t", KeyCode::T),
+    ("u", KeyCode::U),
+    ("v", KeyCode::V),
+    ("w", KeyCode::W),
+    ("x", KeyCode::X),
+    ("y", KeyCode::Y),
+    ("z", KeyCode::Z),
+    ("0", KeyCode::Key0),
+    ("1", KeyCode::Key1),
+    ("2", KeyCode::Key2),
+    ("3", KeyCode::Key3),
+    ("4", KeyCode::Key4),
+    ("5", KeyCode::Key5),
+    ("6", KeyCode::Key6),
+    ("7", KeyCode::Key7),
+    ("8", KeyCode::Key8),
+    ("9", KeyCode::Key9),
+    ("f1", KeyCode::F1),
+    ("f2", KeyCode::F2),
+    ("f3", KeyCode::F3),
+    ("f4", KeyCode::F4),
+    ("f5", KeyCode::F5),
+    ("f6", KeyCode::F6),
+    ("f7", KeyCode::F7),
+    ("f8", KeyCode::F8),
+    ("f9", KeyCode::F9),
+    ("f10", KeyCode::F10),
+    ("f11", KeyCode::F11),
+    ("f12", KeyCode::F12),
+    ("enter", KeyCode::Enter),
+    ("tab", KeyCode::Tab),
+    ("space", KeyCode::Space),
+    ("escape", KeyCode::Escape),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("minus", KeyCode::Minus),
+    ("equals", KeyCode::Equals),
+    ("comma", KeyCode::Comma),
+    ("period", KeyCode::Period),
+    ("slash", KeyCode::Slash),
+    ("backslash", KeyCode::Backslash),
+    ("semicolon", KeyCode::Semicolon),
+    ("apostrophe", KeyCode::Apostrophe),
+    ("grave", KeyCode::Grave),
+    ("lbracket", KeyCode::LBracket),
+    ("rbracket", KeyCode::RBracket),
+    ("plus", KeyCode::Plus),
+    ("numpadenter", KeyCode::NumpadEnter),
+    ("numpadadd", KeyCode::NumpadAdd),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rebound(bindings: &[(Command, &[&str])]) -> Keymap {
+        Keymap::new(
+            bindings
+                .iter()
+                .map(|(command, keys)| (*command, keys.iter().map(ToString::to_string).collect()))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_modifiers_and_keys() {
+        assert_eq!(
+            Binding::parse("ctrl+shift+s").unwrap(),
+            Binding {
+                key: KeyCode::S,
+                modifiers: Modifiers::CTRL | Modifiers::SHIFT,
+            }
+        );
+        assert_eq!(
+            Binding::parse(" Alt + Enter ").unwrap(),
+            Binding {
+                key: KeyCode::Enter,
+                modifiers: Modifiers::ALT,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_modifiers_and_keys() {
+        for binding in ["hyper+s", "ctrl+nope", "ctrl+", ""] {
+            assert!(
+                matches!(Binding::parse(binding), Err(Error::InvalidBinding(_))),
+                "`{binding}` parsed"
+            );
+        }
+    }
+
+    #[test]
+    fn bindings_round_trip() {
+        let defaults = Command::defaults()
+            .into_iter()
+            .map(|(_, binding)| Binding::parse(binding).unwrap());
+        let keys = KEYS.iter().map(|(_, key)| Binding {
+            key: *key,
+            modifiers: Modifiers::CTRL | Modifiers::ALT | Modifiers::SHIFT,
+        });
+
+        for binding in defaults.chain(keys) {
+            assert_eq!(Binding::parse(&binding.to_string()).unwrap(), binding);
+        }
+    }
+
+    #[test]
+    fn defaults_dont_conflict() {
+        assert!(Keymap::default().conflicts().is_empty());
+    }
+
+    #[test]
+    fn rebinding_replaces_the_defaults() {
+        let keymap = rebound(&[(Command::Validate, &["f5"])]);
+
+        assert_eq!(
+            keymap.command(KeyCode::F5, Modifiers::empty()),
+            Some(Command::Validate)
+        );
+        assert_eq!(keymap.command(KeyCode::Enter, Modifiers::CTRL), None);
+    }
+
+    #[test]
+    fn rebinding_to_bound_keys_conflicts() {
+        let keymap = rebound(&[(Command::Validate, &["f3"])]);
+        let conflicts = keymap.conflicts();
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            conflicts[0].commands,
+            [Command::Validate, Command::ToggleStats]
+        );
+        assert!(conflicts[0].shadows(Command::ToggleStats));
+        assert!(!conflicts[0].shadows(Command::Validate));
+        assert_eq!(
+            conflicts[0].to_string(),
+            "F3 is bound to Validate & Toggle frame stats, so only Validate runs"
+        );
+        assert_eq!(
+            keymap.command(KeyCode::F3, Modifiers::empty()),
+            Some(Command::Validate)
+        );
+    }
+
+    #[test]
+    fn binding_a_command_twice_to_the_same_keys_doesnt_conflict() {
+        let keymap = rebound(&[(Command::Save, &["ctrl+s", "ctrl+s"])]);
+
+        assert!(keymap.conflicts().is_empty());
+    }
+}
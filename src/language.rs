@@ -0,0 +1,61 @@
+//! Language features for editing WGSL, found from the module naga parses from a shader.
+mod builtins;
+mod completion;
+mod hover;
+mod navigation;
+mod symbols;
+
+pub use completion::{complete, prefix, Completion};
+pub use hover::{hover, signature_help, Hover, SignatureHelp};
+pub use navigation::{definition, references, Item};
+pub use symbols::Kind;
+use symbols::Symbol;
+
+use crate::validation;
+use crate::viewer::probe;
+
+/// What's known about a shader from the last time it was parsed.
+#[derive(Debug)]
+pub struct Analysis {
+    /// The shader that was parsed, which the spans of its symbols are in.
+    shader: String,
+    /// The shader with its probes erased, which the spans of the module are in.
+    erased: probe::Erased,
+    module: naga::Module,
+    /// The types of the module's expressions, if it's valid.
+    info: Option<naga::valid::ModuleInfo>,
+    symbols: Vec<Symbol>,
+    outline: Vec<Item>,
+}
+
+impl Analysis {
+    /// Parses & validates `shader`, which only needs to parse to be analysed.
+    pub fn new(shader: &str) -> Result<Self, validation::Error> {
+        let (module, erased) = validation::parse(shader)?;
+
+        // validated with every capability, as only the types it finds are needed
+        let info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .ok();
+
+        let symbols = symbols::find(&module, info.as_ref(), &erased);
+        let outline = navigation::outline(shader, &module, &symbols);
+
+        Ok(Self {
+            shader: shader.to_string(),
+            erased,
+            module,
+            info,
+            symbols,
+            outline,
+        })
+    }
+
+    /// The symbols declared at the top level of the shader, in the order they're declared.
+    pub fn outline(&self) -> &[Item] {
+        &self.outline
+    }
+}
@@ -0,0 +1,579 @@
+//! WGSL's keywords, types & builtin functions.
+//!
+//! Generic signatures follow the WGSL spec: `T` is a scalar or vector of floats unless said
+//! otherwise, & a vector `T`'s functions apply to each component.
+
+pub const KEYWORDS: [&str; 24] = [
+    "alias",
+    "break",
+    "case",
+    "const",
+    "const_assert",
+    "continue",
+    "continuing",
+    "default",
+    "discard",
+    "else",
+    "enable",
+    "false",
+    "fn",
+    "for",
+    "if",
+    "let",
+    "loop",
+    "override",
+    "return",
+    "struct",
+    "switch",
+    "true",
+    "var",
+    "while",
+];
+
+pub const TYPES: [&str; 38] = [
+    "bool",
+    "f16",
+    "f32",
+    "i32",
+    "u32",
+    "vec2",
+    "vec3",
+    "vec4",
+    "vec2f",
+    "vec3f",
+    "vec4f",
+    "vec2i",
+    "vec3i",
+    "vec4i",
+    "vec2u",
+    "vec3u",
+    "vec4u",
+    "mat2x2",
+    "mat2x3",
+    "mat2x4",
+    "mat3x2",
+    "mat3x3",
+    "mat3x4",
+    "mat4x2",
+    "mat4x3",
+    "mat4x4",
+    "mat2x2f",
+    "mat3x3f",
+    "mat4x4f",
+    "array",
+    "atomic",
+    "ptr",
+    "sampler",
+    "sampler_comparison",
+    "texture_2d",
+    "texture_3d",
+    "texture_cube",
+    "texture_storage_2d",
+];
+
+/// A builtin function.
+#[derive(Debug, Clone, Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub signature: &'static str,
+    pub description: &'static str,
+}
+
+const fn builtin(
+    name: &'static str,
+    signature: &'static str,
+    description: &'static str,
+) -> Builtin {
+    Builtin {
+        name,
+        signature,
+        description,
+    }
+}
+
+/// The builtin function or type constructor called `name`, if there's one.
+pub fn find(name: &str) -> Option<&'static Builtin> {
+    BUILTINS
+        .iter()
+        .chain(&CONSTRUCTORS)
+        .find(|builtin| builtin.name == name)
+}
+
+pub const BUILTINS: [Builtin; 79] = [
+    // numeric
+    builtin(
+        "abs",
+        "fn abs(e: T) -> T",
+        "The absolute value of `e`, where `T` is any numeric scalar or vector.",
+    ),
+    builtin("acos", "fn acos(e: T) -> T", "The arc cosine of `e`, in radians."),
+    builtin(
+        "acosh",
+        "fn acosh(e: T) -> T",
+        "The inverse hyperbolic cosine of `e`.",
+    ),
+    builtin("asin", "fn asin(e: T) -> T", "The arc sine of `e`, in radians."),
+    builtin(
+        "asinh",
+        "fn asinh(e: T) -> T",
+        "The inverse hyperbolic sine of `e`.",
+    ),
+    builtin("atan", "fn atan(e: T) -> T", "The arc tangent of `e`, in radians."),
+    builtin(
+        "atanh",
+        "fn atanh(e: T) -> T",
+        "The inverse hyperbolic tangent of `e`.",
+    ),
+    builtin(
+        "atan2",
+        "fn atan2(y: T, x: T) -> T",
+        "The angle of the point (`x`, `y`) from the positive x axis, from -π to π.",
+    ),
+    builtin("ceil", "fn ceil(e: T) -> T", "Rounds `e` up to the next integer."),
+    builtin(
+        "clamp",
+        "fn clamp(e: T, low: T, high: T) -> T",
+        "Restricts `e` to between `low` & `high`, where `T` is any numeric scalar or vector.",
+    ),
+    builtin("cos", "fn cos(e: T) -> T", "The cosine of `e` radians."),
+    builtin("cosh", "fn cosh(e: T) -> T", "The hyperbolic cosine of `e`."),
+    builtin(
+        "countOneBits",
+        "fn countOneBits(e: T) -> T",
+        "The number of set bits in `e`, where `T` is an integer scalar or vector.",
+    ),
+    builtin(
+        "cross",
+        "fn cross(a: vec3<f32>, b: vec3<f32>) -> vec3<f32>",
+        "The cross product of `a` & `b`, perpendicular to both.",
+    ),
+    builtin(
+        "degrees",
+        "fn degrees(e: T) -> T",
+        "Converts `e` from radians to degrees.",
+    ),
+    builtin(
+        "determinant",
+        "fn determinant(e: matCxC<f32>) -> f32",
+        "The determinant of the square matrix `e`.",
+    ),
+    builtin(
+        "distance",
+        "fn distance(a: T, b: T) -> f32",
+        "The distance between the points `a` & `b`.",
+    ),
+    builtin(
+        "dot",
+        "fn dot(a: vecN<T>, b: vecN<T>) -> T",
+        "The dot product of `a` & `b`.",
+    ),
+    builtin("exp", "fn exp(e: T) -> T", "The natural exponent of `e`, e^`e`."),
+    builtin("exp2", "fn exp2(e: T) -> T", "2 raised to the power of `e`."),
+    builtin(
+        "faceForward",
+        "fn faceForward(e1: T, e2: T, e3: T) -> T",
+        "`e1` if `dot(e2, e3)` is negative, otherwise `-e1`.",
+    ),
+    builtin(
+        "floor",
+        "fn floor(e: T) -> T",
+        "Rounds `e` down to the previous integer.",
+    ),
+    builtin(
+        "fma",
+        "fn fma(a: T, b: T, c: T) -> T",
+        "`a * b + c`, computed as a single operation.",
+    ),
+    builtin(
+        "fract",
+        "fn fract(e: T) -> T",
+        "The fractional part of `e`, `e - floor(e)`.",
+    ),
+    builtin(
+        "inverseSqrt",
+        "fn inverseSqrt(e: T) -> T",
+        "The reciprocal of the square root of `e`.",
+    ),
+    builtin(
+        "ldexp",
+        "fn ldexp(e1: T, e2: I) -> T",
+        "`e1 * 2^e2`, where `I` is an integer scalar or vector.",
+    ),
+    builtin("length", "fn length(e: T) -> f32", "The length of the vector `e`."),
+    builtin("log", "fn log(e: T) -> T", "The natural logarithm of `e`."),
+    builtin("log2", "fn log2(e: T) -> T", "The base 2 logarithm of `e`."),
+    builtin(
+        "max",
+        "fn max(a: T, b: T) -> T",
+        "The larger of `a` & `b`, where `T` is any numeric scalar or vector.",
+    ),
+    builtin(
+        "min",
+        "fn min(a: T, b: T) -> T",
+        "The smaller of `a` & `b`, where `T` is any numeric scalar or vector.",
+    ),
+    builtin(
+        "mix",
+        "fn mix(a: T, b: T, t: T) -> T",
+        "Linearly interpolates from `a` to `b` by `t`, `a * (1 - t) + b * t`.",
+    ),
+    builtin(
+        "modf",
+        "fn modf(e: T) -> __modf_result",
+        "Splits `e` into its fractional part `fract` & its whole part `whole`.",
+    ),
+    builtin(
+        "normalize",
+        "fn normalize(e: vecN<f32>) -> vecN<f32>",
+        "The vector in the direction of `e` with a length of 1.",
+    ),
+    builtin("pow", "fn pow(e1: T, e2: T) -> T", "`e1` raised to the power of `e2`."),
+    builtin(
+        "radians",
+        "fn radians(e: T) -> T",
+        "Converts `e` from degrees to radians.",
+    ),
+    builtin(
+        "reflect",
+        "fn reflect(e1: T, e2: T) -> T",
+        "Reflects the direction `e1` about the surface normal `e2`.",
+    ),
+    builtin(
+        "refract",
+        "fn refract(e1: vecN<f32>, e2: vecN<f32>, e3: f32) -> vecN<f32>",
+        "Refracts the direction `e1` through the surface normal `e2` with the ratio of indices of refraction `e3`.",
+    ),
+    builtin(
+        "reverseBits",
+        "fn reverseBits(e: T) -> T",
+        "Reverses the bits of `e`, where `T` is an integer scalar or vector.",
+    ),
+    builtin(
+        "round",
+        "fn round(e: T) -> T",
+        "Rounds `e` to the nearest integer, with halves rounded to the even integer.",
+    ),
+    builtin(
+        "saturate",
+        "fn saturate(e: T) -> T",
+        "Clamps `e` to between 0 & 1.",
+    ),
+    builtin(
+        "sign",
+        "fn sign(e: T) -> T",
+        "-1, 0 or 1 depending on the sign of `e`, where `T` is a signed numeric scalar or vector.",
+    ),
+    builtin("sin", "fn sin(e: T) -> T", "The sine of `e` radians."),
+    builtin("sinh", "fn sinh(e: T) -> T", "The hyperbolic sine of `e`."),
+    builtin(
+        "smoothstep",
+        "fn smoothstep(low: T, high: T, x: T) -> T",
+        "Smooth Hermite interpolation from 0 to 1 as `x` goes from `low` to `high`.",
+    ),
+    builtin("sqrt", "fn sqrt(e: T) -> T", "The square root of `e`."),
+    builtin(
+        "step",
+        "fn step(edge: T, x: T) -> T",
+        "1 if `edge` is less than or equal to `x`, otherwise 0.",
+    ),
+    builtin("tan", "fn tan(e: T) -> T", "The tangent of `e` radians."),
+    builtin("tanh", "fn tanh(e: T) -> T", "The hyperbolic tangent of `e`."),
+    builtin(
+        "transpose",
+        "fn transpose(e: matRxC<f32>) -> matCxR<f32>",
+        "The transpose of the matrix `e`.",
+    ),
+    builtin(
+        "trunc",
+        "fn trunc(e: T) -> T",
+        "Rounds `e` towards 0 to the nearest integer.",
+    ),
+    // logical
+    builtin(
+        "all",
+        "fn all(e: vecN<bool>) -> bool",
+        "Whether every component of `e` is true.",
+    ),
+    builtin(
+        "any",
+        "fn any(e: vecN<bool>) -> bool",
+        "Whether any component of `e` is true.",
+    ),
+    builtin(
+        "select",
+        "fn select(f: T, t: T, cond: bool) -> T",
+        "`t` if `cond` is true, otherwise `f`. A vector `cond` selects each component.",
+    ),
+    builtin(
+        "arrayLength",
+        "fn arrayLength(p: ptr<storage, array<E>>) -> u32",
+        "The number of elements in the runtime-sized array `p` points to.",
+    ),
+    // derivatives
+    builtin(
+        "dpdx",
+        "fn dpdx(e: T) -> T",
+        "The partial derivative of `e` along x in screen space.",
+    ),
+    builtin(
+        "dpdy",
+        "fn dpdy(e: T) -> T",
+        "The partial derivative of `e` along y in screen space.",
+    ),
+    builtin(
+        "fwidth",
+        "fn fwidth(e: T) -> T",
+        "`abs(dpdx(e)) + abs(dpdy(e))`, how fast `e` changes across a pixel.",
+    ),
+    // textures
+    builtin(
+        "textureDimensions",
+        "fn textureDimensions(t: texture_2d<T>, level?: u32) -> vec2<u32>",
+        "The size of the texture `t` in texels, at mip `level` if given.",
+    ),
+    builtin(
+        "textureLoad",
+        "fn textureLoad(t: texture_2d<T>, coords: vec2<i32>, level: i32) -> vec4<T>",
+        "The texel of `t` at `coords` & mip `level`, without filtering.",
+    ),
+    builtin(
+        "textureSample",
+        "fn textureSample(t: texture_2d<f32>, s: sampler, coords: vec2<f32>) -> vec4<f32>",
+        "Samples `t` with `s` at the normalized `coords`.",
+    ),
+    builtin(
+        "textureSampleLevel",
+        "fn textureSampleLevel(t: texture_2d<f32>, s: sampler, coords: vec2<f32>, level: f32) -> vec4<f32>",
+        "Samples `t` with `s` at `coords` from mip `level`, which works outside of uniform control flow.",
+    ),
+    builtin(
+        "textureSampleBias",
+        "fn textureSampleBias(t: texture_2d<f32>, s: sampler, coords: vec2<f32>, bias: f32) -> vec4<f32>",
+        "Samples `t` with `s` at `coords`, with `bias` added to the mip level.",
+    ),
+    builtin(
+        "textureSampleGrad",
+        "fn textureSampleGrad(t: texture_2d<f32>, s: sampler, coords: vec2<f32>, ddx: vec2<f32>, ddy: vec2<f32>) -> vec4<f32>",
+        "Samples `t` with `s` at `coords`, choosing the mip level from the gradients `ddx` & `ddy`.",
+    ),
+    builtin(
+        "textureStore",
+        "fn textureStore(t: texture_storage_2d<F, write>, coords: vec2<i32>, value: vec4<T>)",
+        "Writes `value` to the texel of `t` at `coords`.",
+    ),
+    builtin(
+        "textureNumLevels",
+        "fn textureNumLevels(t: texture_2d<T>) -> u32",
+        "The number of mip levels of `t`.",
+    ),
+    // packing
+    builtin(
+        "pack4x8unorm",
+        "fn pack4x8unorm(e: vec4<f32>) -> u32",
+        "Packs the components of `e`, from 0 to 1, into the bytes of a `u32`.",
+    ),
+    builtin(
+        "unpack4x8unorm",
+        "fn unpack4x8unorm(e: u32) -> vec4<f32>",
+        "Unpacks the bytes of `e` into components from 0 to 1.",
+    ),
+    builtin(
+        "pack2x16float",
+        "fn pack2x16float(e: vec2<f32>) -> u32",
+        "Packs the components of `e` as half floats into a `u32`.",
+    ),
+    builtin(
+        "unpack2x16float",
+        "fn unpack2x16float(e: u32) -> vec2<f32>",
+        "Unpacks the half floats of `e`.",
+    ),
+    builtin(
+        "bitcast",
+        "fn bitcast<T>(e: S) -> T",
+        "Reinterprets the bits of `e` as a `T` of the same size.",
+    ),
+    // synchronization & atomics
+    builtin(
+        "workgroupBarrier",
+        "fn workgroupBarrier()",
+        "Waits for every invocation of the workgroup to reach it, making workgroup memory writes visible.",
+    ),
+    builtin(
+        "storageBarrier",
+        "fn storageBarrier()",
+        "Waits for every invocation of the workgroup to reach it, making storage memory writes visible.",
+    ),
+    builtin(
+        "atomicLoad",
+        "fn atomicLoad(p: ptr<AS, atomic<T>>) -> T",
+        "Atomically reads the value `p` points to.",
+    ),
+    builtin(
+        "atomicStore",
+        "fn atomicStore(p: ptr<AS, atomic<T>>, v: T)",
+        "Atomically writes `v` to where `p` points.",
+    ),
+    builtin(
+        "atomicAdd",
+        "fn atomicAdd(p: ptr<AS, atomic<T>>, v: T) -> T",
+        "Atomically adds `v` to the value `p` points to, returning its previous value.",
+    ),
+    builtin(
+        "atomicMax",
+        "fn atomicMax(p: ptr<AS, atomic<T>>, v: T) -> T",
+        "Atomically sets the value `p` points to to its maximum with `v`, returning its previous value.",
+    ),
+    builtin(
+        "atomicMin",
+        "fn atomicMin(p: ptr<AS, atomic<T>>, v: T) -> T",
+        "Atomically sets the value `p` points to to its minimum with `v`, returning its previous value.",
+    ),
+    // halo
+    builtin(
+        "halo_probe",
+        "fn halo_probe(e: T) -> T",
+        "Shows the value of `e` at the inspected pixel beside its line, returning `e` unchanged.",
+    ),
+];
+
+/// The constructors of WGSL's types, which are called like functions.
+pub const CONSTRUCTORS: [Builtin; 30] = [
+    builtin(
+        "bool",
+        "fn bool(e: T) -> bool",
+        "`e` converted to a bool, which is `false` only for zero.",
+    ),
+    builtin("f16", "fn f16(e: T) -> f16", "`e` converted to an f16."),
+    builtin("f32", "fn f32(e: T) -> f32", "`e` converted to an f32."),
+    builtin(
+        "i32",
+        "fn i32(e: T) -> i32",
+        "`e` converted to an i32, truncating floats toward zero.",
+    ),
+    builtin("u32", "fn u32(e: T) -> u32", "`e` converted to a u32, truncating floats toward zero."),
+    builtin(
+        "vec2",
+        "fn vec2<T>(x: T, y: T) -> vec2<T>",
+        "A vector of its 2 components, or of any mix of scalars & vectors with 2 in total, or of a single scalar repeated. `T` is inferred when it's left out.",
+    ),
+    builtin(
+        "vec3",
+        "fn vec3<T>(x: T, y: T, z: T) -> vec3<T>",
+        "A vector of its 3 components, or of any mix of scalars & vectors with 3 in total, or of a single scalar repeated. `T` is inferred when it's left out.",
+    ),
+    builtin(
+        "vec4",
+        "fn vec4<T>(x: T, y: T, z: T, w: T) -> vec4<T>",
+        "A vector of its 4 components, or of any mix of scalars & vectors with 4 in total, or of a single scalar repeated. `T` is inferred when it's left out.",
+    ),
+    builtin(
+        "vec2f",
+        "fn vec2f(x: f32, y: f32) -> vec2<f32>",
+        "A `vec2<f32>`, constructed like `vec2`.",
+    ),
+    builtin(
+        "vec2i",
+        "fn vec2i(x: i32, y: i32) -> vec2<i32>",
+        "A `vec2<i32>`, constructed like `vec2`.",
+    ),
+    builtin(
+        "vec2u",
+        "fn vec2u(x: u32, y: u32) -> vec2<u32>",
+        "A `vec2<u32>`, constructed like `vec2`.",
+    ),
+    builtin(
+        "vec3f",
+        "fn vec3f(x: f32, y: f32, z: f32) -> vec3<f32>",
+        "A `vec3<f32>`, constructed like `vec3`.",
+    ),
+    builtin(
+        "vec3i",
+        "fn vec3i(x: i32, y: i32, z: i32) -> vec3<i32>",
+        "A `vec3<i32>`, constructed like `vec3`.",
+    ),
+    builtin(
+        "vec3u",
+        "fn vec3u(x: u32, y: u32, z: u32) -> vec3<u32>",
+        "A `vec3<u32>`, constructed like `vec3`.",
+    ),
+    builtin(
+        "vec4f",
+        "fn vec4f(x: f32, y: f32, z: f32, w: f32) -> vec4<f32>",
+        "A `vec4<f32>`, constructed like `vec4`.",
+    ),
+    builtin(
+        "vec4i",
+        "fn vec4i(x: i32, y: i32, z: i32, w: i32) -> vec4<i32>",
+        "A `vec4<i32>`, constructed like `vec4`.",
+    ),
+    builtin(
+        "vec4u",
+        "fn vec4u(x: u32, y: u32, z: u32, w: u32) -> vec4<u32>",
+        "A `vec4<u32>`, constructed like `vec4`.",
+    ),
+    builtin(
+        "mat2x2",
+        "fn mat2x2<T>(c0: vec2<T>, c1: vec2<T>) -> mat2x2<T>",
+        "A matrix of its 2 columns, or of all 4 of its elements in column-major order.",
+    ),
+    builtin(
+        "mat2x3",
+        "fn mat2x3<T>(c0: vec3<T>, c1: vec3<T>) -> mat2x3<T>",
+        "A matrix of its 2 columns, or of all 6 of its elements in column-major order.",
+    ),
+    builtin(
+        "mat2x4",
+        "fn mat2x4<T>(c0: vec4<T>, c1: vec4<T>) -> mat2x4<T>",
+        "A matrix of its 2 columns, or of all 8 of its elements in column-major order.",
+    ),
+    builtin(
+        "mat3x2",
+        "fn mat3x2<T>(c0: vec2<T>, c1: vec2<T>, c2: vec2<T>) -> mat3x2<T>",
+        "A matrix of its 3 columns, or of all 6 of its elements in column-major order.",
+    ),
+    builtin(
+        "mat3x3",
+        "fn mat3x3<T>(c0: vec3<T>, c1: vec3<T>, c2: vec3<T>) -> mat3x3<T>",
+        "A matrix of its 3 columns, or of all 9 of its elements in column-major order.",
+    ),
+    builtin(
+        "mat3x4",
+        "fn mat3x4<T>(c0: vec4<T>, c1: vec4<T>, c2: vec4<T>) -> mat3x4<T>",
+        "A matrix of its 3 columns, or of all 12 of its elements in column-major order.",
+    ),
+    builtin(
+        "mat4x2",
+        "fn mat4x2<T>(c0: vec2<T>, c1: vec2<T>, c2: vec2<T>, c3: vec2<T>) -> mat4x2<T>",
+        "A matrix of its 4 columns, or of all 8 of its elements in column-major order.",
+    ),
+    builtin(
+        "mat4x3",
+        "fn mat4x3<T>(c0: vec3<T>, c1: vec3<T>, c2: vec3<T>, c3: vec3<T>) -> mat4x3<T>",
+        "A matrix of its 4 columns, or of all 12 of its elements in column-major order.",
+    ),
+    builtin(
+        "mat4x4",
+        "fn mat4x4<T>(c0: vec4<T>, c1: vec4<T>, c2: vec4<T>, c3: vec4<T>) -> mat4x4<T>",
+        "A matrix of its 4 columns, or of all 16 of its elements in column-major order.",
+    ),
+    builtin(
+        "mat2x2f",
+        "fn mat2x2f(c0: vec2<f32>, c1: vec2<f32>) -> mat2x2<f32>",
+        "A `mat2x2<f32>`, constructed like `mat2x2`.",
+    ),
+    builtin(
+        "mat3x3f",
+        "fn mat3x3f(c0: vec3<f32>, c1: vec3<f32>, c2: vec3<f32>) -> mat3x3<f32>",
+        "A `mat3x3<f32>`, constructed like `mat3x3`.",
+    ),
+    builtin(
+        "mat4x4f",
+        "fn mat4x4f(c0: vec4<f32>, c1: vec4<f32>, c2: vec4<f32>, c3: vec4<f32>) -> mat4x4<f32>",
+        "A `mat4x4<f32>`, constructed like `mat4x4`.",
+    ),
+    builtin(
+        "array",
+        "fn array<T, N>(e0: T, ..., eN: T) -> array<T, N>",
+        "An array of its elements, whose type & count are inferred when they're left out.",
+    ),
+];
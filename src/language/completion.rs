@@ -0,0 +1,126 @@
+//! Completion of the word being typed from WGSL's keywords, types & builtins, & the symbols the
+//! shader declares.
+use crate::language::builtins::{BUILTINS, KEYWORDS, TYPES};
+use crate::language::symbols::{self, Kind};
+use crate::language::Analysis;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: Kind,
+    /// A signature or type.
+    pub detail: String,
+}
+
+/// The partly typed word at the end of `before`, the text before the cursor.
+pub fn prefix(before: &str) -> &str {
+    let start = before
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_identifier(*c))
+        .last()
+        .map_or(before.len(), |(i, _)| i);
+
+    &before[start..]
+}
+
+/// The completions of the word being typed at the end of `before`, which is at `offset` in the
+/// shader, best first.
+///
+/// After a `.`, they're the members of the struct before it, otherwise they're everything that
+/// can be used at `offset`, which only includes the shader's symbols once it's been analysed.
+pub fn complete(analysis: Option<&Analysis>, before: &str, offset: usize) -> Vec<Completion> {
+    let prefix = prefix(before);
+    let before_prefix = &before[..before.len() - prefix.len()];
+
+    if let Some(receiver) = before_prefix.strip_suffix('.') {
+        return members(analysis, receiver, offset)
+            .into_iter()
+            .filter(|completion| matches(prefix, &completion.label))
+            .collect();
+    }
+
+    // don't complete numbers like `1.0`
+    if prefix.is_empty() || prefix.starts_with(|c: char| c.is_ascii_digit()) {
+        return vec![];
+    }
+
+    let symbols = analysis
+        .into_iter()
+        .flat_map(|analysis| analysis.symbols.iter())
+        .filter(|symbol| symbol.is_visible_at(offset))
+        .map(|symbol| Completion {
+            label: symbol.name.clone(),
+            kind: symbol.kind,
+            detail: symbol.detail.clone(),
+        });
+
+    let builtins = BUILTINS.iter().map(|builtin| Completion {
+        label: builtin.name.to_string(),
+        kind: Kind::Builtin,
+        detail: builtin.signature.to_string(),
+    });
+
+    let words = |words: &'static [&'static str], kind| {
+        words.iter().map(move |word| Completion {
+            label: word.to_string(),
+            kind,
+            detail: String::new(),
+        })
+    };
+
+    let mut completions = symbols
+        .chain(builtins)
+        .chain(words(&TYPES, Kind::Type))
+        .chain(words(&KEYWORDS, Kind::Keyword))
+        .filter(|completion| matches(prefix, &completion.label) && completion.label != prefix)
+        .collect::<Vec<_>>();
+
+    // closest scope first, then alphabetically, keeping only the closest of symbols which shadow
+    // others
+    completions.sort_by(|a, b| (a.kind, &a.label).cmp(&(b.kind, &b.label)));
+    completions.dedup_by(|a, b| a.label == b.label);
+
+    completions
+}
+
+/// The members of the struct `receiver` ends with.
+fn members(analysis: Option<&Analysis>, receiver: &str, offset: usize) -> Vec<Completion> {
+    let Some(analysis) = analysis else {
+        return vec![];
+    };
+
+    let name = prefix(receiver.trim_end());
+
+    let Some(ty) = analysis
+        .symbols
+        .iter()
+        .filter(|symbol| symbol.name == name && symbol.is_visible_at(offset))
+        // a function's type is the type it returns, which it has no members of
+        .filter(|symbol| symbol.kind != Kind::Function)
+        .min_by_key(|symbol| symbol.kind)
+        .and_then(|symbol| symbol.ty)
+    else {
+        return vec![];
+    };
+
+    symbols::members(&analysis.module, ty)
+        .into_iter()
+        .map(|(label, detail)| Completion {
+            label,
+            kind: Kind::Member,
+            detail,
+        })
+        .collect()
+}
+
+/// Whether `label` starts with `prefix`, ignoring case.
+fn matches(prefix: &str, label: &str) -> bool {
+    label
+        .get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+fn is_identifier(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
@@ -0,0 +1,256 @@
+//! Documentation of what's under the cursor & the signature of the call it's in.
+use crate::language::builtins;
+use crate::language::symbols::{self, Kind, Symbol};
+use crate::language::{prefix, Analysis};
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hover {
+    /// The signature of a function, or the name & type of anything else.
+    pub signature: String,
+    pub description: Option<String>,
+}
+
+/// The signature of a function being called, with the parameter being typed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureHelp {
+    pub signature: String,
+    /// The range of each parameter in the signature.
+    pub parameters: Vec<Range<usize>>,
+    /// The parameter the cursor is in, counted from 0.
+    pub active: usize,
+}
+
+impl SignatureHelp {
+    /// The range of the signature of the parameter being typed.
+    pub fn active_parameter(&self) -> Option<Range<usize>> {
+        self.parameters.get(self.active).cloned()
+    }
+}
+
+/// The documentation of the builtin or symbol at `offset` in the `shader`, or the type of the
+/// expression there if the shader hasn't changed since it was analysed.
+pub fn hover(analysis: Option<&Analysis>, shader: &str, offset: usize) -> Option<Hover> {
+    let word = word_at(shader, offset);
+    let name = &shader[word.clone()];
+
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    // a member, e.g. `time` in `uniforms.time`, is only found from the type of its expression
+    let is_member = shader[..word.start].trim_end().ends_with('.');
+
+    if let Some(builtin) = builtins::find(name).filter(|_| !is_member) {
+        return Some(Hover {
+            signature: builtin.signature.to_string(),
+            description: Some(builtin.description.to_string()),
+        });
+    }
+
+    let analysis = analysis?;
+
+    if let Some(symbol) = find(analysis, name, offset).filter(|_| !is_member) {
+        return Some(Hover {
+            signature: match symbol.kind {
+                Kind::Function | Kind::Struct => symbol.detail.clone(),
+                _ => format!("{name}: {}", symbol.detail),
+            },
+            description: symbol
+                .span
+                .as_ref()
+                .and_then(|span| comment_before(&analysis.shader, span.start)),
+        });
+    }
+
+    if analysis.shader != shader {
+        return None;
+    }
+
+    let (range, ty) = expression_at(analysis, offset)?;
+
+    Some(Hover {
+        signature: format!("{}: {ty}", &shader[range]),
+        description: None,
+    })
+}
+
+/// The signature of the function called around `offset` in the `shader`, if it's inside the
+/// arguments of a call.
+pub fn signature_help(
+    analysis: Option<&Analysis>,
+    shader: &str,
+    offset: usize,
+) -> Option<SignatureHelp> {
+    let (name, active) = enclosing_call(&shader[..offset.min(shader.len())])?;
+
+    let signature = match builtins::find(name) {
+        Some(builtin) => builtin.signature.to_string(),
+        None => analysis?
+            .symbols
+            .iter()
+            .find(|symbol| symbol.kind == Kind::Function && symbol.name == name)?
+            .detail
+            .clone(),
+    };
+
+    let parameters = parameters(&signature);
+
+    Some(SignatureHelp {
+        signature,
+        parameters,
+        active,
+    })
+}
+
+/// The closest symbol called `name` that can be used at `offset`.
+pub(super) fn find<'a>(analysis: &'a Analysis, name: &str, offset: usize) -> Option<&'a Symbol> {
+    analysis
+        .symbols
+        .iter()
+        .filter(|symbol| symbol.name == name && symbol.is_visible_at(offset))
+        .min_by_key(|symbol| symbol.kind)
+}
+
+/// The range & type of the smallest expression at `offset`.
+fn expression_at(analysis: &Analysis, offset: usize) -> Option<(Range<usize>, String)> {
+    let info = analysis.info.as_ref()?;
+
+    symbols::functions(&analysis.module, Some(info))
+        .flat_map(|(function, _, function_info)| {
+            function.expressions.iter().filter_map(move |(handle, _)| {
+                let range =
+                    symbols::to_shader(function.expressions.get_span(handle), &analysis.erased)?;
+                let ty = &function_info?[handle].ty;
+
+                range.contains(&offset).then_some((range, ty))
+            })
+        })
+        .min_by_key(|(range, _)| range.len())
+        .map(|(range, ty)| (range, symbols::value_type_name(&analysis.module, ty)))
+}
+
+/// The range of the identifier at or just before `offset`.
+pub(super) fn word_at(shader: &str, offset: usize) -> Range<usize> {
+    let offset = offset.min(shader.len());
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+
+    let start = shader[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_identifier(*c))
+        .last()
+        .map_or(offset, |(i, _)| i);
+
+    let end = shader[offset..]
+        .char_indices()
+        .find(|(_, c)| !is_identifier(*c))
+        .map_or(shader.len(), |(i, _)| offset + i);
+
+    start..end
+}
+
+/// The `//` comments on the lines right before the line at `offset`, joined into one.
+fn comment_before(shader: &str, offset: usize) -> Option<String> {
+    let line_start = shader[..offset].rfind('\n').map_or(0, |i| i + 1);
+
+    let mut lines = shader[..line_start]
+        .lines()
+        .rev()
+        .map_while(|line| line.trim().strip_prefix("//"))
+        .map(str::trim)
+        .collect::<Vec<_>>();
+
+    lines.reverse();
+
+    let comment = lines.join(" ");
+
+    (!comment.is_empty()).then_some(comment)
+}
+
+/// The name of the function whose arguments end at the end of `before`, & the index of the
+/// argument being typed.
+fn enclosing_call(before: &str) -> Option<(&str, usize)> {
+    let mut depth = 0;
+    let mut argument = 0;
+
+    for (i, c) in before.char_indices().rev() {
+        match c {
+            ')' | ']' => depth += 1,
+            '(' | '[' if depth > 0 => depth -= 1,
+            '(' => {
+                let name = prefix(without_template(before[..i].trim_end()));
+
+                return (!name.is_empty()).then_some((name, argument));
+            }
+            ',' if depth == 0 => argument += 1,
+            '[' | ';' | '{' | '}' => return None,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// `callee` without a trailing template list, like the `<f32>` of `vec4<f32>`, so a templated
+/// constructor is named by its type.
+fn without_template(callee: &str) -> &str {
+    if !callee.ends_with('>') {
+        return callee;
+    }
+
+    let mut depth = 0;
+
+    for (i, c) in callee.char_indices().rev() {
+        match c {
+            '>' => depth += 1,
+            '<' => {
+                depth -= 1;
+
+                if depth == 0 {
+                    // a comparison like `a < b > (c)` has spaces before its `<`
+                    return if prefix(&callee[..i]).is_empty() {
+                        callee
+                    } else {
+                        &callee[..i]
+                    };
+                }
+            }
+            _ => {}
+        }
+    }
+
+    callee
+}
+
+/// The ranges of the parameters in a signature like `fn f(a: T, b: array<f32, 4>) -> T`.
+fn parameters(signature: &str) -> Vec<Range<usize>> {
+    let Some(open) = signature.find('(') else {
+        return vec![];
+    };
+
+    let mut parameters = vec![];
+    let mut depth = 0;
+    let mut start = open + 1;
+
+    for (i, c) in signature.char_indices().skip(open + 1) {
+        match c {
+            '(' | '<' => depth += 1,
+            ')' if depth == 0 => {
+                if i > start {
+                    parameters.push(start..i);
+                }
+
+                break;
+            }
+            ')' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                parameters.push(start..i);
+                start = i + 2;
+            }
+            _ => {}
+        }
+    }
+
+    parameters
+}
@@ -0,0 +1,151 @@
+//! Finding where the shader's symbols are declared & used.
+use crate::language::hover::{find, word_at};
+use crate::language::symbols::{Kind, Symbol};
+use crate::language::Analysis;
+use std::ops::Range;
+
+/// A symbol declared at the top level of the shader, listed in its outline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub name: String,
+    pub kind: Kind,
+    /// The signature of a function, or the type of anything else.
+    pub detail: String,
+    /// Where its name is declared in the shader.
+    pub offset: usize,
+}
+
+/// The range of the name of the declaration of the symbol at `offset` in the `shader`.
+pub fn definition(
+    analysis: Option<&Analysis>,
+    shader: &str,
+    offset: usize,
+) -> Option<Range<usize>> {
+    let analysis = analysis?;
+    let word = word_at(shader, offset);
+
+    if is_member(shader, word.start) {
+        return None;
+    }
+
+    let symbol = symbol_at(analysis, &shader[word], offset)?;
+
+    declaration(&analysis.shader, symbol)
+}
+
+/// The ranges of every use of the symbol at `offset` in the `shader`, including its declaration,
+/// skipping those of other symbols with the same name which shadow it.
+pub fn references(analysis: Option<&Analysis>, shader: &str, offset: usize) -> Vec<Range<usize>> {
+    let Some(analysis) = analysis else {
+        return vec![];
+    };
+
+    let word = word_at(shader, offset);
+    let name = &shader[word.clone()];
+
+    if is_member(shader, word.start) {
+        return vec![];
+    }
+
+    let Some(symbol) = symbol_at(analysis, name, offset) else {
+        return vec![];
+    };
+
+    let declaration = declaration(&analysis.shader, symbol);
+
+    occurrences(shader, name)
+        .filter(|range| {
+            Some(range) == declaration.as_ref()
+                || find(analysis, name, range.start)
+                    .is_some_and(|found| std::ptr::eq(found, symbol))
+        })
+        .collect()
+}
+
+/// The structs, constants, globals & functions the shader declares, in the order it declares
+/// them.
+pub(super) fn outline(shader: &str, module: &naga::Module, symbols: &[Symbol]) -> Vec<Item> {
+    let declared = symbols
+        .iter()
+        .filter(|symbol| {
+            matches!(
+                symbol.kind,
+                Kind::Struct | Kind::Constant | Kind::Global | Kind::Function
+            )
+        })
+        .filter_map(|symbol| {
+            Some(Item {
+                name: symbol.name.clone(),
+                kind: symbol.kind,
+                detail: symbol.detail.clone(),
+                offset: declaration(shader, symbol)?.start,
+            })
+        });
+
+    // entry points have no spans, so they're found by their `fn`
+    let entry_points = module.entry_points.iter().filter_map(|entry_point| {
+        let range = occurrences(shader, &entry_point.name)
+            .find(|range| shader[..range.start].trim_end().ends_with("fn"))?;
+
+        let stage = format!("{:?}", entry_point.stage).to_lowercase();
+
+        Some(Item {
+            name: entry_point.name.clone(),
+            kind: Kind::Function,
+            detail: format!("@{stage} fn {}", entry_point.name),
+            offset: range.start,
+        })
+    });
+
+    let mut items = declared.chain(entry_points).collect::<Vec<_>>();
+    items.sort_by_key(|item| item.offset);
+
+    items
+}
+
+/// The symbol named `name` at `offset`, which is either used or declared there, as the name of a
+/// `let` comes before its scope.
+fn symbol_at<'a>(analysis: &'a Analysis, name: &str, offset: usize) -> Option<&'a Symbol> {
+    find(analysis, name, offset).or_else(|| {
+        analysis.symbols.iter().find(|symbol| {
+            symbol.name == name
+                && declaration(&analysis.shader, symbol)
+                    .is_some_and(|range| range.start <= offset && offset <= range.end)
+        })
+    })
+}
+
+/// The range of the name of a symbol where it's declared in the `shader` it was found in.
+fn declaration(shader: &str, symbol: &Symbol) -> Option<Range<usize>> {
+    let span = symbol.span.as_ref()?;
+
+    // the spans of most symbols cover their whole declaration, but a `let`'s only covers the
+    // value it's initialised to, which comes after its name
+    occurrences(shader, &symbol.name)
+        .find(|range| span.start <= range.start && range.end <= span.end)
+        .or_else(|| occurrences(&shader[..span.start], &symbol.name).last())
+}
+
+/// The ranges of every whole word `name` in the `shader`, outside of comments & not as a member.
+fn occurrences<'a>(shader: &'a str, name: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+
+    shader
+        .match_indices(name)
+        .map(|(start, _)| start..start + name.len())
+        .filter(move |range| {
+            let before = shader[..range.start].chars().next_back();
+            let after = shader[range.end..].chars().next();
+            let line_start = shader[..range.start].rfind('\n').map_or(0, |i| i + 1);
+
+            !before.is_some_and(is_identifier)
+                && !after.is_some_and(is_identifier)
+                && !shader[line_start..range.start].contains("//")
+                && !is_member(shader, range.start)
+        })
+}
+
+/// Whether the word starting at `start` is a member, like `time` in `uniforms.time`.
+fn is_member(shader: &str, start: usize) -> bool {
+    shader[..start].trim_end().ends_with('.')
+}
@@ -0,0 +1,414 @@
+//! The symbols a shader declares, found in the module naga parses from it.
+use crate::validation::OFFSET;
+use crate::viewer::probe;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    Local,
+    Parameter,
+    Member,
+    Global,
+    Constant,
+    Function,
+    Struct,
+    Builtin,
+    Type,
+    Keyword,
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Kind::Local => "local",
+            Kind::Parameter => "parameter",
+            Kind::Member => "field",
+            Kind::Global => "global",
+            Kind::Constant => "const",
+            Kind::Function => "fn",
+            Kind::Struct => "struct",
+            Kind::Builtin => "builtin",
+            Kind::Type => "type",
+            Kind::Keyword => "keyword",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: Kind,
+    /// The signature of a function, or the type of anything else.
+    pub detail: String,
+    /// Where it's declared in the shader, or `None` if it's declared in the prelude or an
+    /// imported library.
+    pub span: Option<Range<usize>>,
+    /// The part of the shader it can be used in, or `None` if it can be used anywhere.
+    pub scope: Option<Range<usize>>,
+    /// Its type, to find the members of a struct.
+    pub ty: Option<naga::Handle<naga::Type>>,
+}
+
+impl Symbol {
+    /// Whether the symbol can be used at `offset` in the shader.
+    pub fn is_visible_at(&self, offset: usize) -> bool {
+        match &self.scope {
+            Some(scope) => scope.contains(&offset),
+            None => true,
+        }
+    }
+}
+
+/// Every symbol declared in the `module` parsed from the `erased` shader, with the types of its
+/// `let`s if it was also validated.
+pub fn find(
+    module: &naga::Module,
+    info: Option<&naga::valid::ModuleInfo>,
+    erased: &probe::Erased,
+) -> Vec<Symbol> {
+    let to_shader = |span| to_shader(span, erased);
+    let len = erased.to_shader(erased.source.len());
+
+    let mut symbols = vec![];
+
+    for (handle, ty) in module.types.iter() {
+        if let (Some(name), naga::TypeInner::Struct { .. }) = (&ty.name, &ty.inner) {
+            symbols.push(Symbol {
+                name: name.clone(),
+                kind: Kind::Struct,
+                detail: format!("struct {name}"),
+                span: to_shader(module.types.get_span(handle)),
+                scope: None,
+                ty: Some(handle),
+            });
+        }
+    }
+
+    for (handle, constant) in module.constants.iter() {
+        if let Some(name) = &constant.name {
+            symbols.push(Symbol {
+                name: name.clone(),
+                kind: Kind::Constant,
+                detail: type_name(module, constant.ty),
+                span: to_shader(module.constants.get_span(handle)),
+                scope: None,
+                ty: Some(constant.ty),
+            });
+        }
+    }
+
+    for (handle, global) in module.global_variables.iter() {
+        if let Some(name) = &global.name {
+            symbols.push(Symbol {
+                name: name.clone(),
+                kind: Kind::Global,
+                detail: type_name(module, global.ty),
+                span: to_shader(module.global_variables.get_span(handle)),
+                scope: None,
+                ty: Some(global.ty),
+            });
+        }
+    }
+
+    // the starts of the declarations at the module's top level, as entry points have no spans
+    // to find where their locals go out of scope
+    let mut declarations = symbols
+        .iter()
+        .filter_map(|symbol| Some(symbol.span.as_ref()?.start))
+        .collect::<Vec<_>>();
+
+    for (handle, function) in module.functions.iter() {
+        let span = to_shader(module.functions.get_span(handle));
+
+        if let Some(span) = &span {
+            declarations.push(span.start);
+        }
+
+        if let Some(name) = &function.name {
+            symbols.push(Symbol {
+                name: name.clone(),
+                kind: Kind::Function,
+                detail: signature(module, name, function),
+                span,
+                scope: None,
+                ty: function.result.as_ref().map(|result| result.ty),
+            });
+        }
+    }
+
+    declarations.sort_unstable();
+
+    let end_of = |start: usize| {
+        declarations
+            .iter()
+            .find(|declaration| **declaration > start)
+            .copied()
+            .unwrap_or(len)
+    };
+
+    for (function, span, function_info) in functions(module, info) {
+        let span = to_shader(span);
+        let scope = |start: usize| match &span {
+            Some(span) => start..span.end,
+            None => start..end_of(start),
+        };
+
+        for (handle, variable) in function.local_variables.iter() {
+            let (Some(name), Some(span)) = (
+                &variable.name,
+                to_shader(function.local_variables.get_span(handle)),
+            ) else {
+                continue;
+            };
+
+            symbols.push(Symbol {
+                name: name.clone(),
+                kind: Kind::Local,
+                detail: type_name(module, variable.ty),
+                scope: Some(scope(span.start)),
+                span: Some(span),
+                ty: Some(variable.ty),
+            });
+        }
+
+        // parameters & `let`s
+        for (handle, name) in &function.named_expressions {
+            let Some(span) = to_shader(function.expressions.get_span(*handle)) else {
+                continue;
+            };
+
+            let (kind, ty) = match function.expressions[*handle] {
+                naga::Expression::FunctionArgument(i) => {
+                    let ty = function.arguments[i as usize].ty;
+
+                    (Kind::Parameter, Some(ty))
+                }
+                _ => (Kind::Local, None),
+            };
+
+            let resolution = function_info.map(|info| &info[*handle].ty);
+            let detail = match (ty, resolution) {
+                (Some(ty), _) => type_name(module, ty),
+                (None, Some(resolution)) => value_type_name(module, resolution),
+                (None, None) => String::new(),
+            };
+
+            symbols.push(Symbol {
+                name: name.clone(),
+                kind,
+                detail,
+                scope: Some(scope(span.start)),
+                span: Some(span),
+                ty: ty.or(resolution.and_then(|resolution| resolution.handle())),
+            });
+        }
+    }
+
+    symbols
+}
+
+/// The range of the shader a span of the module parsed from it `erased` covers, if it's in the
+/// shader rather than the prelude or an imported library.
+pub fn to_shader(span: naga::Span, erased: &probe::Erased) -> Option<Range<usize>> {
+    // spans are of the source naga parsed, which is the erased shader between the prelude &
+    // imports
+    let range = span.to_range()?;
+    let start = range.start.checked_sub(OFFSET)?;
+
+    (start < erased.source.len())
+        .then(|| erased.to_shader(start)..erased.to_shader(range.end - OFFSET))
+}
+
+/// Every function of the module, including its entry points which have no spans, with its info
+/// if the module was validated.
+pub fn functions<'a>(
+    module: &'a naga::Module,
+    info: Option<&'a naga::valid::ModuleInfo>,
+) -> impl Iterator<
+    Item = (
+        &'a naga::Function,
+        naga::Span,
+        Option<&'a naga::valid::FunctionInfo>,
+    ),
+> {
+    module
+        .functions
+        .iter()
+        .map(move |(handle, function)| {
+            (
+                function,
+                module.functions.get_span(handle),
+                info.map(|info| &info[handle]),
+            )
+        })
+        .chain(
+            module
+                .entry_points
+                .iter()
+                .enumerate()
+                .map(move |(i, entry_point)| {
+                    (
+                        &entry_point.function,
+                        naga::Span::default(),
+                        info.map(|info| info.get_entry_point(i)),
+                    )
+                }),
+        )
+}
+
+/// The members of a struct & their types, if `ty` is one or a pointer to one.
+pub fn members(module: &naga::Module, ty: naga::Handle<naga::Type>) -> Vec<(String, String)> {
+    match &module.types[ty].inner {
+        naga::TypeInner::Struct { members, .. } => members
+            .iter()
+            .filter_map(|member| Some((member.name.clone()?, type_name(module, member.ty))))
+            .collect(),
+        naga::TypeInner::Pointer { base, .. } => members(module, *base),
+        _ => vec![],
+    }
+}
+
+fn signature(module: &naga::Module, name: &str, function: &naga::Function) -> String {
+    let arguments = function
+        .arguments
+        .iter()
+        .map(|argument| {
+            format!(
+                "{}: {}",
+                argument.name.as_deref().unwrap_or("_"),
+                type_name(module, argument.ty)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match &function.result {
+        Some(result) => format!("fn {name}({arguments}) -> {}", type_name(module, result.ty)),
+        None => format!("fn {name}({arguments})"),
+    }
+}
+
+/// The name of a type as it's written in WGSL.
+pub fn type_name(module: &naga::Module, ty: naga::Handle<naga::Type>) -> String {
+    let ty = &module.types[ty];
+
+    match &ty.name {
+        Some(name) => name.clone(),
+        None => inner_name(module, &ty.inner),
+    }
+}
+
+/// The name of the type of a value, or of what it points to for a pointer as WGSL loads them
+/// where they're used.
+pub fn value_type_name(module: &naga::Module, resolution: &naga::proc::TypeResolution) -> String {
+    match *resolution.inner_with(&module.types) {
+        naga::TypeInner::Pointer { base, .. } => type_name(module, base),
+        naga::TypeInner::ValuePointer {
+            size: Some(size),
+            kind,
+            width,
+            ..
+        } => format!("vec{}<{}>", size as u8, scalar_name(kind, width)),
+        naga::TypeInner::ValuePointer {
+            size: None,
+            kind,
+            width,
+            ..
+        } => scalar_name(kind, width).to_string(),
+        _ => resolution_name(module, resolution),
+    }
+}
+
+fn resolution_name(module: &naga::Module, resolution: &naga::proc::TypeResolution) -> String {
+    match resolution {
+        naga::proc::TypeResolution::Handle(ty) => type_name(module, *ty),
+        naga::proc::TypeResolution::Value(inner) => inner_name(module, inner),
+    }
+}
+
+fn inner_name(module: &naga::Module, inner: &naga::TypeInner) -> String {
+    match *inner {
+        naga::TypeInner::Scalar { kind, width } => scalar_name(kind, width).to_string(),
+        naga::TypeInner::Vector { size, kind, width } => {
+            format!("vec{}<{}>", size as u8, scalar_name(kind, width))
+        }
+        naga::TypeInner::Matrix {
+            columns,
+            rows,
+            width,
+        } => format!(
+            "mat{}x{}<{}>",
+            columns as u8,
+            rows as u8,
+            scalar_name(naga::ScalarKind::Float, width)
+        ),
+        naga::TypeInner::Atomic { kind, width } => {
+            format!("atomic<{}>", scalar_name(kind, width))
+        }
+        naga::TypeInner::Pointer { base, .. } => format!("ptr<{}>", type_name(module, base)),
+        naga::TypeInner::ValuePointer {
+            size, kind, width, ..
+        } => match size {
+            Some(size) => format!("ptr<vec{}<{}>>", size as u8, scalar_name(kind, width)),
+            None => format!("ptr<{}>", scalar_name(kind, width)),
+        },
+        naga::TypeInner::Array { base, size, .. } => match size {
+            naga::ArraySize::Constant(size) => {
+                format!("array<{}, {size}>", type_name(module, base))
+            }
+            naga::ArraySize::Dynamic => format!("array<{}>", type_name(module, base)),
+        },
+        naga::TypeInner::Struct { .. } => "struct".to_string(),
+        naga::TypeInner::Image {
+            dim,
+            arrayed,
+            class,
+        } => {
+            let dim = match dim {
+                naga::ImageDimension::D1 => "1d",
+                naga::ImageDimension::D2 => "2d",
+                naga::ImageDimension::D3 => "3d",
+                naga::ImageDimension::Cube => "cube",
+            };
+            let array = if arrayed { "_array" } else { "" };
+
+            match class {
+                naga::ImageClass::Sampled { kind, multi } => format!(
+                    "texture_{}{dim}{array}<{}>",
+                    if multi { "multisampled_" } else { "" },
+                    scalar_name(kind, 4)
+                ),
+                naga::ImageClass::Depth { multi } => format!(
+                    "texture_depth_{}{dim}{array}",
+                    if multi { "multisampled_" } else { "" }
+                ),
+                naga::ImageClass::Storage { .. } => format!("texture_storage_{dim}{array}"),
+            }
+        }
+        naga::TypeInner::Sampler { comparison } => if comparison {
+            "sampler_comparison"
+        } else {
+            "sampler"
+        }
+        .to_string(),
+        naga::TypeInner::AccelerationStructure => "acceleration_structure".to_string(),
+        naga::TypeInner::RayQuery => "ray_query".to_string(),
+        naga::TypeInner::BindingArray { base, .. } => {
+            format!("binding_array<{}>", type_name(module, base))
+        }
+    }
+}
+
+fn scalar_name(kind: naga::ScalarKind, width: naga::Bytes) -> &'static str {
+    match (kind, width) {
+        (naga::ScalarKind::Bool, _) => "bool",
+        (naga::ScalarKind::Float, 2) => "f16",
+        (naga::ScalarKind::Float, 8) => "f64",
+        (naga::ScalarKind::Float, _) => "f32",
+        (naga::ScalarKind::Sint, _) => "i32",
+        (naga::ScalarKind::Uint, _) => "u32",
+    }
+}
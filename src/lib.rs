@@ -0,0 +1,328 @@
+//! Halo's shader viewer & validation, for embedding live shader previews in other iced apps.
+//!
+//! [`Viewer`] is an iced shader program: put [`Viewer::preview`] in your view, map its
+//! [`viewer::Message`]s back to [`Viewer::update`], & call [`Viewer::set_shader`] once a shader
+//! passes [`validation::validate`]. [`format`] formats WGSL like the editor's "Format document".
+//! [`lsp`] serves the editor's diagnostics & language features to other editors.
+//! [`run`] starts the full halo editor.
+
+mod editor;
+pub mod format;
+mod keymap;
+mod language;
+pub mod lsp;
+mod palette;
+mod preferences;
+mod theme;
+pub mod validation;
+pub mod viewer;
+mod widget;
+
+pub use viewer::{headless, Viewer};
+
+use crate::editor::Editor;
+use crate::keymap::Keymap;
+use crate::palette::Palette;
+use crate::preferences::Preferences;
+use crate::theme::Theme;
+use crate::widget::pane_grid::PaneGrid;
+use crate::widget::{Element, Shortcuts};
+use iced::font::{Family, Stretch, Style, Weight};
+use iced::widget::pane_grid::Configuration;
+use iced::widget::{column, container, pane_grid, text};
+use iced::{executor, system, window, Application, Command, Font, Length};
+use std::sync::Arc;
+
+pub type FragmentShader = String;
+
+const HALO: &str = "Halo";
+
+const JETBRAINS_MONO: Font = Font {
+    family: Family::Name("JetBrains Mono"),
+    weight: Weight::Normal,
+    stretch: Stretch::Normal,
+    style: Style::Normal,
+};
+
+/// Runs the halo editor.
+pub fn run() -> iced::Result {
+    Halo::run(iced::Settings {
+        fonts: vec![
+            include_bytes!("../fonts/JetBrainsMono-Regular.ttf")
+                .as_slice()
+                .into(),
+            include_bytes!("../fonts/halo-icons.ttf").as_slice().into(),
+        ],
+        window: window::Settings {
+            size: (1600, 900),
+            ..Default::default()
+        },
+        default_font: Font::MONOSPACE,
+        ..Default::default()
+    })
+}
+
+struct Halo {
+    viewer: Viewer,
+    editor: Editor,
+    keymap: Keymap,
+    palette: Palette,
+    panes: pane_grid::State<Pane>,
+    /// The pane clicked last, which gets the key presses of editing shortcuts.
+    focus: Pane,
+}
+
+//TODO toggle editor
+#[derive(Clone, Debug)]
+enum Message {
+    PaneResized(pane_grid::ResizeEvent),
+    PaneClicked(pane_grid::Pane),
+    Editor(editor::Message),
+    Shortcut(keymap::Command),
+    KeymapLoaded(Result<Keymap, keymap::Error>),
+    Palette(palette::Message),
+    Viewer(viewer::Message),
+    Loaded(Result<(Preferences, Arc<FragmentShader>), preferences::Error>),
+}
+
+impl Application for Halo {
+    type Executor = executor::Default;
+    type Message = Message;
+    type Theme = Theme;
+    type Flags = ();
+
+    fn new(_flags: Self::Flags) -> (Self, Command<Self::Message>) {
+        (
+            //TODO save settings
+            Self {
+                viewer: Viewer::default(),
+                editor: Editor::default(),
+                keymap: Keymap::default(),
+                palette: Palette::default(),
+                panes: pane_grid::State::with_configuration(Configuration::Split {
+                    axis: pane_grid::Axis::Vertical,
+                    ratio: 0.5,
+                    a: Box::new(Configuration::Pane(Pane::Viewer)),
+                    b: Box::new(Configuration::Pane(Pane::Editor)),
+                }),
+                focus: Pane::Editor,
+            },
+            //TODO load last shader file from settings
+            Command::batch(vec![
+                Command::perform(preferences::load(), Message::Loaded),
+                Command::perform(keymap::load(), Message::KeymapLoaded),
+                system::fetch_information(|information| {
+                    Message::Viewer(viewer::Message::SystemInformation(information))
+                }),
+            ]),
+        )
+    }
+
+    fn title(&self) -> String {
+        HALO.to_string()
+    }
+
+    fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
+        match message {
+            Message::Editor(msg) => {
+                let (event, cmd) = self.editor.update(msg);
+
+                match event {
+                    editor::Event::UpdatePipeline(shader) => {
+                        self.viewer.set_shader(&shader);
+                    }
+                    _ => {}
+                };
+
+                return cmd.map(Message::Editor);
+            }
+            Message::PaneResized(pane_grid::ResizeEvent { split, ratio }) => {
+                self.panes.resize(split, ratio);
+            }
+            Message::PaneClicked(pane) => {
+                if let Some(pane) = self.panes.get(pane) {
+                    self.focus = *pane;
+                }
+            }
+            Message::Shortcut(keymap::Command::CommandPalette) => {
+                // commands which wouldn't do anything now, like exporting hidden stats, aren't listed
+                let available = keymap::Command::ALL
+                    .into_iter()
+                    .filter(|command| self.shortcut(*command).is_some())
+                    .collect();
+
+                return self.palette.toggle(available).map(Message::Palette);
+            }
+            Message::Shortcut(command) => {
+                if let Some(msg) = self.shortcut(command) {
+                    return self.update(msg);
+                }
+            }
+            Message::Palette(msg) => {
+                let (event, cmd) = self.palette.update(msg);
+
+                if let palette::Event::Run(command) = event {
+                    let recent = self.palette.recent().to_vec();
+
+                    return Command::batch(vec![
+                        cmd.map(Message::Palette),
+                        self.update(Message::Editor(editor::Message::RecentCommands(recent))),
+                        self.update(Message::Shortcut(command)),
+                    ]);
+                }
+
+                return cmd.map(Message::Palette);
+            }
+            Message::KeymapLoaded(result) => match result {
+                Ok(keymap) => {
+                    self.keymap = keymap;
+                }
+                Err(e) => println!("Error loading key bindings: {e}"),
+            },
+            Message::Loaded(result) => {
+                if let Ok((prefs, _)) = &result {
+                    self.palette.set_recent(prefs.recent_commands.clone());
+                }
+
+                return self.update(Message::Editor(editor::Message::Init(result)));
+            }
+            Message::Viewer(msg) => {
+                let (event, cmd) = self.viewer.update(msg);
+
+                let editor_msg = match event {
+                    viewer::Event::DeviceDetected(device) => {
+                        Some(editor::Message::DeviceDetected(device))
+                    }
+                    viewer::Event::SettingsChanged(settings) => {
+                        Some(editor::Message::RenderSettingsChanged(settings))
+                    }
+                    viewer::Event::Probed(readings) => Some(editor::Message::Probed(readings)),
+                    viewer::Event::None => None,
+                };
+
+                if let Some(msg) = editor_msg {
+                    return Command::batch(vec![
+                        cmd.map(Message::Viewer),
+                        self.update(Message::Editor(msg)),
+                    ]);
+                }
+
+                return cmd.map(Message::Viewer);
+            }
+        }
+
+        Command::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        let panes = PaneGrid::new(&self.panes, |_id, pane, _is_maximized| {
+            pane.view(&self.editor, &self.viewer).into()
+        })
+        .on_click(Message::PaneClicked)
+        .on_resize(10, Message::PaneResized);
+
+        //TODO float the palette over the panes once iced can stack widgets
+        let palette: Option<Element<Message>> = self.palette.is_open().then(|| {
+            container(self.palette.view(&self.keymap).map(Message::Palette))
+                .width(Length::Fill)
+                .center_x()
+                .padding(10)
+                .style(theme::Container::Controls)
+                .into()
+        });
+
+        // conflicts are listed in the palette, so only point to it while it's closed
+        let conflicts = self.keymap.conflicts();
+        let status: Option<Element<Message>> = (!conflicts.is_empty() && !self.palette.is_open())
+            .then(|| {
+                let palette = self
+                    .keymap
+                    .bindings(keymap::Command::CommandPalette)
+                    .next()
+                    .map_or(String::new(), |binding| format!(" ({binding})"));
+
+                container(
+                    text(format!(
+                        "{} key bindings conflict; open the command palette{palette} to see them",
+                        conflicts.len()
+                    ))
+                    .size(14)
+                    .style(theme::Text::Error),
+                )
+                .width(Length::Fill)
+                .padding([5, 10])
+                .style(theme::Container::Controls)
+                .into()
+            });
+
+        let content = container(column(
+            palette
+                .into_iter()
+                .chain([panes.into()])
+                .chain(status)
+                .collect(),
+        ))
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+        // editing keys like tab reach the viewer's keyboard unless the editor was clicked last
+        let is_editing = !self.palette.is_open() && self.focus == Pane::Editor;
+
+        Shortcuts::new(content, move |key, modifiers| {
+            let key_press = if self.palette.is_open() {
+                self.palette.key_press(key, modifiers).map(Message::Palette)
+            } else if is_editing {
+                self.editor.key_press(key, modifiers).map(Message::Editor)
+            } else {
+                None
+            };
+
+            key_press.or_else(|| {
+                self.keymap
+                    .command(key, modifiers)
+                    .filter(|command| is_editing || !command.is_editing())
+                    .map(Message::Shortcut)
+            })
+        })
+        .on_modifiers_change(|modifiers| {
+            Message::Editor(editor::Message::ModifiersChanged(modifiers))
+        })
+        .into()
+    }
+
+    fn theme(&self) -> Self::Theme {
+        Theme::Dark
+    }
+}
+
+impl Halo {
+    /// The message that runs `command`, if it would do anything now.
+    fn shortcut(&self, command: keymap::Command) -> Option<Message> {
+        self.editor
+            .shortcut(command)
+            .map(Message::Editor)
+            .or_else(|| self.viewer.shortcut(command).map(Message::Viewer))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Viewer,
+    Editor,
+}
+
+impl Pane {
+    fn view<'a>(
+        &'a self,
+        editor: &'a Editor,
+        viewer: &'a Viewer,
+    ) -> widget::pane_grid::Content<Message> {
+        match self {
+            Self::Viewer => viewer.content(),
+            Self::Editor => widget::pane_grid::Content::new(editor.view().map(Message::Editor))
+                .title_bar(widget::pane_grid::TitleBar::new(
+                    editor.title_bar().map(Message::Editor),
+                )),
+        }
+    }
+}
@@ -0,0 +1,467 @@
+//! A language server, run with `halo --lsp`, giving other editors halo's diagnostics, completions,
+//! documentation, navigation & formatting over stdio.
+//!
+//! Shaders are validated the same way the editor validates them, after the prelude & with the
+//! libraries they import, so using `uniforms` or an imported function isn't an error. They're
+//! validated against WebGPU's capabilities, as there's no device to detect.
+mod transport;
+
+use crate::language::{self, Analysis, Kind};
+use crate::validation;
+use crate::viewer::device::Target;
+use crate::{format, FragmentShader};
+use iced::futures::executor::block_on;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::ops::Range;
+use std::sync::Arc;
+
+// JSON-RPC's error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Couldn't read or write a message: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid header: {0}")]
+    InvalidHeader(String),
+    #[error("Message has no Content-Length")]
+    MissingLength,
+    #[error("Exited without being shut down")]
+    NotShutDown,
+}
+
+/// Answers the messages read from `input`, writing responses & diagnostics to `output`, until the
+/// client exits.
+pub fn run(mut input: impl BufRead, mut output: impl Write) -> Result<(), Error> {
+    let mut server = Server::default();
+
+    while let Some(message) = transport::read(&mut input)? {
+        let id = message.get("id").cloned();
+        let params = &message["params"];
+
+        match (message["method"].as_str(), id) {
+            (Some("exit"), _) => {
+                return if server.is_shut_down {
+                    Ok(())
+                } else {
+                    Err(Error::NotShutDown)
+                };
+            }
+            (Some(method), Some(id)) => {
+                let result = if server.is_shut_down {
+                    Err((INVALID_REQUEST, "The server is shut down".to_string()))
+                } else {
+                    server.request(method, params)
+                };
+
+                transport::write(&mut output, &response(id, result))?;
+            }
+            (Some(method), None) => {
+                for notification in server.notify(method, params) {
+                    transport::write(&mut output, &notification)?;
+                }
+            }
+            (None, _) if message.is_null() => {
+                let error = Err((PARSE_ERROR, "Message isn't JSON".to_string()));
+
+                transport::write(&mut output, &response(Value::Null, error))?;
+            }
+            // a response, but halo never sends requests
+            (None, _) => {}
+        }
+    }
+
+    Err(Error::NotShutDown)
+}
+
+/// The result of a request, or its error code & message.
+type Response = Result<Value, (i64, String)>;
+
+#[derive(Default)]
+struct Server {
+    /// The documents the client has open, by URI.
+    documents: HashMap<String, Document>,
+    is_shut_down: bool,
+}
+
+struct Document {
+    text: String,
+    /// From the last time the document parsed, so its symbols are still known while it's edited.
+    analysis: Option<Analysis>,
+}
+
+impl Server {
+    fn request(&mut self, method: &str, params: &Value) -> Response {
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": capabilities(),
+                "serverInfo": { "name": "halo", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => {
+                self.is_shut_down = true;
+                Ok(Value::Null)
+            }
+            "textDocument/completion" => self.completion(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/signatureHelp" => self.signature_help(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/references" => self.references(params, true),
+            "textDocument/documentHighlight" => self.references(params, false),
+            "textDocument/documentSymbol" => self.document_symbols(params),
+            "textDocument/formatting" => self.formatting(params),
+            _ => Err((METHOD_NOT_FOUND, format!("Unsupported method `{method}`"))),
+        }
+    }
+
+    /// Handles a notification, returning the notifications to send back.
+    fn notify(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+
+                vec![self.update(uri, text.to_string())]
+            }
+            // the whole document is sent with each change, so only the last one matters
+            "textDocument/didChange" => params["contentChanges"]
+                .as_array()
+                .and_then(|changes| changes.last()?["text"].as_str())
+                .map(|text| self.update(uri, text.to_string()))
+                .into_iter()
+                .collect(),
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+
+                vec![publish_diagnostics(uri, vec![])]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Replaces the text of a document, returning its diagnostics.
+    fn update(&mut self, uri: &str, text: String) -> Value {
+        let previous = self
+            .documents
+            .remove(uri)
+            .and_then(|document| document.analysis);
+        let analysis = Analysis::new(&text).ok().or(previous);
+        let diagnostics = diagnostics(&text);
+
+        self.documents
+            .insert(uri.to_string(), Document { text, analysis });
+
+        publish_diagnostics(uri, diagnostics)
+    }
+
+    fn completion(&self, params: &Value) -> Response {
+        let (document, offset) = self.position(params)?;
+        let line_start = document.text[..offset].rfind('\n').map_or(0, |i| i + 1);
+
+        let completions = language::complete(
+            document.analysis.as_ref(),
+            &document.text[line_start..offset],
+            offset,
+        )
+        .into_iter()
+        .map(|completion| {
+            json!({
+                "label": completion.label,
+                "kind": completion_kind(completion.kind),
+                "detail": completion.detail,
+            })
+        })
+        .collect::<Vec<_>>();
+
+        Ok(json!(completions))
+    }
+
+    fn hover(&self, params: &Value) -> Response {
+        let (document, offset) = self.position(params)?;
+
+        let Some(hover) = language::hover(document.analysis.as_ref(), &document.text, offset)
+        else {
+            return Ok(Value::Null);
+        };
+
+        let mut value = format!("```wgsl\n{}\n```", hover.signature);
+
+        if let Some(description) = hover.description {
+            value.push_str("\n\n");
+            value.push_str(&description);
+        }
+
+        Ok(json!({ "contents": { "kind": "markdown", "value": value } }))
+    }
+
+    fn signature_help(&self, params: &Value) -> Response {
+        let (document, offset) = self.position(params)?;
+
+        let Some(help) =
+            language::signature_help(document.analysis.as_ref(), &document.text, offset)
+        else {
+            return Ok(Value::Null);
+        };
+
+        let parameters = help
+            .parameters
+            .iter()
+            .map(|range| json!({ "label": &help.signature[range.clone()] }))
+            .collect::<Vec<_>>();
+
+        Ok(json!({
+            "signatures": [{ "label": help.signature, "parameters": parameters }],
+            "activeSignature": 0,
+            "activeParameter": help.active,
+        }))
+    }
+
+    fn definition(&self, params: &Value) -> Response {
+        let (document, offset) = self.position(params)?;
+
+        Ok(
+            language::definition(document.analysis.as_ref(), &document.text, offset).map_or(
+                Value::Null,
+                |definition| {
+                    json!({
+                        "uri": params["textDocument"]["uri"],
+                        "range": range(&document.text, definition),
+                    })
+                },
+            ),
+        )
+    }
+
+    /// The uses of the symbol at the position, as locations, or as highlights if not `locations`.
+    fn references(&self, params: &Value, locations: bool) -> Response {
+        let (document, offset) = self.position(params)?;
+
+        let references =
+            language::references(document.analysis.as_ref(), &document.text, offset)
+                .into_iter()
+                .map(|reference| {
+                    let range = range(&document.text, reference);
+
+                    if locations {
+                        json!({ "uri": params["textDocument"]["uri"], "range": range })
+                    } else {
+                        json!({ "range": range })
+                    }
+                })
+                .collect::<Vec<_>>();
+
+        Ok(json!(references))
+    }
+
+    fn document_symbols(&self, params: &Value) -> Response {
+        let document = self.document(params)?;
+
+        let symbols = document
+            .analysis
+            .as_ref()
+            .map_or(&[][..], Analysis::outline)
+            .iter()
+            .map(|item| {
+                let range = range(&document.text, item.offset..item.offset + item.name.len());
+
+                json!({
+                    "name": item.name,
+                    "detail": item.detail,
+                    "kind": symbol_kind(item.kind),
+                    "range": range,
+                    "selectionRange": range,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Ok(json!(symbols))
+    }
+
+    fn formatting(&self, params: &Value) -> Response {
+        let document = self.document(params)?;
+        let options = &params["options"];
+
+        let indent = if options["insertSpaces"].as_bool().unwrap_or(true) {
+            " ".repeat(options["tabSize"].as_u64().unwrap_or(4) as usize)
+        } else {
+            "\t".to_string()
+        };
+
+        let formatted = format::format(&document.text, &indent);
+
+        if formatted == document.text {
+            return Ok(json!([]));
+        }
+
+        Ok(json!([{
+            "range": range(&document.text, 0..document.text.len()),
+            "newText": formatted,
+        }]))
+    }
+
+    /// The open document a request is about.
+    fn document(&self, params: &Value) -> Result<&Document, (i64, String)> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .ok_or_else(|| (INVALID_PARAMS, "Missing the document's URI".to_string()))?;
+
+        self.documents
+            .get(uri)
+            .ok_or_else(|| (INVALID_PARAMS, format!("`{uri}` isn't open")))
+    }
+
+    /// The open document a request is about & the byte offset of the position in it.
+    fn position(&self, params: &Value) -> Result<(&Document, usize), (i64, String)> {
+        let document = self.document(params)?;
+        let offset = offset(&document.text, &params["position"])
+            .ok_or_else(|| (INVALID_PARAMS, "Invalid position".to_string()))?;
+
+        Ok((document, offset))
+    }
+}
+
+fn response(id: Value, result: Response) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }),
+    }
+}
+
+fn capabilities() -> Value {
+    json!({
+        // the whole document is sent with each change
+        "textDocumentSync": 1,
+        "completionProvider": { "triggerCharacters": ["."] },
+        "hoverProvider": true,
+        "signatureHelpProvider": { "triggerCharacters": ["(", ","] },
+        "definitionProvider": true,
+        "referencesProvider": true,
+        "documentHighlightProvider": true,
+        "documentSymbolProvider": true,
+        "documentFormattingProvider": true,
+    })
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+/// The errors of a shader as the viewer would build it.
+fn diagnostics(text: &str) -> Vec<Value> {
+    let shader: Arc<FragmentShader> = Arc::new(text.to_string());
+
+    let diagnostic = |error: Range<usize>, message: String| {
+        // errors in imported libraries are past the end of the shader
+        let error = error.start.min(text.len())..error.end.min(text.len());
+
+        json!({
+            "range": range(text, error),
+            "severity": 1,
+            "source": "halo",
+            "message": message,
+        })
+    };
+
+    match block_on(validation::validate(shader, Target::default())) {
+        Ok(_) => vec![],
+        Err(validation::Error::Parse { message, errors }) if !errors.is_empty() => errors
+            .into_iter()
+            .map(|(error, label)| {
+                let message = if label.is_empty() {
+                    message.clone()
+                } else {
+                    format!("{message}: {label}")
+                };
+
+                diagnostic(error, message)
+            })
+            .collect(),
+        // naga's validation errors have no spans once they're messages
+        Err(validation::Error::Parse { message, .. } | validation::Error::Validation(message)) => {
+            vec![diagnostic(0..0, message)]
+        }
+    }
+}
+
+/// The byte offset of an LSP position, whose character is counted in UTF-16 code units.
+fn offset(text: &str, position: &Value) -> Option<usize> {
+    let line = position["line"].as_u64()? as usize;
+    let character = position["character"].as_u64()? as usize;
+
+    let start = match line {
+        0 => 0,
+        line => text.match_indices('\n').nth(line - 1)?.0 + 1,
+    };
+    let line = text[start..].split('\n').next().unwrap_or_default();
+
+    let mut units = 0;
+    let column = line
+        .char_indices()
+        .find(|(_, c)| {
+            let is_past = units >= character;
+            units += c.len_utf16();
+            is_past
+        })
+        .map_or(line.len(), |(i, _)| i);
+
+    Some(start + column)
+}
+
+/// The LSP range of a byte range.
+fn range(text: &str, range: Range<usize>) -> Value {
+    json!({ "start": position(text, range.start), "end": position(text, range.end) })
+}
+
+/// The LSP position of a byte offset, whose character is counted in UTF-16 code units.
+fn position(text: &str, offset: usize) -> Value {
+    let mut line = 0;
+    let mut character = 0;
+
+    for (_, c) in text.char_indices().take_while(|(i, _)| *i < offset) {
+        if c == '\n' {
+            line += 1;
+            character = 0;
+        } else {
+            character += c.len_utf16();
+        }
+    }
+
+    json!({ "line": line, "character": character })
+}
+
+/// LSP's `CompletionItemKind` of a kind of symbol.
+fn completion_kind(kind: Kind) -> u8 {
+    match kind {
+        Kind::Local | Kind::Parameter | Kind::Global => 6,
+        Kind::Member => 5,
+        Kind::Constant => 21,
+        Kind::Function | Kind::Builtin => 3,
+        Kind::Struct => 22,
+        Kind::Type => 7,
+        Kind::Keyword => 14,
+    }
+}
+
+/// LSP's `SymbolKind` of a kind of symbol.
+fn symbol_kind(kind: Kind) -> u8 {
+    match kind {
+        Kind::Function | Kind::Builtin => 12,
+        Kind::Struct | Kind::Type => 23,
+        Kind::Constant => 14,
+        Kind::Member => 8,
+        Kind::Local | Kind::Parameter | Kind::Global | Kind::Keyword => 13,
+    }
+}
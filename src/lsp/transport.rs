@@ -0,0 +1,51 @@
+//! Reading & writing JSON-RPC messages framed by a `Content-Length` header.
+use crate::lsp::Error;
+use serde_json::Value;
+use std::io::{BufRead, Write};
+
+/// Reads the next message, or `None` once the input has ended.
+pub fn read(input: &mut impl BufRead) -> Result<Option<Value>, Error> {
+    let mut length = None;
+
+    loop {
+        let mut header = String::new();
+
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+
+        if header.is_empty() {
+            break;
+        }
+
+        // other headers, like `Content-Type`, have nothing halo needs
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(
+                    value
+                        .trim()
+                        .parse::<usize>()
+                        .map_err(|_| Error::InvalidHeader(header.to_string()))?,
+                );
+            }
+        }
+    }
+
+    let length = length.ok_or(Error::MissingLength)?;
+    let mut content = vec![0; length];
+    input.read_exact(&mut content)?;
+
+    // content that isn't JSON is answered with an error rather than ending the session
+    Ok(Some(serde_json::from_slice(&content).unwrap_or(Value::Null)))
+}
+
+pub fn write(output: &mut impl Write, message: &Value) -> Result<(), Error> {
+    let content = message.to_string();
+
+    write!(output, "Content-Length: {}\r\n\r\n{content}", content.len())?;
+    output.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,351 @@
+//! The command palette, which fuzzy searches every command by name.
+use crate::keymap::{self, Keymap};
+use crate::theme;
+use crate::widget::Element;
+use iced::keyboard::{KeyCode, Modifiers};
+use iced::widget::{button, column, container, row, scrollable, text, text_input};
+use iced::{Command, Length};
+use once_cell::sync::Lazy;
+
+static INPUT: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
+static ENTRIES: Lazy<scrollable::Id> = Lazy::new(scrollable::Id::unique);
+
+/// How many recently run commands are listed first.
+const MAX_RECENT: usize = 5;
+const MAX_HEIGHT: f32 = 400.0;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    QueryChanged(String),
+    Previous,
+    Next,
+    Submit,
+    Run(keymap::Command),
+    Close,
+}
+
+pub enum Event {
+    None,
+    Run(keymap::Command),
+}
+
+#[derive(Default)]
+pub struct Palette {
+    is_open: bool,
+    query: String,
+    /// The index of the selected entry among those matching the query.
+    selected: usize,
+    /// The commands run from the palette, most recent first.
+    recent: Vec<keymap::Command>,
+    /// The commands which did something when the palette was opened.
+    available: Vec<keymap::Command>,
+}
+
+impl Palette {
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    /// The commands run from the palette, most recent first.
+    pub fn recent(&self) -> &[keymap::Command] {
+        &self.recent
+    }
+
+    /// Restores the commands run from the palette, as saved in the preferences.
+    pub fn set_recent(&mut self, mut recent: Vec<keymap::Command>) {
+        recent.truncate(MAX_RECENT);
+        self.recent = recent;
+    }
+
+    /// Opens the palette with an empty query, listing the `available` commands, or closes it if
+    /// it's open.
+    pub fn toggle(&mut self, available: Vec<keymap::Command>) -> Command<Message> {
+        if self.is_open {
+            self.is_open = false;
+
+            return Command::none();
+        }
+
+        self.is_open = true;
+        self.available = available;
+        self.query.clear();
+        self.selected = 0;
+
+        Command::batch(vec![
+            text_input::focus(INPUT.clone()),
+            scrollable::snap_to(ENTRIES.clone(), scrollable::RelativeOffset::START),
+        ])
+    }
+
+    /// The message of a key press that navigates the palette while it's open.
+    pub fn key_press(&self, key: KeyCode, modifiers: Modifiers) -> Option<Message> {
+        if !self.is_open || !modifiers.is_empty() {
+            return None;
+        }
+
+        match key {
+            KeyCode::Escape => Some(Message::Close),
+            KeyCode::Up => Some(Message::Previous),
+            KeyCode::Down => Some(Message::Next),
+            _ => None,
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> (Event, Command<Message>) {
+        match message {
+            Message::QueryChanged(query) => {
+                self.query = query;
+                self.selected = 0;
+
+                return (Event::None, self.snap_to_selected());
+            }
+            Message::Previous => {
+                self.selected = self.selected.saturating_sub(1);
+
+                return (Event::None, self.snap_to_selected());
+            }
+            Message::Next => {
+                let last = self.matches().len().saturating_sub(1);
+                self.selected = (self.selected + 1).min(last);
+
+                return (Event::None, self.snap_to_selected());
+            }
+            Message::Submit => {
+                if let Some(command) = self.matches().get(self.selected) {
+                    return self.update(Message::Run(*command));
+                }
+            }
+            Message::Run(command) => {
+                self.is_open = false;
+
+                self.recent.retain(|recent| *recent != command);
+                self.recent.insert(0, command);
+                self.recent.truncate(MAX_RECENT);
+
+                return (Event::Run(command), Command::none());
+            }
+            Message::Close => {
+                self.is_open = false;
+            }
+        }
+
+        (Event::None, Command::none())
+    }
+
+    pub fn view(&self, keymap: &Keymap) -> Element<Message> {
+        let conflicts = keymap.conflicts();
+
+        let input = text_input("Type a command", &self.query)
+            .id(INPUT.clone())
+            .on_input(Message::QueryChanged)
+            .on_submit(Message::Submit)
+            .padding(8);
+
+        let entries = self
+            .matches()
+            .into_iter()
+            .enumerate()
+            .map(|(i, command)| {
+                let bindings = keymap
+                    .bindings(command)
+                    .map(|binding| binding.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                // bindings which run another command instead
+                let is_shadowed = conflicts.iter().any(|conflict| conflict.shadows(command));
+
+                let recent = text(if self.recent.contains(&command) {
+                    "recently used"
+                } else {
+                    ""
+                })
+                .size(12)
+                .style(theme::Text::Disabled);
+
+                let entry = row![
+                    text(command).size(14).width(Length::Fill),
+                    recent,
+                    text(bindings).size(14).style(if is_shadowed {
+                        theme::Text::Error
+                    } else {
+                        theme::Text::Disabled
+                    }),
+                ]
+                .spacing(10);
+
+                button(entry)
+                    .width(Length::Fill)
+                    .padding([4, 8])
+                    .style(if i == self.selected {
+                        theme::Button::SelectedEntry
+                    } else {
+                        theme::Button::Entry
+                    })
+                    .on_press(Message::Run(command))
+                    .into()
+            })
+            .collect::<Vec<_>>();
+
+        let entries: Element<Message> = if entries.is_empty() {
+            text("No matching commands")
+                .size(14)
+                .style(theme::Text::Disabled)
+                .into()
+        } else {
+            scrollable(column(entries)).id(ENTRIES.clone()).into()
+        };
+
+        let conflicts = conflicts
+            .iter()
+            .map(|conflict| text(conflict).size(12).style(theme::Text::Error).into())
+            .collect::<Vec<_>>();
+
+        container(
+            column![
+                input,
+                column(conflicts).spacing(2),
+                container(entries).max_height(MAX_HEIGHT)
+            ]
+            .spacing(5)
+            .max_width(600),
+        )
+        .padding(10)
+        .style(theme::Container::Tooltip)
+        .into()
+    }
+
+    /// The available commands matching the query, best first. Recently run commands come before
+    /// others that match as well.
+    fn matches(&self) -> Vec<keymap::Command> {
+        let commands = self
+            .recent
+            .iter()
+            .copied()
+            .chain(
+                keymap::Command::ALL
+                    .into_iter()
+                    .filter(|command| *command != keymap::Command::CommandPalette)
+                    .filter(|command| !self.recent.contains(command)),
+            )
+            .filter(|command| self.available.contains(command));
+
+        let mut matches = commands
+            .filter_map(|command| Some((score(&self.query, &command.to_string())?, command)))
+            .collect::<Vec<_>>();
+
+        // stable, so equal scores keep their order
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        matches.into_iter().map(|(_, command)| command).collect()
+    }
+
+    /// Scrolls the entries so the selected one is in view.
+    fn snap_to_selected(&self) -> Command<Message> {
+        let last = self.matches().len().saturating_sub(1).max(1);
+
+        scrollable::snap_to(
+            ENTRIES.clone(),
+            scrollable::RelativeOffset {
+                x: 0.0,
+                y: self.selected as f32 / last as f32,
+            },
+        )
+    }
+}
+
+/// How well `query` matches `name`, if its characters all appear in `name` in order, ignoring
+/// case & whitespace. Characters starting a word or following the previous match score higher.
+fn score(query: &str, name: &str) -> Option<usize> {
+    let name = name.to_lowercase().chars().collect::<Vec<_>>();
+    let mut position = 0;
+    let mut score = 0;
+
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = position + name[position..].iter().position(|n| *n == c)?;
+
+        score += 1;
+
+        if found == 0 || !name[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+
+        if position > 0 && found == position {
+            score += 2;
+        }
+
+        position = found + 1;
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keymap::Command as C;
+
+    fn open(recent: &[C]) -> Palette {
+        let mut palette = Palette::default();
+        let _ = palette.toggle(C::ALL.to_vec());
+
+        for command in recent {
+            let _ = palette.update(Message::Run(*command));
+        }
+
+        palette
+    }
+
+    #[test]
+    fn scores_characters_in_order() {
+        assert!(score("vld", "Validate").is_some());
+        assert!(score("Save S", "save shader").is_some());
+        assert_eq!(score("dv", "Validate"), None);
+        assert_eq!(score("xyz", "Validate"), None);
+        assert_eq!(score("", "Validate"), Some(0));
+    }
+
+    #[test]
+    fn scores_word_starts_higher() {
+        // `t` & `s` start the words of "toggle stats", but not of "outcast"
+        assert_eq!(score("ts", "toggle stats"), Some(6));
+        assert_eq!(score("ts", "outcast"), Some(2));
+        // `t` follows `s` in "stats", but not in "sort"
+        assert_eq!(score("st", "stats"), Some(6));
+        assert_eq!(score("st", "sort"), Some(4));
+    }
+
+    #[test]
+    fn lists_recent_commands_first() {
+        let mut palette = open(&[C::ExportStats, C::Validate]);
+
+        assert_eq!(
+            palette.matches()[..3],
+            [C::Validate, C::ExportStats, C::New]
+        );
+
+        // other commands still come first when they match better
+        let _ = palette.update(Message::QueryChanged("new".to_string()));
+        assert_eq!(palette.matches()[0], C::New);
+    }
+
+    #[test]
+    fn keeps_the_most_recent_commands() {
+        let palette = open(&C::ALL[..7]);
+
+        assert_eq!(palette.recent().len(), MAX_RECENT);
+        assert_eq!(palette.recent()[0], C::ALL[6]);
+        assert!(!palette.recent().contains(&C::ALL[0]));
+
+        let mut palette = Palette::default();
+        palette.set_recent(C::ALL.to_vec());
+        assert_eq!(palette.recent(), &C::ALL[..MAX_RECENT]);
+    }
+
+    #[test]
+    fn leaves_out_unavailable_commands() {
+        let mut palette = open(&[C::ExportStats]);
+        let _ = palette.toggle(vec![C::Validate, C::ToggleStats]);
+
+        assert_eq!(palette.matches(), [C::Validate, C::ToggleStats]);
+    }
+}
@@ -1,8 +1,10 @@
+use crate::keymap;
+use crate::viewer::device::Profile;
+use crate::FragmentShader;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use std::path::PathBuf;
 use std::sync::Arc;
-use crate::FragmentShader;
 
 const PATH: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/preferences.json");
 
@@ -11,6 +13,68 @@ pub struct Preferences {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_shader_path: Option<PathBuf>,
     pub auto_validate: bool,
+    #[serde(default)]
+    pub target_profile: Profile,
+    #[serde(default)]
+    pub indentation: Indentation,
+    #[serde(default)]
+    pub format_on_save: bool,
+    /// The commands run from the command palette, most recent first.
+    #[serde(default)]
+    pub recent_commands: Vec<keymap::Command>,
+}
+
+/// How the editor indents code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Indentation {
+    /// The number of columns in a level of indentation.
+    pub width: usize,
+    /// Whether to indent with spaces rather than tabs.
+    pub spaces: bool,
+}
+
+impl Indentation {
+    pub const ALL: [Indentation; 4] = [
+        Indentation::spaces(2),
+        Indentation::spaces(4),
+        Indentation::spaces(8),
+        Indentation {
+            width: 4,
+            spaces: false,
+        },
+    ];
+
+    const fn spaces(width: usize) -> Self {
+        Self {
+            width,
+            spaces: true,
+        }
+    }
+
+    /// A single level of indentation.
+    pub fn unit(&self) -> String {
+        if self.spaces {
+            " ".repeat(self.width)
+        } else {
+            "\t".to_string()
+        }
+    }
+}
+
+impl Default for Indentation {
+    fn default() -> Self {
+        Self::spaces(4)
+    }
+}
+
+impl Display for Indentation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.spaces {
+            write!(f, "{} spaces", self.width)
+        } else {
+            write!(f, "Tabs")
+        }
+    }
 }
 
 pub async fn load() -> Result<(Preferences, Arc<FragmentShader>), Error> {
@@ -1,4 +1,7 @@
-use iced::widget::{button, checkbox, container, pane_grid, scrollable, text, text_editor};
+use iced::widget::overlay::menu;
+use iced::widget::{
+    button, checkbox, container, pane_grid, pick_list, scrollable, text, text_editor, text_input,
+};
 use iced::{application, Color};
 
 //const OFF_WHITE: Color = Color::from_rgb8(242, 239, 233);
@@ -87,6 +90,12 @@ pub enum Container {
     Tooltip,
     Controls,
     Error,
+    /// The editor's line numbers.
+    Gutter,
+    /// The editor's text, whose background is drawn here so the current line shows through it.
+    Editor,
+    /// The current line in the editor & its gutter.
+    CurrentLine,
     #[default]
     None,
 }
@@ -120,6 +129,27 @@ impl container::StyleSheet for Theme {
                 border_width: 1.0,
                 border_color: palette.error,
             },
+            Container::Gutter => container::Appearance {
+                text_color: Some(palette.disabled),
+                background: Some(palette.background.into()),
+                border_radius: Default::default(),
+                border_width: 0.0,
+                border_color: Default::default(),
+            },
+            Container::Editor => container::Appearance {
+                text_color: Some(palette.text),
+                background: Some(palette.background.into()),
+                border_radius: Default::default(),
+                border_width: 0.0,
+                border_color: Default::default(),
+            },
+            Container::CurrentLine => container::Appearance {
+                text_color: Some(palette.text),
+                background: Some(palette.base_darkest.into()),
+                border_radius: Default::default(),
+                border_width: 0.0,
+                border_color: Default::default(),
+            },
         }
     }
 }
@@ -129,6 +159,8 @@ pub enum Text {
     #[default]
     Primary,
     Error,
+    Disabled,
+    Color(Color),
 }
 
 impl text::StyleSheet for Theme {
@@ -144,6 +176,10 @@ impl text::StyleSheet for Theme {
             Text::Error => text::Appearance {
                 color: Some(palette.error),
             },
+            Text::Disabled => text::Appearance {
+                color: Some(palette.disabled),
+            },
+            Text::Color(color) => text::Appearance { color: Some(color) },
         }
     }
 }
@@ -188,17 +224,27 @@ impl pane_grid::StyleSheet for Theme {
 pub enum Button {
     #[default]
     Control,
+    /// An entry of the command palette.
+    Entry,
+    /// The command palette's selected entry.
+    SelectedEntry,
 }
 
 impl button::StyleSheet for Theme {
     type Style = Button;
 
-    fn active(&self, _style: &Self::Style) -> button::Appearance {
+    fn active(&self, style: &Self::Style) -> button::Appearance {
         let palette = self.palette();
 
+        let background = match style {
+            Button::Control => Some(palette.base.into()),
+            Button::Entry => None,
+            Button::SelectedEntry => Some(palette.base_darker.into()),
+        };
+
         button::Appearance {
             shadow_offset: Default::default(),
-            background: Some(palette.base.into()),
+            background,
             border_radius: 2.0.into(),
             border_width: 0.0,
             border_color: Default::default(),
@@ -206,12 +252,17 @@ impl button::StyleSheet for Theme {
         }
     }
 
-    fn hovered(&self, _style: &Self::Style) -> button::Appearance {
+    fn hovered(&self, style: &Self::Style) -> button::Appearance {
         let palette = self.palette();
 
+        let background = match style {
+            Button::Control => palette.base_lighter,
+            Button::Entry | Button::SelectedEntry => palette.base,
+        };
+
         button::Appearance {
             shadow_offset: Default::default(),
-            background: Some(palette.base_lighter.into()),
+            background: Some(background.into()),
             border_radius: 2.0.into(),
             border_width: 0.0,
             border_color: Default::default(),
@@ -257,17 +308,71 @@ impl checkbox::StyleSheet for Theme {
     }
 }
 
+#[derive(Default, Clone)]
+pub struct PickList;
+
+impl pick_list::StyleSheet for Theme {
+    type Style = PickList;
+
+    fn active(&self, _style: &Self::Style) -> pick_list::Appearance {
+        let palette = self.palette();
+
+        pick_list::Appearance {
+            text_color: palette.text,
+            placeholder_color: palette.disabled,
+            handle_color: palette.text,
+            background: palette.base.into(),
+            border_radius: 2.0.into(),
+            border_width: 0.0,
+            border_color: Default::default(),
+        }
+    }
+
+    fn hovered(&self, style: &Self::Style) -> pick_list::Appearance {
+        pick_list::Appearance {
+            background: self.palette().base_lighter.into(),
+            ..self.active(style)
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct Menu;
+
+impl From<PickList> for Menu {
+    fn from(_pick_list: PickList) -> Self {
+        Menu
+    }
+}
+
+impl menu::StyleSheet for Theme {
+    type Style = Menu;
+
+    fn appearance(&self, _style: &Self::Style) -> menu::Appearance {
+        let palette = self.palette();
+
+        menu::Appearance {
+            text_color: palette.text,
+            background: palette.base_darker.into(),
+            border_width: BORDER_WIDTH,
+            border_radius: BORDER_RADIUS.into(),
+            border_color: palette.base_darkest,
+            selected_text_color: palette.text,
+            selected_background: palette.base.into(),
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct Editor;
 
 impl text_editor::StyleSheet for Theme {
     type Style = Editor;
 
+    // the background is drawn by `Container::Editor`, under the current line's highlight
     fn active(&self, _style: &Self::Style) -> text_editor::Appearance {
-        let palette = self.palette();
-
         text_editor::Appearance {
-            background: palette.background.into(),
+            background: Color::TRANSPARENT.into(),
             border_radius: Default::default(),
             border_width: 0.0,
             border_color: Default::default(),
@@ -276,7 +381,7 @@ impl text_editor::StyleSheet for Theme {
 
     fn focused(&self, _style: &Self::Style) -> text_editor::Appearance {
         text_editor::Appearance {
-            background: self.palette().background.into(),
+            background: Color::TRANSPARENT.into(),
             border_radius: Default::default(),
             border_width: 0.0,
             border_color: Default::default(),
@@ -309,6 +414,57 @@ impl text_editor::StyleSheet for Theme {
     }
 }
 
+#[derive(Default)]
+pub struct TextInput;
+
+impl text_input::StyleSheet for Theme {
+    type Style = TextInput;
+
+    fn active(&self, _style: &Self::Style) -> text_input::Appearance {
+        let palette = self.palette();
+
+        text_input::Appearance {
+            background: palette.background.into(),
+            border_radius: BORDER_RADIUS.into(),
+            border_width: 1.0,
+            border_color: palette.base_darker,
+            icon_color: palette.disabled,
+        }
+    }
+
+    fn focused(&self, _style: &Self::Style) -> text_input::Appearance {
+        let palette = self.palette();
+
+        text_input::Appearance {
+            background: palette.background.into(),
+            border_radius: BORDER_RADIUS.into(),
+            border_width: 1.0,
+            border_color: palette.base_lighter,
+            icon_color: palette.disabled,
+        }
+    }
+
+    fn placeholder_color(&self, _style: &Self::Style) -> Color {
+        self.palette().disabled
+    }
+
+    fn value_color(&self, _style: &Self::Style) -> Color {
+        self.palette().text
+    }
+
+    fn disabled_color(&self, _style: &Self::Style) -> Color {
+        self.palette().disabled
+    }
+
+    fn selection_color(&self, _style: &Self::Style) -> Color {
+        self.palette().base_darker
+    }
+
+    fn disabled(&self, style: &Self::Style) -> text_input::Appearance {
+        self.active(style)
+    }
+}
+
 #[derive(Default)]
 pub struct Scrollable;
 
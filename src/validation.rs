@@ -0,0 +1,153 @@
+use crate::viewer::device::Target;
+use crate::viewer::library;
+use crate::viewer::probe;
+use crate::viewer::shader::{Reflection, Resource};
+use crate::FragmentShader;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// The uniforms & helpers every shader is parsed after.
+const PRELUDE: &str = include_str!("viewer/shaders/uniforms.wgsl");
+
+/// How far the spans of a module from [`parse`] are past those of the shader it was parsed from,
+/// which is the length of the prelude before it.
+pub(crate) const OFFSET: usize = PRELUDE.len() + 1;
+
+/// Parses a shader as the viewer builds it: after the prelude, with its probes erased & the
+/// libraries it imports appended. The spans of the module are of the erased shader.
+pub(crate) fn parse(shader: &str) -> Result<(naga::Module, probe::Erased), Error> {
+    let imports = library::imports(shader);
+    let erased = probe::erase(shader);
+
+    //parse separately so we can show errors instead of panicking on pipeline creation
+    let concat_shader = format!("{PRELUDE}\n{}{imports}", erased.source);
+
+    match naga::front::wgsl::parse_str(&concat_shader) {
+        Ok(module) => Ok((module, erased)),
+        Err(parse_error) => Err(Error::Parse {
+            message: parse_error.message().to_string(),
+            errors: parse_error
+                .labels()
+                .filter_map(|(span, err)| {
+                    // so errors point into the editor's text
+                    let range = span.to_range()?;
+                    let start = range.start.checked_sub(OFFSET)?;
+                    let end = range.end - OFFSET;
+
+                    Some((
+                        erased.to_shader(start)..erased.to_shader(end),
+                        err.to_string(),
+                    ))
+                })
+                .collect::<Vec<_>>(),
+        }),
+    }
+}
+
+/// Checks that a WGSL shader will build in the viewer on a device matching `target`.
+pub async fn validate(
+    shader: Arc<FragmentShader>,
+    target: Target,
+) -> Result<Arc<FragmentShader>, Error> {
+    let imports = library::imports(&shader);
+    let (parsed, _) = parse(&shader)?;
+
+    let info =
+        naga::valid::Validator::new(naga::valid::ValidationFlags::default(), target.capabilities)
+            .validate(&parsed)
+            .map_err(|err| Error::Validation(err.to_string()))?;
+
+    if !parsed
+        .entry_points
+        .iter()
+        .any(|ep| ep.stage == naga::ShaderStage::Fragment && ep.name == "fs_main")
+    {
+        return Err(Error::Validation(
+            "Shader must have a `@fragment fn fs_main`".to_string(),
+        ));
+    }
+
+    validate_limits(&parsed, &target)?;
+
+    let reflection = Reflection::new(&shader, &parsed, &info).map_err(Error::Validation)?;
+    validate_buffers(&reflection, &target)?;
+
+    let instrumented = probe::instrument(&shader, &parsed, &info).map_err(Error::Validation)?;
+
+    if !instrumented.probes.is_empty() {
+        probe::validate(
+            &format!("{}{imports}", instrumented.source),
+            target.capabilities,
+        )
+        .map_err(Error::Validation)?;
+    }
+
+    Ok(shader)
+}
+
+/// Checks the parts of the module that naga doesn't know about, but which would fail on pipeline
+/// creation with the target's limits.
+fn validate_limits(module: &naga::Module, target: &Target) -> Result<(), Error> {
+    let limits = &target.limits;
+
+    for entry_point in module
+        .entry_points
+        .iter()
+        .filter(|ep| ep.stage == naga::ShaderStage::Compute)
+    {
+        let [x, y, z] = entry_point.workgroup_size;
+        let max = [
+            limits.max_compute_workgroup_size_x,
+            limits.max_compute_workgroup_size_y,
+            limits.max_compute_workgroup_size_z,
+        ];
+
+        if x > max[0] || y > max[1] || z > max[2] {
+            return Err(Error::Validation(format!(
+                "Workgroup size of `{}` ({x}, {y}, {z}) exceeds the target's maximum of {max:?}",
+                entry_point.name,
+            )));
+        }
+
+        let invocations = x.saturating_mul(y).saturating_mul(z);
+        if invocations > limits.max_compute_invocations_per_workgroup {
+            return Err(Error::Validation(format!(
+                "`{}` has {invocations} invocations per workgroup, but the target only supports {}",
+                entry_point.name, limits.max_compute_invocations_per_workgroup,
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the storage buffers the shader declares, sized by their `//#length` directives,
+/// can be created & bound on the target.
+fn validate_buffers(reflection: &Reflection, target: &Target) -> Result<(), Error> {
+    let max =
+        u64::from(target.limits.max_storage_buffer_binding_size).min(target.limits.max_buffer_size);
+
+    for (binding, resource) in &reflection.resources {
+        if let Resource::Buffer { size } = resource {
+            if *size > max {
+                return Err(Error::Validation(format!(
+                    "The storage buffer at binding {binding} needs {size} bytes, but the target \
+                    only supports {max}. Lower its `//#length`."
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(thiserror::Error, Clone, Debug)]
+pub enum Error {
+    #[error("Shader parsing error")]
+    Parse {
+        message: String,
+        errors: Vec<(Range<usize>, String)>,
+    },
+    #[error("Validation error: {0}")]
+    Validation(String),
+}
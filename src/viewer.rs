@@ -1,82 +1,731 @@
+pub(crate) mod audio;
+mod cache;
+pub(crate) mod camera;
+pub(crate) mod device;
+mod directive;
+pub mod headless;
+mod inspector;
+mod keyboard;
+pub(crate) mod library;
+pub(crate) mod mesh;
+mod offscreen;
 mod pipeline;
 mod primitive;
-mod uniforms;
+pub(crate) mod probe;
+mod range;
+mod readback;
+mod resources;
+pub(crate) mod settings;
+pub(crate) mod shader;
+mod stats;
+mod texture;
+mod timer;
+pub mod uniforms;
 
-use crate::viewer::primitive::Primitive;
-use crate::viewer::uniforms::Uniforms;
-use crate::{widget, FragmentShader, Message};
+pub use self::audio::{decode as decode_audio, Track};
+pub use self::device::{Device, Target};
+pub use self::mesh::Mesh;
+pub use self::probe::Reading;
+pub use self::settings::Settings;
+
+use crate::keymap;
+use crate::theme;
+use crate::viewer::audio::{Player, Track};
+use crate::viewer::device::Device;
+use crate::viewer::inspector::{Loupe, Sample, SharedSample};
+use crate::viewer::keyboard::Keyboard;
+use crate::viewer::mesh::Mesh;
+use crate::viewer::primitive::{self, Primitive};
+use crate::viewer::range::{Counts, SharedCounts};
+use crate::viewer::settings::{Blend, Clear, Msaa, Scale, Settings};
+use crate::viewer::stats::{SharedTimings, Stats};
+use crate::viewer::uniforms::{self, Mouse, Uniforms};
+use crate::widget;
+use crate::widget::Element;
 use iced::advanced::Shell;
 use iced::event::Status;
-use iced::widget::shader::Event;
-use iced::widget::Shader;
+use iced::widget::shader::Event as ShaderEvent;
+use iced::widget::{
+    button, canvas, checkbox, column, container, pick_list, row, text, tooltip, Shader,
+};
 use iced::window::RedrawRequest;
-use iced::{mouse, Length, Point, Rectangle};
+use iced::{clipboard, mouse, system, Alignment, Command, Length, Point, Rectangle};
+use once_cell::sync::OnceCell;
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use widget::pane_grid;
 
+/// The longest time between two clicks which still counts as a double click.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+#[derive(Clone, Debug)]
+pub enum Message {
+    DeviceDetected(Device),
+    SystemInformation(system::Information),
+    OpenMesh,
+    MeshOpened(Result<Arc<Mesh>, mesh::Error>),
+    OpenAudio,
+    AudioOpened(Result<Arc<Track>, audio::Error>),
+    SettingsChanged(Settings),
+    ToggleStats(bool),
+    /// A frame was presented while the stats, inspector or range check overlay is shown.
+    Frame(Instant),
+    ExportStats,
+    StatsExported(Result<PathBuf, stats::Error>),
+    ToggleInspector(bool),
+    /// Copies the inspected pixel's values to the clipboard.
+    CopySample,
+    ToggleRangeCheck(bool),
+}
+
+pub enum Event {
+    None,
+    DeviceDetected(Device),
+    /// The render settings were changed from the viewer, & should be written to the shader.
+    SettingsChanged(Settings),
+    /// The shader's probes were read at the inspected pixel, or cleared when inspection stopped.
+    Probed(Vec<probe::Reading>),
+}
+
 pub struct Viewer {
+    /// Keeps this viewer's render state apart from other viewers'.
+    id: primitive::Id,
     start: Instant,
-    pub last_valid_shader: Arc<FragmentShader>,
-    pub version: usize,
+    shader: Arc<shader::Shader>,
+    settings: Settings,
+    /// The mesh drawn when the shader has a custom vertex shader.
+    mesh: Arc<Mesh>,
+    /// Written to `halo_audio` each frame, & played from when it's loaded.
+    audio: Option<Arc<Track>>,
+    /// Stops playing the audio when it's replaced.
+    player: Option<Player>,
+    /// Set by the primitive once the pipeline is first prepared on the render thread.
+    device: Arc<OnceCell<Device>>,
+    system: Option<system::Information>,
+    is_loading: bool,
+    show_stats: bool,
+    stats: Stats,
+    /// Written by the primitive on the render thread every frame.
+    timings: SharedTimings,
+    is_inspecting: bool,
+    sample: Option<Sample>,
+    /// Written by the primitive once the pixels under the cursor have been read back.
+    shared_sample: SharedSample,
+    /// Written by the primitive once the shader's probes have been read back.
+    shared_probes: probe::SharedValues,
+    is_checking_range: bool,
+    counts: Option<Counts>,
+    /// Written by the primitive once the range check's counts have been read back.
+    shared_counts: SharedCounts,
 }
 
 impl Default for Viewer {
     fn default() -> Self {
         Self {
+            id: primitive::Id::default(),
             start: Instant::now(),
-            last_valid_shader: Arc::new(
-                include_str!("viewer/shaders/default_frag.wgsl").to_string(),
-            ),
-            version: 0,
+            shader: Arc::new(shader::Shader::new(include_str!(
+                "viewer/shaders/default_frag.wgsl"
+            ))),
+            settings: Settings::default(),
+            mesh: Arc::new(Mesh::cube()),
+            audio: None,
+            player: None,
+            device: Arc::new(OnceCell::new()),
+            system: None,
+            is_loading: false,
+            show_stats: false,
+            stats: Stats::default(),
+            timings: SharedTimings::default(),
+            is_inspecting: false,
+            sample: None,
+            shared_sample: SharedSample::default(),
+            shared_probes: probe::SharedValues::default(),
+            is_checking_range: false,
+            counts: None,
+            shared_counts: SharedCounts::default(),
         }
     }
 }
 
 impl Viewer {
-    pub fn content(&self) -> pane_grid::Content<Message> {
+    pub fn set_shader(&mut self, shader: &str) {
+        self.shader = Arc::new(shader::Shader::new(shader));
+        self.settings = Settings::from_source(shader);
+    }
+
+    /// The message of a keyboard shortcut, if it's one of the viewer's.
+    pub(crate) fn shortcut(&self, command: keymap::Command) -> Option<Message> {
+        match command {
+            keymap::Command::OpenMesh => Some(Message::OpenMesh),
+            keymap::Command::OpenAudio => Some(Message::OpenAudio),
+            keymap::Command::ToggleInspector => Some(Message::ToggleInspector(!self.is_inspecting)),
+            keymap::Command::CopySample => Some(Message::CopySample),
+            keymap::Command::ToggleRangeCheck => {
+                Some(Message::ToggleRangeCheck(!self.is_checking_range))
+            }
+            keymap::Command::ToggleStats => Some(Message::ToggleStats(!self.show_stats)),
+            keymap::Command::ExportStats if self.show_stats => Some(Message::ExportStats),
+            _ => None,
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> (Event, Command<Message>) {
+        match message {
+            Message::DeviceDetected(device) => {
+                return (Event::DeviceDetected(device), Command::none());
+            }
+            Message::SystemInformation(information) => {
+                self.system = Some(information);
+            }
+            Message::OpenMesh => {
+                if !self.is_loading {
+                    self.is_loading = true;
+
+                    return (
+                        Event::None,
+                        Command::perform(mesh::open(), Message::MeshOpened),
+                    );
+                }
+            }
+            Message::MeshOpened(result) => {
+                match result {
+                    Ok(mesh) => {
+                        self.mesh = mesh;
+                    }
+                    Err(e) => {
+                        //TODO loading error msg
+                        println!("Error loading mesh: {e:?}");
+                    }
+                }
+
+                self.is_loading = false;
+            }
+            Message::OpenAudio => {
+                if !self.is_loading {
+                    self.is_loading = true;
+
+                    return (
+                        Event::None,
+                        Command::perform(audio::open(), Message::AudioOpened),
+                    );
+                }
+            }
+            Message::AudioOpened(result) => {
+                match result {
+                    Ok(track) => {
+                        // the track is played from the start, so restart the time it's sampled at
+                        self.start = Instant::now();
+                        // stop the last track before opening another output stream
+                        self.player = None;
+                        self.player = Player::play(&track)
+                            .map_err(|e| println!("Error playing audio: {e:?}"))
+                            .ok();
+                        self.audio = Some(track);
+                    }
+                    Err(e) => {
+                        //TODO loading error msg
+                        println!("Error loading audio: {e:?}");
+                    }
+                }
+
+                self.is_loading = false;
+            }
+            Message::SettingsChanged(settings) => {
+                self.settings = settings;
+
+                return (Event::SettingsChanged(settings), Command::none());
+            }
+            Message::ToggleStats(show) => {
+                self.show_stats = show;
+                self.stats.clear();
+            }
+            Message::Frame(now) => {
+                let mut event = Event::None;
+
+                if self.show_stats {
+                    let timings = self
+                        .timings
+                        .lock()
+                        .map(|timings| *timings)
+                        .unwrap_or_default();
+
+                    self.stats.push(now, timings);
+                }
+
+                if self.is_inspecting {
+                    if let Some(sample) = self
+                        .shared_sample
+                        .lock()
+                        .ok()
+                        .and_then(|mut sample| sample.take())
+                    {
+                        self.sample = Some(sample);
+                    }
+
+                    if let Some(values) = self
+                        .shared_probes
+                        .lock()
+                        .ok()
+                        .and_then(|mut values| values.take())
+                    {
+                        event = Event::Probed(probe::read(&self.shader.probes, &values));
+                    }
+                }
+
+                if self.is_checking_range {
+                    if let Some(counts) = self
+                        .shared_counts
+                        .lock()
+                        .ok()
+                        .and_then(|mut counts| counts.take())
+                    {
+                        self.counts = Some(counts);
+                    }
+                }
+
+                return (event, Command::none());
+            }
+            Message::ExportStats => {
+                return (
+                    Event::None,
+                    Command::perform(stats::export(self.stats.to_csv()), Message::StatsExported),
+                );
+            }
+            Message::StatsExported(result) => {
+                //TODO export error msg
+                if let Err(e) = result {
+                    println!("Error exporting stats: {e:?}");
+                }
+            }
+            Message::ToggleInspector(is_inspecting) => {
+                self.is_inspecting = is_inspecting;
+                self.sample = None;
+
+                if !is_inspecting {
+                    return (Event::Probed(vec![]), Command::none());
+                }
+            }
+            Message::CopySample => {
+                if let Some(sample) = &self.sample {
+                    return (Event::None, clipboard::write(sample.to_string()));
+                }
+            }
+            Message::ToggleRangeCheck(is_checking_range) => {
+                self.is_checking_range = is_checking_range;
+                self.counts = None;
+            }
+        }
+
+        (Event::None, Command::none())
+    }
+
+    /// The shader on its own, without halo's controls & overlays.
+    pub fn preview<Theme>(&self) -> iced::Element<'_, Message, iced::Renderer<Theme>> {
         Shader::new(self)
             .width(Length::Fill)
             .height(Length::Fill)
             .into()
     }
+
+    pub(crate) fn content(&self) -> pane_grid::Content<crate::Message> {
+        let mut content = column![self.preview()];
+
+        if self.is_inspecting {
+            content = content.push(self.inspector_view());
+        }
+
+        if self.is_checking_range {
+            content = content.push(self.range_view());
+        }
+
+        if self.show_stats {
+            content = content.push(self.stats_view());
+        }
+
+        let content: Element<Message> = content.into();
+
+        pane_grid::Content::new(content.map(crate::Message::Viewer)).title_bar(
+            pane_grid::TitleBar::new(self.title_bar().map(crate::Message::Viewer)),
+        )
+    }
+
+    fn title_bar(&self) -> Element<Message> {
+        let adapter = self.system.as_ref().map_or_else(
+            || "Detecting adapter..".to_string(),
+            |info| format!("{} ({})", info.graphics_adapter, info.graphics_backend),
+        );
+
+        let mesh = tooltip(
+            button(text(format!("Mesh: {}", self.mesh.name)).size(14)).on_press(Message::OpenMesh),
+            "Load an .obj or .gltf mesh for custom vertex shaders",
+            tooltip::Position::Bottom,
+        )
+        .padding(10)
+        .style(theme::Container::Tooltip);
+
+        let audio = tooltip(
+            button(
+                text(format!(
+                    "Audio: {}",
+                    self.audio.as_ref().map_or("None", |track| &track.name)
+                ))
+                .size(14),
+            )
+            .on_press(Message::OpenAudio),
+            "Load a .wav, .flac or .ogg file to play & read from `halo_audio`",
+            tooltip::Position::Bottom,
+        )
+        .padding(10)
+        .style(theme::Container::Tooltip);
+
+        let settings = self.settings;
+        let render_settings = row![
+            setting(&Scale::ALL, settings.scale, move |scale| Settings {
+                scale,
+                ..settings
+            }),
+            setting(&Msaa::ALL, settings.msaa, move |msaa| Settings {
+                msaa,
+                ..settings
+            }),
+            setting(&Blend::ALL, settings.blend, move |blend| Settings {
+                blend,
+                ..settings
+            }),
+            setting(&Clear::ALL, settings.clear, move |clear| Settings {
+                clear,
+                ..settings
+            }),
+        ]
+        .spacing(5);
+
+        container(
+            row![
+                container(text(adapter).size(14)).width(Length::Fill),
+                render_settings,
+                mesh,
+                audio,
+                checkbox("Inspect", self.is_inspecting, Message::ToggleInspector).text_size(14),
+                checkbox("Range", self.is_checking_range, Message::ToggleRangeCheck).text_size(14),
+                checkbox("Stats", self.show_stats, Message::ToggleStats).text_size(14),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+        )
+        .width(Length::Fill)
+        .padding([10, 15, 10, 15])
+        .style(theme::Container::Controls)
+        .into()
+    }
+
+    fn inspector_view(&self) -> Element<Message> {
+        let panel: Element<Message> = if let Some(sample) = &self.sample {
+            let [x, y] = sample.pixel;
+            let [u, v] = sample.uv();
+            let [r, g, b, a] = sample.color();
+            let [r8, g8, b8, a8] = sample.rgba8();
+
+            row![
+                canvas(Loupe(sample)).width(110).height(110),
+                column![
+                    text(format!("Pixel: ({x}, {y})")).size(14),
+                    text(format!("UV: ({u:.4}, {v:.4})")).size(14),
+                    text(format!("RGBA: ({r:.4}, {g:.4}, {b:.4}, {a:.4})")).size(14),
+                    text(format!("RGBA8: ({r8}, {g8}, {b8}, {a8})")).size(14),
+                    text("Click to copy").size(12).style(theme::Text::Disabled),
+                ]
+                .spacing(5),
+            ]
+            .spacing(15)
+            .align_items(Alignment::Center)
+            .into()
+        } else {
+            text("Hover over the shader to inspect a pixel")
+                .size(14)
+                .into()
+        };
+
+        container(panel)
+            .width(Length::Fill)
+            .padding([10, 15, 10, 15])
+            .style(theme::Container::Controls)
+            .into()
+    }
+
+    fn range_view(&self) -> Element<Message> {
+        let panel: Element<Message> = if let Some(counts) = &self.counts {
+            let legend = counts
+                .legend()
+                .into_iter()
+                .map(|(label, count, color)| {
+                    row![
+                        text("■").size(14).style(theme::Text::Color(color)),
+                        text(format!("{label}: {count} ({:.2}%)", counts.percent(count))).size(14),
+                    ]
+                    .spacing(5)
+                    .into()
+                })
+                .collect::<Vec<Element<Message>>>();
+
+            row(legend)
+                .spacing(20)
+                .align_items(Alignment::Center)
+                .into()
+        } else {
+            text("Checking frame..").size(14).into()
+        };
+
+        container(panel)
+            .width(Length::Fill)
+            .padding([10, 15, 10, 15])
+            .style(theme::Container::Controls)
+            .into()
+    }
+
+    fn stats_view(&self) -> Element<Message> {
+        let ms = |duration: Duration| format!("{:.2} ms", duration.as_secs_f32() * 1000.0);
+
+        let summary = if let Some(frame) = self.stats.latest() {
+            let gpu = match frame.timings.gpu {
+                Some(gpu) => format!("GPU: {}", ms(gpu)),
+                None => "GPU timing unavailable".to_string(),
+            };
+
+            format!(
+                "{:.0} FPS    Frame: {}    CPU: {}    {gpu}",
+                self.stats.fps(),
+                ms(frame.frame_time),
+                ms(frame.timings.cpu),
+            )
+        } else {
+            "Waiting for frames..".to_string()
+        };
+
+        let export = tooltip(
+            button(text("Export CSV").size(14)).on_press(Message::ExportStats),
+            "Save the recorded frame times for comparison",
+            tooltip::Position::Top,
+        )
+        .padding(10)
+        .style(theme::Container::Tooltip);
+
+        container(
+            column![
+                row![
+                    container(text(summary).size(14)).width(Length::Fill),
+                    export
+                ]
+                .align_items(Alignment::Center),
+                canvas(self.stats.graph()).width(Length::Fill).height(60),
+            ]
+            .spacing(5),
+        )
+        .width(Length::Fill)
+        .padding([10, 15, 10, 15])
+        .style(theme::Container::Controls)
+        .into()
+    }
 }
 
-impl<Message> iced::widget::shader::Program<Message> for Viewer {
-    type State = ();
+fn setting<'a, T>(
+    options: &'a [T],
+    selected: T,
+    on_selected: impl Fn(T) -> Settings + 'a,
+) -> Element<'a, Message>
+where
+    T: ToString + Eq + Clone + 'static,
+{
+    pick_list(options, Some(selected), move |option| {
+        Message::SettingsChanged(on_selected(option))
+    })
+    .text_size(14)
+    .into()
+}
+
+#[derive(Default)]
+pub struct State {
+    device_detected: bool,
+    camera: camera::Orbit,
+    pan: camera::Pan,
+    /// The button dragging the camera & the last cursor position while it's held.
+    drag: Option<(mouse::Button, Point)>,
+    /// When the viewer was last clicked, to reset the camera on a double click.
+    last_click: Option<Instant>,
+    mouse: Mouse,
+    /// Whether the viewer was clicked last, so it receives key presses instead of the editor.
+    is_focused: bool,
+    keyboard: Keyboard,
+}
+
+impl iced::widget::shader::Program<Message> for Viewer {
+    type State = State;
     type Primitive = Primitive;
 
     fn update(
         &self,
-        _state: &mut Self::State,
-        _event: Event,
-        _bounds: Rectangle,
-        _cursor: mouse::Cursor,
+        state: &mut Self::State,
+        event: ShaderEvent,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
         shell: &mut Shell<'_, Message>,
     ) -> (Status, Option<Message>) {
         shell.request_redraw(RedrawRequest::NextFrame);
 
+        // published alongside the event's own message, so the event is still handled
+        if !state.device_detected {
+            if let Some(device) = self.device.get() {
+                state.device_detected = true;
+                shell.publish(Message::DeviceDetected(device.clone()));
+            }
+        }
+
+        if let ShaderEvent::Mouse(mouse_event) = event {
+            let is_over = state.mouse.update(mouse_event, cursor, bounds);
+
+            if let mouse::Event::ButtonPressed(_) = mouse_event {
+                state.is_focused = is_over;
+
+                if !is_over {
+                    state.keyboard.release_all();
+                }
+            }
+        }
+
+        match event {
+            ShaderEvent::RedrawRequested(now) => {
+                state.keyboard.next_frame();
+
+                if self.show_stats || self.is_inspecting || self.is_checking_range {
+                    return (Status::Ignored, Some(Message::Frame(now)));
+                }
+            }
+            ShaderEvent::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                if self.is_inspecting =>
+            {
+                if cursor.is_over(bounds) {
+                    return (Status::Captured, Some(Message::CopySample));
+                }
+            }
+            ShaderEvent::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                if let Some(position) = cursor.position_over(bounds) {
+                    let now = Instant::now();
+
+                    if state
+                        .last_click
+                        .is_some_and(|last| now - last < DOUBLE_CLICK)
+                    {
+                        if self.shader.reflection.uses_pan {
+                            state.pan = camera::Pan::default();
+                        } else {
+                            state.camera = camera::Orbit::default();
+                        }
+
+                        state.last_click = None;
+                    } else {
+                        state.last_click = Some(now);
+                    }
+
+                    state.drag = Some((mouse::Button::Left, position));
+
+                    return (Status::Captured, None);
+                }
+            }
+            ShaderEvent::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) => {
+                if let Some(position) = cursor.position_over(bounds) {
+                    state.drag = Some((mouse::Button::Right, position));
+
+                    return (Status::Captured, None);
+                }
+            }
+            ShaderEvent::Mouse(mouse::Event::CursorMoved { position }) => {
+                if let Some((button, last)) = state.drag {
+                    let delta = position - last;
+
+                    if self.shader.reflection.uses_pan {
+                        state.pan.drag(delta, bounds.size());
+                    } else if button == mouse::Button::Right {
+                        state.camera.pan(delta, bounds.size());
+                    } else {
+                        state.camera.rotate(delta);
+                    }
+
+                    state.drag = Some((button, position));
+
+                    return (Status::Captured, None);
+                }
+            }
+            ShaderEvent::Mouse(mouse::Event::ButtonReleased(button)) => {
+                if state.drag.is_some_and(|(dragging, _)| dragging == button) {
+                    state.drag = None;
+
+                    return (Status::Captured, None);
+                }
+            }
+            ShaderEvent::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                if let Some(position) = cursor.position_in(bounds) {
+                    let lines = uniforms::lines(delta);
+
+                    if self.shader.reflection.uses_pan {
+                        state.pan.zoom(lines.y, position, bounds.size());
+                    } else {
+                        state.camera.dolly(lines.y);
+                    }
+
+                    return (Status::Captured, None);
+                }
+            }
+            ShaderEvent::Keyboard(iced::keyboard::Event::KeyPressed { key_code, .. })
+                if state.is_focused =>
+            {
+                state.keyboard.press(key_code);
+
+                return (Status::Captured, None);
+            }
+            ShaderEvent::Keyboard(iced::keyboard::Event::KeyReleased { key_code, .. })
+                if state.is_focused =>
+            {
+                state.keyboard.release(key_code);
+
+                return (Status::Captured, None);
+            }
+            _ => {}
+        }
+
         (Status::Ignored, None)
     }
 
     fn draw(
         &self,
-        _state: &Self::State,
+        state: &Self::State,
         cursor: mouse::Cursor,
         bounds: Rectangle,
     ) -> Self::Primitive {
         Primitive {
+            id: self.id.clone(),
             uniforms: Uniforms {
                 time: Instant::now() - self.start,
-                mouse: match cursor {
-                    mouse::Cursor::Available(pt) => pt,
-                    //we go full circle..
-                    mouse::Cursor::Unavailable => Point::new(-1.0, -1.0),
-                },
+                mouse: state.mouse,
                 bounds,
             },
-            shader: self.last_valid_shader.clone(),
-            version: self.version,
+            camera: state.camera.to_raw(bounds.width / bounds.height),
+            pan: state.pan,
+            shader: self.shader.clone(),
+            settings: self.settings,
+            mesh: self.mesh.clone(),
+            audio: self.audio.clone(),
+            keyboard: state.keyboard.texture(),
+            device: self.device.clone(),
+            timings: self.timings.clone(),
+            inspect: self
+                .is_inspecting
+                .then(|| cursor.position_in(bounds))
+                .flatten(),
+            sample: self.shared_sample.clone(),
+            probes: self.shared_probes.clone(),
+            check_range: self.is_checking_range,
+            counts: self.shared_counts.clone(),
         }
     }
 }
@@ -0,0 +1,410 @@
+//! Audio files for shaders to react to, like Shadertoy's audio channel.
+//!
+//! Each frame, the part of the track playing at `uniforms.time` is analysed & written to
+//! `halo_audio`, a [`WIDTH`] x 2 texture whose first row is the spectrum & second the waveform.
+use iced::Size;
+use std::f32::consts::PI;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+const FILE_EXT_FILTERS: [&str; 4] = ["wav", "flac", "ogg", "oga"];
+
+/// The binding of `halo_audio` in halo's bind group.
+pub const BINDING: u32 = 3;
+
+/// The number of texels in each row of `halo_audio`.
+pub const WIDTH: u32 = 512;
+
+/// The size of `halo_audio`.
+pub const SIZE: Size<u32> = Size {
+    width: WIDTH,
+    height: 2,
+};
+
+/// The number of samples the spectrum is computed from; only the lowest [`WIDTH`] of its bins
+/// are kept, which covers up to a quarter of the sample rate.
+const FFT_SIZE: usize = 4 * WIDTH as usize;
+
+/// The range of magnitudes mapped to 0..1 in the spectrum, the same as Web Audio's analyser.
+const MIN_DECIBELS: f32 = -100.0;
+const MAX_DECIBELS: f32 = -30.0;
+
+/// A decoded audio file, mixed down to mono for analysis.
+#[derive(Debug)]
+pub struct Track {
+    pub name: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Interleaved samples of every channel, for playback.
+    samples: Vec<f32>,
+    mono: Vec<f32>,
+}
+
+impl Track {
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.mono.len() as f64 / f64::from(self.sample_rate))
+    }
+
+    /// The contents of `halo_audio` `time` into the track, which loops.
+    pub fn texture(&self, time: Duration) -> Vec<u8> {
+        if self.mono.is_empty() {
+            return silence();
+        }
+
+        let position =
+            (time.as_secs_f64() * f64::from(self.sample_rate)) as usize % self.mono.len();
+
+        // the samples leading up to the current one, padded with silence at the start
+        let window = |size: usize| {
+            (0..size).map(move |i| {
+                (position + i + 1)
+                    .checked_sub(size)
+                    .map_or(0.0, |index| self.mono[index])
+            })
+        };
+
+        let spectrum = spectrum(&window(FFT_SIZE).collect::<Vec<_>>());
+        let waveform = window(WIDTH as usize).map(|sample| (sample + 1.0) * 0.5);
+
+        spectrum.into_iter().chain(waveform).map(unorm).collect()
+    }
+}
+
+/// The contents of `halo_audio` `time` into `track`, or silence without one.
+pub fn texture(track: Option<&Track>, time: Duration) -> Vec<u8> {
+    track.map_or_else(silence, |track| track.texture(time))
+}
+
+/// The contents of `halo_audio` when no audio is loaded.
+pub fn silence() -> Vec<u8> {
+    let mut texture = vec![0; WIDTH as usize * 2];
+    texture[WIDTH as usize..].fill(unorm(0.5));
+    texture
+}
+
+fn unorm(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// The magnitudes of the lowest [`WIDTH`] frequency bins of `samples`, in decibels mapped to 0..1.
+fn spectrum(samples: &[f32]) -> Vec<f32> {
+    let size = samples.len();
+
+    // a Blackman window, as Web Audio uses, to keep each frequency to a few bins
+    let mut real = samples
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| {
+            let x = 2.0 * PI * i as f32 / size as f32;
+            sample * (0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos())
+        })
+        .collect::<Vec<_>>();
+    let mut imaginary = vec![0.0; size];
+
+    fft(&mut real, &mut imaginary);
+
+    (0..WIDTH as usize)
+        .map(|bin| {
+            let magnitude = real[bin].hypot(imaginary[bin]) / size as f32;
+            let decibels = 20.0 * magnitude.max(f32::MIN_POSITIVE).log10();
+
+            (decibels - MIN_DECIBELS) / (MAX_DECIBELS - MIN_DECIBELS)
+        })
+        .collect()
+}
+
+/// An in-place radix-2 FFT; the length must be a power of two.
+fn fft(real: &mut [f32], imaginary: &mut [f32]) {
+    let size = real.len();
+    let mut j = 0;
+
+    for i in 1..size {
+        let mut bit = size >> 1;
+
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+
+        j |= bit;
+
+        if i < j {
+            real.swap(i, j);
+            imaginary.swap(i, j);
+        }
+    }
+
+    let mut length = 2;
+
+    while length <= size {
+        let angle = -2.0 * PI / length as f32;
+
+        for start in (0..size).step_by(length) {
+            for k in 0..length / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let a = start + k;
+                let b = a + length / 2;
+
+                let t_real = real[b] * cos - imaginary[b] * sin;
+                let t_imaginary = real[b] * sin + imaginary[b] * cos;
+
+                real[b] = real[a] - t_real;
+                imaginary[b] = imaginary[a] - t_imaginary;
+                real[a] += t_real;
+                imaginary[a] += t_imaginary;
+            }
+        }
+
+        length <<= 1;
+    }
+}
+
+pub async fn open() -> Result<Arc<Track>, Error> {
+    let file = rfd::AsyncFileDialog::new()
+        .add_filter("supported audio formats", &FILE_EXT_FILTERS)
+        .set_title("Open an audio file...")
+        .pick_file()
+        .await
+        .ok_or(Error::DialogueClosed)?;
+
+    load(file.path().to_owned()).await
+}
+
+pub async fn load(path: PathBuf) -> Result<Arc<Track>, Error> {
+    tokio::task::spawn_blocking(move || decode(&path).map(Arc::new))
+        .await
+        .map_err(|_| Error::Load("Audio loading was interrupted".to_string()))?
+}
+
+/// Decodes every sample of the audio file at `path`.
+pub fn decode(path: &Path) -> Result<Track, Error> {
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::errors::Error as SymphoniaError;
+
+    let load_error = |error: SymphoniaError| Error::Load(error.to_string());
+
+    let file = File::open(path).map_err(|error| Error::Load(error.to_string()))?;
+    let stream = symphonia::core::io::MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = symphonia::core::probe::Hint::new();
+    if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let mut format = symphonia::default::get_probe()
+        .format(&hint, stream, &Default::default(), &Default::default())
+        .map_err(|_| Error::UnsupportedFormat)?
+        .format;
+
+    let track = format.default_track().ok_or(Error::UnsupportedFormat)?;
+    let id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &Default::default())
+        .map_err(|_| Error::UnsupportedFormat)?;
+
+    let mut channels = 0;
+    let mut samples = vec![];
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(error))
+                if error.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                break;
+            }
+            Err(error) => return Err(load_error(error)),
+        };
+
+        if packet.track_id() != id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // a corrupt packet only skips a few milliseconds, so keep going
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(error) => return Err(load_error(error)),
+        };
+
+        let spec = *decoded.spec();
+        channels = spec.channels.count();
+        sample_rate = Some(spec.rate);
+
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buffer.samples());
+    }
+
+    let sample_rate = sample_rate.ok_or(Error::UnsupportedFormat)?;
+    let channels = channels.max(1);
+    let mono = samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+
+    Ok(Track {
+        name: path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("Audio")
+            .to_string(),
+        sample_rate,
+        channels: channels as u16,
+        samples,
+        mono,
+    })
+}
+
+/// Plays a track on the default output device until it's dropped, looping it so it stays in
+/// sync with `uniforms.time` when started along with it.
+#[cfg(feature = "playback")]
+pub struct Player {
+    _stream: rodio::OutputStream,
+    _sink: rodio::Sink,
+}
+
+#[cfg(feature = "playback")]
+impl Player {
+    pub fn play(track: &Track) -> Result<Self, Error> {
+        use rodio::Source;
+
+        let playback_error = |error: &dyn std::fmt::Display| Error::Playback(error.to_string());
+
+        let (stream, handle) =
+            rodio::OutputStream::try_default().map_err(|error| playback_error(&error))?;
+        let sink = rodio::Sink::try_new(&handle).map_err(|error| playback_error(&error))?;
+
+        sink.append(
+            rodio::buffer::SamplesBuffer::new(
+                track.channels,
+                track.sample_rate,
+                track.samples.clone(),
+            )
+            .repeat_infinite(),
+        );
+
+        Ok(Self {
+            _stream: stream,
+            _sink: sink,
+        })
+    }
+}
+
+/// Without the `playback` feature, tracks are only analysed.
+#[cfg(not(feature = "playback"))]
+pub struct Player;
+
+#[cfg(not(feature = "playback"))]
+impl Player {
+    pub fn play(_track: &Track) -> Result<Self, Error> {
+        Ok(Self)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    DialogueClosed,
+    UnsupportedFormat,
+    Load(String),
+    Playback(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// Writes a 16-bit PCM WAV file of a `frequency` Hz sine wave, `channels` times over.
+    fn sine_wav(name: &str, frequency: f32, channels: u16) -> PathBuf {
+        let samples = (0..SAMPLE_RATE)
+            .map(|i| (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32).sin() * 0.5)
+            .flat_map(|sample| vec![(sample * f32::from(i16::MAX)) as i16; channels as usize])
+            .flat_map(i16::to_le_bytes)
+            .collect::<Vec<_>>();
+
+        let block_align = channels * 2;
+        let mut wav = vec![];
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + samples.len() as u32).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&channels.to_le_bytes());
+        wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        wav.extend_from_slice(&(SAMPLE_RATE * u32::from(block_align)).to_le_bytes());
+        wav.extend_from_slice(&block_align.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(samples.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&samples);
+
+        let path = std::env::temp_dir().join(format!("halo-{name}-{}.wav", std::process::id()));
+        std::fs::write(&path, wav).unwrap();
+        path
+    }
+
+    /// The middle of the loudest bins of a texture's spectrum, which clip around a pure tone.
+    fn peak(texture: &[u8]) -> f32 {
+        let spectrum = &texture[..WIDTH as usize];
+        let max = spectrum.iter().max().unwrap();
+        let first = spectrum.iter().position(|bin| bin == max).unwrap();
+        let last = spectrum.iter().rposition(|bin| bin == max).unwrap();
+
+        (first + last) as f32 / 2.0
+    }
+
+    #[test]
+    fn spectrum_peaks_at_frequency() {
+        let path = sine_wav("sine", 440.0, 1);
+        let track = decode(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(track.sample_rate, SAMPLE_RATE);
+        assert_eq!(track.duration(), Duration::from_secs(1));
+
+        let texture = track.texture(Duration::from_millis(500));
+        assert_eq!(texture.len(), WIDTH as usize * 2);
+
+        // each bin is a quarter of the sample rate / `WIDTH` Hz wide
+        let bin = 440.0 / (SAMPLE_RATE as f32 / 4.0 / WIDTH as f32);
+        assert!((peak(&texture) - bin).abs() <= 1.0, "{}", peak(&texture));
+        assert!(texture[100..WIDTH as usize].iter().all(|&bin| bin < 64));
+
+        let waveform = &texture[WIDTH as usize..];
+        assert!(waveform.iter().any(|&sample| sample < 96));
+        assert!(waveform.iter().any(|&sample| sample > 160));
+    }
+
+    #[test]
+    fn channels_are_mixed_down() {
+        let path = sine_wav("stereo", 2000.0, 2);
+        let track = decode(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(track.channels, 2);
+        assert_eq!(track.duration(), Duration::from_secs(1));
+
+        let bin = 2000.0 / (SAMPLE_RATE as f32 / 4.0 / WIDTH as f32);
+        let texture = track.texture(Duration::from_millis(250));
+        assert!((peak(&texture) - bin).abs() <= 1.0, "{}", peak(&texture));
+
+        // the track loops, so it's analysed at the same point a second later
+        assert_eq!(texture, track.texture(Duration::from_millis(1250)));
+    }
+
+    #[test]
+    fn silence_before_audio_starts() {
+        let path = sine_wav("start", 440.0, 1);
+        let track = decode(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let texture = track.texture(Duration::ZERO);
+        assert!(texture[..WIDTH as usize].iter().all(|&bin| bin == 0));
+        assert_eq!(silence()[..WIDTH as usize], texture[..WIDTH as usize]);
+    }
+}
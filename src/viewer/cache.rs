@@ -0,0 +1,106 @@
+/// How many pipelines are kept around after they were last used.
+const CAPACITY: usize = 8;
+
+/// A least-recently-used cache of pipelines, keyed by the hash of their shader & render settings.
+///
+/// Switching back to a recently used shader (e.g. after an undo) reuses its pipeline instead of
+/// recompiling it.
+pub struct Cache<T> {
+    /// Ordered from least to most recently used.
+    entries: Vec<(u64, T)>,
+}
+
+impl<T> Default for Cache<T> {
+    fn default() -> Self {
+        Self { entries: vec![] }
+    }
+}
+
+impl<T> Cache<T> {
+    pub fn get(&self, id: u64) -> Option<&T> {
+        self.entries
+            .iter()
+            .find(|(key, _)| *key == id)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the value with the given id, marking it as most recently used. If it isn't cached,
+    /// it's created with `create`, evicting the least recently used value if full.
+    pub fn get_or_insert_with(&mut self, id: u64, create: impl FnOnce() -> T) -> &T {
+        let entry = match self.entries.iter().position(|(key, _)| *key == id) {
+            Some(index) => self.entries.remove(index),
+            None => {
+                if self.entries.len() >= CAPACITY {
+                    let _ = self.entries.remove(0);
+                }
+
+                (id, create())
+            }
+        };
+
+        self.entries.push(entry);
+
+        &self.entries.last().expect("Cache must not be empty").1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled() -> Cache<u64> {
+        let mut cache = Cache::default();
+
+        for id in 0..CAPACITY as u64 {
+            cache.get_or_insert_with(id, || id);
+        }
+
+        cache
+    }
+
+    #[test]
+    fn creates_each_value_once() {
+        let mut cache = filled();
+        let mut created = 0;
+
+        for id in 0..CAPACITY as u64 {
+            cache.get_or_insert_with(id, || {
+                created += 1;
+                id
+            });
+        }
+
+        assert_eq!(created, 0);
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_at_capacity() {
+        let mut cache = filled();
+
+        cache.get_or_insert_with(CAPACITY as u64, || CAPACITY as u64);
+
+        assert_eq!(cache.get(0), None);
+        assert!((1..=CAPACITY as u64).all(|id| cache.get(id) == Some(&id)));
+    }
+
+    #[test]
+    fn using_a_value_keeps_it_from_eviction() {
+        let mut cache = filled();
+
+        cache.get_or_insert_with(0, || unreachable!());
+        cache.get_or_insert_with(CAPACITY as u64, || CAPACITY as u64);
+
+        assert_eq!(cache.get(0), Some(&0));
+        assert_eq!(cache.get(1), None);
+    }
+
+    #[test]
+    fn looking_up_a_value_doesnt_mark_it_as_used() {
+        let mut cache = filled();
+
+        assert_eq!(cache.get(0), Some(&0));
+        cache.get_or_insert_with(CAPACITY as u64, || CAPACITY as u64);
+
+        assert_eq!(cache.get(0), None);
+    }
+}
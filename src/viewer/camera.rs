@@ -0,0 +1,177 @@
+use glam::{DVec2, Mat4, Vec3};
+use iced::{Point, Size, Vector};
+use std::f32::consts::FRAC_PI_2;
+
+const FOV_Y: f32 = std::f32::consts::FRAC_PI_4;
+const NEAR: f32 = 0.01;
+const FAR: f32 = 100.0;
+const ROTATION_SPEED: f32 = 0.01;
+/// How much a line of scrolling moves the orbit camera towards its target.
+const DOLLY_PER_LINE: f32 = 1.1;
+const MIN_DISTANCE: f32 = 0.05;
+const MAX_DISTANCE: f32 = 50.0;
+/// How much a line of scrolling zooms the pan camera in.
+const ZOOM_PER_LINE: f64 = 1.2;
+const MIN_ZOOM: f64 = 1e-3;
+/// Past this, pixels near the center of a large viewer are closer together than f64 can tell.
+const MAX_ZOOM: f64 = 1e12;
+
+/// A camera orbiting around a target, rotated by dragging, dollied by scrolling & panned by
+/// dragging with the right button.
+#[derive(Debug, Clone, Copy)]
+pub struct Orbit {
+    target: Vec3,
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        Self {
+            target: Vec3::ZERO,
+            yaw: 0.6,
+            pitch: 0.4,
+            distance: 3.0,
+        }
+    }
+}
+
+impl Orbit {
+    /// Rotates the camera around its target by a cursor movement of `delta` logical pixels.
+    pub fn rotate(&mut self, delta: Vector) {
+        self.yaw -= delta.x * ROTATION_SPEED;
+        self.pitch =
+            (self.pitch + delta.y * ROTATION_SPEED).clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+    }
+
+    /// Moves the camera towards its target by `lines` scrolled, or away from it when negative.
+    pub fn dolly(&mut self, lines: f32) {
+        self.distance =
+            (self.distance / DOLLY_PER_LINE.powf(lines)).clamp(MIN_DISTANCE, MAX_DISTANCE);
+    }
+
+    /// Moves the camera & its target along with a cursor movement of `delta` logical pixels in a
+    /// viewer of `bounds`, so the target follows the cursor.
+    pub fn pan(&mut self, delta: Vector, bounds: Size) {
+        let view = Mat4::look_at_rh(self.eye(), self.target, Vec3::Y);
+        let right = view.row(0).truncate();
+        let up = view.row(1).truncate();
+
+        // the size of a logical pixel at the target's depth
+        let pixel_size = 2.0 * self.distance * (FOV_Y / 2.0).tan() / bounds.height.max(1.0);
+
+        self.target -= (right * delta.x - up * delta.y) * pixel_size;
+    }
+
+    pub fn eye(&self) -> Vec3 {
+        self.target
+            + Vec3::new(
+                self.distance * self.pitch.cos() * self.yaw.sin(),
+                self.distance * self.pitch.sin(),
+                self.distance * self.pitch.cos() * self.yaw.cos(),
+            )
+    }
+
+    pub fn to_raw(self, aspect_ratio: f32) -> Raw {
+        let eye = self.eye();
+        let view = Mat4::look_at_rh(eye, self.target, Vec3::Y);
+        let projection = Mat4::perspective_rh(FOV_Y, aspect_ratio.max(f32::EPSILON), NEAR, FAR);
+        let view_projection = projection * view;
+
+        Raw {
+            view_projection,
+            view,
+            projection,
+            eye: eye.into(),
+            _padding: 0.0,
+            inverse_view_projection: view_projection.inverse(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct Raw {
+    pub view_projection: Mat4,
+    pub view: Mat4,
+    pub projection: Mat4,
+    pub eye: [f32; 3],
+    pub _padding: f32,
+    pub inverse_view_projection: Mat4,
+}
+
+/// A 2D camera for exploring fractals & SDFs, panned by dragging & zoomed by scrolling.
+///
+/// Its center is kept in double precision & passed to shaders split into a high & low part, so
+/// zooming deeper than a single precision float can represent stays smooth.
+#[derive(Debug, Clone, Copy)]
+pub struct Pan {
+    center: DVec2,
+    /// 1 when the viewer's height spans from -1 to 1.
+    zoom: f64,
+}
+
+impl Default for Pan {
+    fn default() -> Self {
+        Self {
+            center: DVec2::ZERO,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Pan {
+    /// Moves the view along with a cursor movement of `delta` logical pixels.
+    pub fn drag(&mut self, delta: Vector, bounds: Size) {
+        self.center -=
+            DVec2::new(f64::from(delta.x), -f64::from(delta.y)) * self.pixel_size(bounds);
+    }
+
+    /// Zooms in by `lines` scrolled, keeping the point under the cursor at `position` in place.
+    pub fn zoom(&mut self, lines: f32, position: Point, bounds: Size) {
+        let before = self.position(position, bounds);
+        self.zoom = (self.zoom * ZOOM_PER_LINE.powf(f64::from(lines))).clamp(MIN_ZOOM, MAX_ZOOM);
+        let after = self.position(position, bounds);
+
+        self.center += before - after;
+    }
+
+    /// The size of a logical pixel in view coordinates.
+    fn pixel_size(&self, bounds: Size) -> f64 {
+        2.0 / (self.zoom * f64::from(bounds.height.max(1.0)))
+    }
+
+    /// The view coordinates of a point in the viewer, with y up.
+    fn position(&self, point: Point, bounds: Size) -> DVec2 {
+        let offset = DVec2::new(
+            f64::from(point.x - bounds.width / 2.0),
+            f64::from(bounds.height / 2.0 - point.y),
+        );
+
+        self.center + offset * self.pixel_size(bounds)
+    }
+
+    /// The uniforms of a viewer of `bounds`, rendered at `scale` physical pixels per logical one.
+    pub fn to_raw(self, bounds: Size, scale: f32) -> PanRaw {
+        let center = self.center.as_vec2();
+
+        PanRaw {
+            center: center.into(),
+            center_low: (self.center - center.as_dvec2()).as_vec2().into(),
+            pixel_size: (self.pixel_size(bounds) / f64::from(scale)) as f32,
+            zoom: self.zoom as f32,
+            _padding: [0.0; 2],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct PanRaw {
+    pub center: [f32; 2],
+    pub center_low: [f32; 2],
+    pub pixel_size: f32,
+    pub zoom: f32,
+    pub _padding: [f32; 2],
+}
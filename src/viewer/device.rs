@@ -0,0 +1,137 @@
+use iced::widget::shader::wgpu;
+use naga::valid::Capabilities;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// The features & limits of the device the viewer is rendering with.
+#[derive(Debug, Clone)]
+pub struct Device {
+    pub features: wgpu::Features,
+    pub limits: wgpu::Limits,
+}
+
+impl Device {
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self {
+            features: device.features(),
+            limits: device.limits(),
+        }
+    }
+
+    /// Maps the device's features to the naga capabilities that wgpu will validate against
+    /// when creating a shader module.
+    pub fn capabilities(&self) -> Capabilities {
+        let features = self.features;
+        // downlevel flags aren't exposed through the device, so we assume naga's defaults
+        let mut caps = Capabilities::default();
+
+        caps.set(
+            Capabilities::PUSH_CONSTANT,
+            features.contains(wgpu::Features::PUSH_CONSTANTS),
+        );
+        caps.set(
+            Capabilities::FLOAT64,
+            features.contains(wgpu::Features::SHADER_F64),
+        );
+        caps.set(
+            Capabilities::PRIMITIVE_INDEX,
+            features.contains(wgpu::Features::SHADER_PRIMITIVE_INDEX),
+        );
+        caps.set(
+            Capabilities::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING,
+            features.contains(
+                wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING,
+            ),
+        );
+        caps.set(
+            Capabilities::UNIFORM_BUFFER_AND_STORAGE_TEXTURE_ARRAY_NON_UNIFORM_INDEXING,
+            features.contains(
+                wgpu::Features::UNIFORM_BUFFER_AND_STORAGE_TEXTURE_ARRAY_NON_UNIFORM_INDEXING,
+            ),
+        );
+        caps.set(
+            Capabilities::SAMPLER_NON_UNIFORM_INDEXING,
+            features.contains(
+                wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING,
+            ),
+        );
+        caps.set(
+            Capabilities::STORAGE_TEXTURE_16BIT_NORM_FORMATS,
+            features.contains(wgpu::Features::TEXTURE_FORMAT_16BIT_NORM),
+        );
+        caps.set(
+            Capabilities::MULTIVIEW,
+            features.contains(wgpu::Features::MULTIVIEW),
+        );
+        caps.set(
+            Capabilities::EARLY_DEPTH_TEST,
+            features.contains(wgpu::Features::SHADER_EARLY_DEPTH_TEST),
+        );
+        caps.set(
+            Capabilities::DUAL_SOURCE_BLENDING,
+            features.contains(wgpu::Features::DUAL_SOURCE_BLENDING),
+        );
+
+        caps
+    }
+}
+
+/// The set of capabilities & limits a shader is validated against.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub capabilities: Capabilities,
+    pub limits: wgpu::Limits,
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Profile::WebGpu.target(None)
+    }
+}
+
+/// Which capabilities a shader should be validated against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Profile {
+    /// Whatever the local GPU supports.
+    #[default]
+    Device,
+    /// The baseline guaranteed by the WebGPU spec.
+    WebGpu,
+    /// WebGPU running on top of WebGL2, which has no compute & much lower limits.
+    WebGl2,
+}
+
+impl Profile {
+    pub const ALL: [Profile; 3] = [Profile::Device, Profile::WebGpu, Profile::WebGl2];
+
+    /// Returns the validation target of this profile. Falls back to the WebGPU baseline if the
+    /// device hasn't been detected yet.
+    pub fn target(&self, device: Option<&Device>) -> Target {
+        match (self, device) {
+            (Profile::Device, Some(device)) => Target {
+                capabilities: device.capabilities(),
+                limits: device.limits.clone(),
+            },
+            (Profile::Device | Profile::WebGpu, _) => Target {
+                capabilities: Capabilities::default(),
+                limits: wgpu::Limits::default(),
+            },
+            (Profile::WebGl2, _) => Target {
+                capabilities: Capabilities::empty(),
+                limits: wgpu::Limits::downlevel_webgl2_defaults(),
+            },
+        }
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Profile::Device => "Local GPU",
+            Profile::WebGpu => "WebGPU",
+            Profile::WebGl2 => "WebGL2",
+        };
+
+        write!(f, "{str}")
+    }
+}
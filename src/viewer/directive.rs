@@ -0,0 +1,78 @@
+//! Halo-specific settings written as `//#` comments, so a shader stays valid WGSL.
+//!
+//! ```wgsl
+//! //#dispatch cs_main 64 1 1
+//! //#length particles 4096
+//! //#render scale=0.5 msaa=4
+//! //#import raymarch
+//! ```
+use crate::viewer::library;
+use crate::viewer::settings::Settings;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Directive {
+    /// How many workgroups a compute entry point is dispatched with each frame.
+    Dispatch {
+        entry_point: String,
+        workgroups: [u32; 3],
+    },
+    /// The number of elements of a storage buffer's runtime-sized array.
+    Length { variable: String, elements: u32 },
+    /// How the shader is rendered; see [`Settings`].
+    Render(Settings),
+    /// One of halo's WGSL libraries to append to the shader.
+    Import(&'static str),
+}
+
+const PREFIX: &str = "//#";
+
+/// Parses every directive in `source`, along with the byte range of the line it's declared on.
+pub fn parse(source: &str) -> Vec<(Range<usize>, Result<Directive, String>)> {
+    let mut offset = 0;
+
+    source
+        .split_inclusive('\n')
+        .filter_map(|line| {
+            let range = offset..offset + line.trim_end().len();
+            offset += line.len();
+
+            let directive = line.trim().strip_prefix(PREFIX)?;
+
+            Some((range, parse_directive(directive)))
+        })
+        .collect()
+}
+
+fn parse_directive(directive: &str) -> Result<Directive, String> {
+    let mut args = directive.split_whitespace();
+    let name = args.next().unwrap_or_default();
+    let args = args.collect::<Vec<_>>();
+
+    let number = |arg: &str| {
+        arg.parse::<u32>()
+            .map_err(|_| format!("Expected a positive integer, found `{arg}`"))
+    };
+
+    match (name, args.as_slice()) {
+        ("dispatch", [entry_point, x, y, z]) => Ok(Directive::Dispatch {
+            entry_point: entry_point.to_string(),
+            workgroups: [number(x)?, number(y)?, number(z)?],
+        }),
+        ("dispatch", _) => Err("Usage: //#dispatch <entry point> <x> <y> <z>".to_string()),
+        ("length", [variable, elements]) => Ok(Directive::Length {
+            variable: variable.to_string(),
+            elements: number(elements)?,
+        }),
+        ("length", _) => Err("Usage: //#length <variable> <elements>".to_string()),
+        ("render", args) => Settings::parse(args).map(Directive::Render),
+        ("import", [name]) => library::find(name).map(Directive::Import).ok_or_else(|| {
+            format!(
+                "Unknown library `{name}`, expected one of: {}",
+                library::NAMES.join(", ")
+            )
+        }),
+        ("import", _) => Err("Usage: //#import <library>".to_string()),
+        (name, _) => Err(format!("Unknown directive `{name}`")),
+    }
+}
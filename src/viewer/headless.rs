@@ -0,0 +1,306 @@
+use crate::viewer::audio::{self, Track};
+use crate::viewer::camera;
+use crate::viewer::mesh::Mesh;
+use crate::viewer::offscreen::{self, Blit, Offscreen};
+use crate::viewer::pipeline::{MeshBuffers, Pipeline};
+use crate::viewer::settings::Settings;
+use crate::viewer::shader::Shader;
+use crate::viewer::timer::GpuTimer;
+use crate::viewer::uniforms::{Mouse, Uniforms};
+use iced::widget::shader::wgpu;
+use iced::{Point, Rectangle, Size};
+use std::cell::Cell;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The format of a rendered [`Image`].
+pub(crate) const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+const BYTES_PER_PIXEL: u32 = 4;
+
+/// Renders shaders without a window, the same way the viewer does, e.g. to compare a shader's
+/// output against a known image in tests.
+pub struct Renderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    blit: Blit,
+    mesh: MeshBuffers,
+    audio: Option<Arc<Track>>,
+    timer: Option<GpuTimer>,
+    gpu_time: Cell<Option<Duration>>,
+}
+
+/// A rendered frame as 8-bit sRGB RGBA pixels, row by row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum Error {
+    #[error("No adapter found")]
+    NoAdapter,
+    #[error("Failed to request a device: {0}")]
+    RequestDevice(String),
+    #[error("Failed to render shader: {0}")]
+    Render(String),
+    #[error("Failed to read back the rendered frame")]
+    Readback,
+}
+
+impl Renderer {
+    /// Renders with the first adapter available, falling back to a software adapter if there's
+    /// no hardware one.
+    pub async fn new() -> Result<Self, Error> {
+        let (device, queue) = device().await?;
+
+        Ok(Self::with_device(device, queue))
+    }
+
+    /// Renders with an existing device.
+    pub fn with_device(device: wgpu::Device, queue: wgpu::Queue) -> Self {
+        Self {
+            blit: Blit::new(&device, FORMAT),
+            mesh: MeshBuffers::new(&device, &Mesh::cube()),
+            audio: None,
+            timer: GpuTimer::new(&device, &queue),
+            gpu_time: Cell::new(None),
+            device,
+            queue,
+        }
+    }
+
+    /// Sets the audio `halo_audio` is written from, as if it were loaded in the viewer. Tracks are
+    /// decoded with [`decode_audio`](crate::viewer::decode_audio).
+    pub fn set_audio(&mut self, track: Option<Arc<Track>>) {
+        self.audio = track;
+    }
+
+    /// How long the shader's passes took on the GPU in the last render, if the adapter supports
+    /// timestamp queries.
+    pub fn gpu_time(&self) -> Option<Duration> {
+        self.gpu_time.get()
+    }
+
+    /// Renders `shader` at `width` x `height` pixels, as it would be rendered in the viewer
+    /// `time` after it started, with its `//#render` settings.
+    pub async fn render(
+        &self,
+        source: &str,
+        width: u32,
+        height: u32,
+        time: Duration,
+    ) -> Result<Image, Error> {
+        let device = &self.device;
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let shader = Shader::new(source);
+        let settings = Settings::from_source(source);
+
+        let width = width.max(1);
+        let height = height.max(1);
+        let bounds = Rectangle::new(Point::ORIGIN, Size::new(width as f32, height as f32));
+        let scale = settings.scale.factor();
+        let size = offscreen::frame_size(bounds.size(), scale);
+
+        let offscreen = Offscreen::new(device, size, settings.msaa.sample_count(), &self.blit);
+        let pipeline = Pipeline::new(device, &shader, &settings);
+        let bindings = pipeline.bindings(device, &shader.reflection, size);
+
+        let uniforms = Uniforms {
+            time,
+            mouse: Mouse::default(),
+            bounds,
+        };
+
+        pipeline.prepare(
+            &self.queue,
+            &uniforms.to_raw(size, scale),
+            &camera::Orbit::default().to_raw(bounds.width / bounds.height),
+            &camera::Pan::default().to_raw(bounds.size(), scale),
+        );
+
+        pipeline
+            .audio()
+            .write(&self.queue, &audio::texture(self.audio.as_deref(), time));
+
+        let target = target(device, width, height);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("halo.headless.encoder"),
+        });
+
+        pipeline.render(
+            &mut encoder,
+            &offscreen,
+            settings.clear.to_wgpu(),
+            &self.mesh,
+            &bindings,
+            self.timer.as_ref(),
+        );
+
+        if let Some(timer) = &self.timer {
+            timer.resolve(&mut encoder);
+        }
+
+        self.blit.render(
+            &mut encoder,
+            &target.create_view(&wgpu::TextureViewDescriptor::default()),
+            Rectangle {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            },
+            &offscreen.bind_group,
+        );
+
+        self.queue.submit([encoder.finish()]);
+
+        if let Some(error) = device.pop_error_scope().await {
+            return Err(Error::Render(error.to_string()));
+        }
+
+        let image = read(device, &self.queue, &target)?;
+
+        // the timestamps are mapped on the first poll, & read on the next once that's completed
+        self.gpu_time.set(self.timer.as_ref().and_then(|timer| {
+            let _ = timer.poll();
+            device.poll(wgpu::Maintain::Wait);
+            timer.poll()
+        }));
+
+        Ok(image)
+    }
+}
+
+/// Requests a device from the first adapter available, falling back to a software adapter if
+/// there's no hardware one.
+pub(crate) async fn device() -> Result<(wgpu::Device, wgpu::Queue), Error> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+
+    let mut adapter = None;
+
+    for force_fallback_adapter in [false, true] {
+        adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                force_fallback_adapter,
+                ..Default::default()
+            })
+            .await;
+
+        if adapter.is_some() {
+            break;
+        }
+    }
+
+    let adapter = adapter.ok_or(Error::NoAdapter)?;
+
+    // the same limits iced requests, so shaders which render here render in the viewer, with
+    // timestamp queries for timing them where the adapter has them
+    adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("halo.headless.device"),
+                features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
+                limits: wgpu::Limits {
+                    max_bind_groups: 2,
+                    ..wgpu::Limits::downlevel_defaults()
+                }
+                .using_resolution(adapter.limits()),
+            },
+            None,
+        )
+        .await
+        .map_err(|error| Error::RequestDevice(error.to_string()))
+}
+
+/// A texture of [`FORMAT`] to render a frame into & read it back from.
+pub(crate) fn target(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("halo.headless.target"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+/// Reads back a [`target`] once the frame rendered into it has been submitted.
+pub(crate) fn read(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    target: &wgpu::Texture,
+) -> Result<Image, Error> {
+    let width = target.width();
+    let height = target.height();
+
+    let bytes_per_row =
+        (width * BYTES_PER_PIXEL).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("halo.headless.readback"),
+        size: u64::from(bytes_per_row * height),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("halo.headless.readback.encoder"),
+    });
+
+    encoder.copy_texture_to_buffer(
+        target.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &readback,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: None,
+            },
+        },
+        target.size(),
+    );
+
+    queue.submit([encoder.finish()]);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    readback
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+
+    device.poll(wgpu::Maintain::Wait);
+
+    receiver
+        .recv()
+        .ok()
+        .and_then(Result::ok)
+        .ok_or(Error::Readback)?;
+
+    let pixels = readback
+        .slice(..)
+        .get_mapped_range()
+        .chunks(bytes_per_row as usize)
+        .flat_map(|row| &row[..(width * BYTES_PER_PIXEL) as usize])
+        .copied()
+        .collect();
+
+    Ok(Image {
+        width,
+        height,
+        pixels,
+    })
+}
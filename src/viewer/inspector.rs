@@ -0,0 +1,285 @@
+use crate::theme::Theme;
+use crate::viewer::offscreen::Offscreen;
+use crate::viewer::readback::Readback;
+use crate::widget::Renderer;
+use iced::widget::canvas;
+use iced::widget::shader::wgpu;
+use iced::{mouse, Color, Point, Rectangle, Size};
+use std::sync::{Arc, Mutex};
+
+/// The width & height of the region read back around the inspected pixel, shown in the loupe.
+pub const LOUPE_SIZE: u32 = 11;
+
+/// Bytes per pixel of [`crate::viewer::offscreen::FRAME_FORMAT`].
+const PIXEL_SIZE: u32 = 8;
+
+const BYTES_PER_ROW: u32 = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+/// The shader's output around the inspected pixel, read back from the offscreen frame.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    /// The inspected pixel.
+    pub pixel: [u32; 2],
+    /// The size of the frame the sample was read from, i.e. `uniforms.scale`.
+    pub frame_size: Size<u32>,
+    /// The top left pixel of the region that was read back.
+    origin: [u32; 2],
+    extent: [u32; 2],
+    /// The linear RGBA output of `fs_main` of every pixel in the region, row by row.
+    colors: Vec<[f32; 4]>,
+}
+
+pub type SharedSample = Arc<Mutex<Option<Sample>>>;
+
+impl Sample {
+    /// The color at `pixel`, if it was read back.
+    pub fn get(&self, pixel: [i64; 2]) -> Option<[f32; 4]> {
+        let x = pixel[0] - i64::from(self.origin[0]);
+        let y = pixel[1] - i64::from(self.origin[1]);
+
+        if x < 0 || y < 0 || x >= i64::from(self.extent[0]) || y >= i64::from(self.extent[1]) {
+            return None;
+        }
+
+        self.colors
+            .get(y as usize * self.extent[0] as usize + x as usize)
+            .copied()
+    }
+
+    /// The output of `fs_main` at the inspected pixel.
+    pub fn color(&self) -> [f32; 4] {
+        self.get(self.pixel.map(i64::from)).unwrap_or_default()
+    }
+
+    /// The `uv` of the inspected pixel, as computed with `clip_pos.xy / uniforms.scale`.
+    pub fn uv(&self) -> [f32; 2] {
+        [
+            (self.pixel[0] as f32 + 0.5) / self.frame_size.width as f32,
+            (self.pixel[1] as f32 + 0.5) / self.frame_size.height as f32,
+        ]
+    }
+
+    /// The color at the inspected pixel as it's displayed: clamped & encoded as 8-bit sRGB.
+    pub fn rgba8(&self) -> [u8; 4] {
+        let [r, g, b, a] = self.color();
+
+        [
+            to_srgb(r),
+            to_srgb(g),
+            to_srgb(b),
+            (a.clamp(0.0, 1.0) * 255.0).round() as u8,
+        ]
+    }
+}
+
+impl std::fmt::Display for Sample {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [x, y] = self.pixel;
+        let [u, v] = self.uv();
+        let [r, g, b, a] = self.color();
+        let [r8, g8, b8, a8] = self.rgba8();
+
+        write!(
+            f,
+            "pixel: ({x}, {y}) uv: ({u:.4}, {v:.4}) rgba: ({r:.4}, {g:.4}, {b:.4}, {a:.4}) \
+            rgba8: ({r8}, {g8}, {b8}, {a8})"
+        )
+    }
+}
+
+/// Copies the region around the inspected pixel out of the offscreen frame, & reads it back on a
+/// later frame.
+pub struct Inspector {
+    readback: Readback<Region>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Region {
+    pixel: [u32; 2],
+    frame_size: Size<u32>,
+    origin: [u32; 2],
+    extent: [u32; 2],
+}
+
+impl Inspector {
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self {
+            readback: Readback::new(
+                device,
+                "halo.inspector.readback",
+                u64::from(BYTES_PER_ROW * LOUPE_SIZE),
+            ),
+        }
+    }
+
+    /// Copies the region around `pixel` out of the frame, unless the last copy is still being
+    /// read back.
+    pub fn copy(&self, encoder: &mut wgpu::CommandEncoder, offscreen: &Offscreen, pixel: [u32; 2]) {
+        if !self.readback.is_idle() {
+            return;
+        }
+
+        let frame_size = offscreen.size();
+        let pixel = [
+            pixel[0].min(frame_size.width.saturating_sub(1)),
+            pixel[1].min(frame_size.height.saturating_sub(1)),
+        ];
+        let extent = [
+            LOUPE_SIZE.min(frame_size.width),
+            LOUPE_SIZE.min(frame_size.height),
+        ];
+        let origin = [
+            pixel[0]
+                .saturating_sub(LOUPE_SIZE / 2)
+                .min(frame_size.width - extent[0]),
+            pixel[1]
+                .saturating_sub(LOUPE_SIZE / 2)
+                .min(frame_size.height - extent[1]),
+        ];
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &offscreen.frame,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: origin[0],
+                    y: origin[1],
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: self.readback.buffer(),
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(BYTES_PER_ROW),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: extent[0],
+                height: extent[1],
+                depth_or_array_layers: 1,
+            },
+        );
+
+        self.readback.copied(Region {
+            pixel,
+            frame_size,
+            origin,
+            extent,
+        });
+    }
+
+    /// Returns the region copied on an earlier frame, once it's been read back.
+    pub fn poll(&self) -> Option<Sample> {
+        self.readback.poll(|data, region| {
+            let colors = data
+                .chunks(BYTES_PER_ROW as usize)
+                .take(region.extent[1] as usize)
+                .flat_map(|row| {
+                    row.chunks_exact(PIXEL_SIZE as usize)
+                        .take(region.extent[0] as usize)
+                        .map(|pixel| {
+                            [0, 1, 2, 3].map(|i| {
+                                f16_to_f32(u16::from_le_bytes([pixel[i * 2], pixel[i * 2 + 1]]))
+                            })
+                        })
+                })
+                .collect();
+
+            Sample {
+                pixel: region.pixel,
+                frame_size: region.frame_size,
+                origin: region.origin,
+                extent: region.extent,
+                colors,
+            }
+        })
+    }
+}
+
+/// A magnified view of the pixels around the inspected one.
+pub struct Loupe<'a>(pub &'a Sample);
+
+impl<'a, Message> canvas::Program<Message, Renderer> for Loupe<'a> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let cell = Size::new(
+            bounds.width / LOUPE_SIZE as f32,
+            bounds.height / LOUPE_SIZE as f32,
+        );
+
+        let half = i64::from(LOUPE_SIZE / 2);
+        let [x, y] = self.0.pixel.map(i64::from);
+
+        for row in 0..LOUPE_SIZE {
+            for column in 0..LOUPE_SIZE {
+                let pixel = [x + i64::from(column) - half, y + i64::from(row) - half];
+
+                let color = self
+                    .0
+                    .get(pixel)
+                    .map_or(Color::TRANSPARENT, |[r, g, b, _]| {
+                        Color::from_linear_rgba(
+                            r.clamp(0.0, 1.0),
+                            g.clamp(0.0, 1.0),
+                            b.clamp(0.0, 1.0),
+                            1.0,
+                        )
+                    });
+
+                frame.fill_rectangle(
+                    Point::new(column as f32 * cell.width, row as f32 * cell.height),
+                    cell,
+                    color,
+                );
+            }
+        }
+
+        // outline the inspected pixel
+        let center = Point::new(half as f32 * cell.width, half as f32 * cell.height);
+        frame.stroke(
+            &canvas::Path::rectangle(center, cell),
+            canvas::Stroke::default()
+                .with_color(theme.palette().accent)
+                .with_width(2.0),
+        );
+
+        vec![frame.into_geometry()]
+    }
+}
+
+fn to_srgb(linear: f32) -> u8 {
+    let linear = linear.clamp(0.0, 1.0);
+
+    let srgb = if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+
+    (srgb * 255.0).round() as u8
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = i32::from((half >> 10) & 0x1f);
+    let mantissa = f32::from(half & 0x3ff);
+
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
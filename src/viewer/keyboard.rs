@@ -0,0 +1,220 @@
+//! The keys held down while the viewer is focused, for interactive shaders, like Shadertoy's
+//! keyboard texture.
+//!
+//! `halo_keyboard` is a [`KEYS`] x 3 texture indexed by JavaScript key code, e.g. 65 for `A` or
+//! 37 for the left arrow, so shaders written for Shadertoy read the same keys:
+//!
+//! ```wgsl
+//! let is_held = textureLoad(halo_keyboard, vec2<i32>(65, 0), 0).r > 0.5;
+//! let was_pressed = textureLoad(halo_keyboard, vec2<i32>(65, 1), 0).r > 0.5;
+//! let is_toggled = textureLoad(halo_keyboard, vec2<i32>(65, 2), 0).r > 0.5;
+//! ```
+use iced::keyboard::KeyCode;
+use iced::Size;
+
+/// The binding of `halo_keyboard` in halo's bind group.
+pub const BINDING: u32 = 4;
+
+/// The number of key codes in each row of `halo_keyboard`.
+pub const KEYS: u32 = 256;
+
+/// The size of `halo_keyboard`; its rows are the held, just pressed & toggled keys.
+pub const SIZE: Size<u32> = Size {
+    width: KEYS,
+    height: 3,
+};
+
+#[derive(Debug, Clone)]
+pub struct Keyboard {
+    /// Counts the frames drawn, to tell which keys were pressed since the last one.
+    frame: u64,
+    keys: Vec<Key>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Key {
+    is_held: bool,
+    is_toggled: bool,
+    /// The first frame drawn after the key was last pressed.
+    pressed_on: Option<u64>,
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self {
+            frame: 0,
+            keys: vec![Key::default(); KEYS as usize],
+        }
+    }
+}
+
+impl Keyboard {
+    /// Records a key press. Repeated presses while a key is held are ignored.
+    pub fn press(&mut self, key_code: KeyCode) {
+        let frame = self.frame;
+
+        if let Some(key) = self.key(key_code).filter(|key| !key.is_held) {
+            key.is_held = true;
+            key.is_toggled = !key.is_toggled;
+            key.pressed_on = Some(frame + 1);
+        }
+    }
+
+    pub fn release(&mut self, key_code: KeyCode) {
+        if let Some(key) = self.key(key_code) {
+            key.is_held = false;
+        }
+    }
+
+    /// Releases every held key, e.g. once the viewer loses focus & stops receiving key releases.
+    pub fn release_all(&mut self) {
+        for key in &mut self.keys {
+            key.is_held = false;
+        }
+    }
+
+    /// Called before each frame is drawn, so keys are only just pressed for a single frame.
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// The contents of `halo_keyboard`.
+    pub fn texture(&self) -> Vec<u8> {
+        let row = |texel: fn(&Key, u64) -> bool| {
+            self.keys
+                .iter()
+                .map(move |key| if texel(key, self.frame) { u8::MAX } else { 0 })
+        };
+
+        row(|key, _| key.is_held)
+            .chain(row(|key, frame| key.pressed_on == Some(frame)))
+            .chain(row(|key, _| key.is_toggled))
+            .collect()
+    }
+
+    fn key(&mut self, key_code: KeyCode) -> Option<&mut Key> {
+        self.keys.get_mut(usize::from(code(key_code)?))
+    }
+}
+
+/// The JavaScript key code of a key, where it has one.
+fn code(key_code: KeyCode) -> Option<u8> {
+    use KeyCode as K;
+
+    let letters = [
+        K::A,
+        K::B,
+        K::C,
+        K::D,
+        K::E,
+        K::F,
+        K::G,
+        K::H,
+        K::I,
+        K::J,
+        K::K,
+        K::L,
+        K::M,
+        K::N,
+        K::O,
+        K::P,
+        K::Q,
+        K::R,
+        K::S,
+        K::T,
+        K::U,
+        K::V,
+        K::W,
+        K::X,
+        K::Y,
+        K::Z,
+    ];
+    let digits = [
+        K::Key0,
+        K::Key1,
+        K::Key2,
+        K::Key3,
+        K::Key4,
+        K::Key5,
+        K::Key6,
+        K::Key7,
+        K::Key8,
+        K::Key9,
+    ];
+    let numpad = [
+        K::Numpad0,
+        K::Numpad1,
+        K::Numpad2,
+        K::Numpad3,
+        K::Numpad4,
+        K::Numpad5,
+        K::Numpad6,
+        K::Numpad7,
+        K::Numpad8,
+        K::Numpad9,
+    ];
+    let functions = [
+        K::F1,
+        K::F2,
+        K::F3,
+        K::F4,
+        K::F5,
+        K::F6,
+        K::F7,
+        K::F8,
+        K::F9,
+        K::F10,
+        K::F11,
+        K::F12,
+    ];
+
+    let offset = |keys: &[KeyCode], first: u8| {
+        keys.iter()
+            .position(|key| *key == key_code)
+            .map(|i| first + i as u8)
+    };
+
+    offset(&letters, 65)
+        .or_else(|| offset(&digits, 48))
+        .or_else(|| offset(&numpad, 96))
+        .or_else(|| offset(&functions, 112))
+        .or(match key_code {
+            K::Backspace => Some(8),
+            K::Tab => Some(9),
+            K::Enter | K::NumpadEnter => Some(13),
+            K::LShift | K::RShift => Some(16),
+            K::LControl | K::RControl => Some(17),
+            K::LAlt | K::RAlt => Some(18),
+            K::Pause => Some(19),
+            K::Capital => Some(20),
+            K::Escape => Some(27),
+            K::Space => Some(32),
+            K::PageUp => Some(33),
+            K::PageDown => Some(34),
+            K::End => Some(35),
+            K::Home => Some(36),
+            K::Left => Some(37),
+            K::Up => Some(38),
+            K::Right => Some(39),
+            K::Down => Some(40),
+            K::Insert => Some(45),
+            K::Delete => Some(46),
+            K::NumpadMultiply => Some(106),
+            K::NumpadAdd => Some(107),
+            K::NumpadSubtract => Some(109),
+            K::NumpadDecimal => Some(110),
+            K::NumpadDivide => Some(111),
+            K::Semicolon => Some(186),
+            K::Equals => Some(187),
+            K::Comma => Some(188),
+            K::Minus => Some(189),
+            K::Period => Some(190),
+            K::Slash => Some(191),
+            K::Grave => Some(192),
+            K::LBracket => Some(219),
+            K::Backslash => Some(220),
+            K::RBracket => Some(221),
+            K::Apostrophe => Some(222),
+            _ => None,
+        })
+}
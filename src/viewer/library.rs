@@ -0,0 +1,37 @@
+//! WGSL libraries a shader can pull in with an `//#import` directive.
+//!
+//! Imported libraries are appended after the shader rather than prepended like the prelude, so
+//! the shader's spans still line up with the editor's when reporting errors.
+use crate::viewer::directive::{self, Directive};
+
+const LIBRARIES: [(&str, &str); 2] = [
+    ("sdf", include_str!("shaders/sdf.wgsl")),
+    ("raymarch", include_str!("shaders/raymarch.wgsl")),
+];
+
+/// The names of every library, for error messages.
+pub const NAMES: [&str; 2] = [LIBRARIES[0].0, LIBRARIES[1].0];
+
+/// The name of the library called `name`, if there's one.
+pub fn find(name: &str) -> Option<&'static str> {
+    NAMES.into_iter().find(|library| *library == name)
+}
+
+/// The source of every library `shader` imports, each preceded by a newline, to append to it.
+///
+/// A library is only appended once, however many times it's imported.
+pub fn imports(shader: &str) -> String {
+    let imported = directive::parse(shader)
+        .into_iter()
+        .filter_map(|(_range, directive)| match directive {
+            Ok(Directive::Import(name)) => Some(name),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    LIBRARIES
+        .iter()
+        .filter(|(name, _)| imported.contains(name))
+        .map(|(_, source)| format!("\n{source}"))
+        .collect()
+}
@@ -0,0 +1,262 @@
+use glam::Vec3;
+use iced::widget::shader::wgpu;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const FILE_EXT_FILTERS: [&str; 3] = ["obj", "gltf", "glb"];
+
+/// A vertex as it's laid out in the vertex buffer. Custom vertex shaders can read each attribute
+/// with `@location(0) position: vec3<f32>`, `@location(1) normal: vec3<f32>` and
+/// `@location(2) uv: vec2<f32>`.
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+impl Vertex {
+    pub const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2];
+}
+
+/// An indexed triangle mesh, normalized to fit within a unit sphere around the origin.
+#[derive(Debug)]
+pub struct Mesh {
+    /// Hash of the mesh's source, used to look up its GPU buffers.
+    pub id: u64,
+    pub name: String,
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    fn new(name: String, mut vertices: Vec<Vertex>, indices: Vec<u32>, has_normals: bool) -> Self {
+        if !has_normals {
+            compute_normals(&mut vertices, &indices);
+        }
+
+        normalize(&mut vertices);
+
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        bytemuck::cast_slice::<_, u8>(&vertices).hash(&mut hasher);
+        indices.hash(&mut hasher);
+
+        Self {
+            id: hasher.finish(),
+            name,
+            vertices,
+            indices,
+        }
+    }
+
+    /// A unit cube, used when a custom vertex shader is active but no mesh has been loaded.
+    pub fn cube() -> Self {
+        let faces = [
+            (Vec3::X, Vec3::Y),
+            (Vec3::NEG_X, Vec3::Y),
+            (Vec3::Y, Vec3::NEG_Z),
+            (Vec3::NEG_Y, Vec3::Z),
+            (Vec3::Z, Vec3::Y),
+            (Vec3::NEG_Z, Vec3::Y),
+        ];
+
+        let mut vertices = Vec::with_capacity(24);
+        let mut indices = Vec::with_capacity(36);
+
+        for (normal, up) in faces {
+            let right = up.cross(normal);
+            let base = vertices.len() as u32;
+
+            for (u, v) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+                let position = normal + right * (u * 2.0 - 1.0) + up * (v * 2.0 - 1.0);
+
+                vertices.push(Vertex {
+                    position: (position * 0.5).into(),
+                    normal: normal.into(),
+                    uv: [u, 1.0 - v],
+                });
+            }
+
+            indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+
+        Self::new("cube".to_string(), vertices, indices, true)
+    }
+}
+
+pub async fn open() -> Result<Arc<Mesh>, Error> {
+    let file = rfd::AsyncFileDialog::new()
+        .add_filter("supported mesh formats", &FILE_EXT_FILTERS)
+        .set_title("Open a mesh...")
+        .pick_file()
+        .await
+        .ok_or(Error::DialogueClosed)?;
+
+    load(file.path().to_owned()).await
+}
+
+pub async fn load(path: PathBuf) -> Result<Arc<Mesh>, Error> {
+    tokio::task::spawn_blocking(move || {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("obj") => load_obj(&path),
+            Some("gltf" | "glb") => load_gltf(&path),
+            _ => Err(Error::UnsupportedFormat),
+        }
+        .map(Arc::new)
+    })
+    .await
+    .map_err(|_| Error::Load("Mesh loading was interrupted".to_string()))?
+}
+
+fn load_obj(path: &Path) -> Result<Mesh, Error> {
+    let (models, _materials) = tobj::load_obj(
+        path,
+        &tobj::LoadOptions {
+            triangulate: true,
+            single_index: true,
+            ..Default::default()
+        },
+    )
+    .map_err(|error| Error::Load(error.to_string()))?;
+
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    let mut has_normals = true;
+
+    for model in models {
+        let mesh = model.mesh;
+        let base = vertices.len() as u32;
+        has_normals &= !mesh.normals.is_empty();
+
+        for i in 0..mesh.positions.len() / 3 {
+            vertices.push(Vertex {
+                position: [
+                    mesh.positions[i * 3],
+                    mesh.positions[i * 3 + 1],
+                    mesh.positions[i * 3 + 2],
+                ],
+                normal: mesh
+                    .normals
+                    .get(i * 3..i * 3 + 3)
+                    .map_or([0.0; 3], |n| [n[0], n[1], n[2]]),
+                uv: mesh
+                    .texcoords
+                    .get(i * 2..i * 2 + 2)
+                    .map_or([0.0; 2], |uv| [uv[0], uv[1]]),
+            });
+        }
+
+        indices.extend(mesh.indices.iter().map(|index| base + index));
+    }
+
+    Ok(Mesh::new(file_name(path), vertices, indices, has_normals))
+}
+
+fn load_gltf(path: &Path) -> Result<Mesh, Error> {
+    let (document, buffers, _images) =
+        gltf::import(path).map_err(|error| Error::Load(error.to_string()))?;
+
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    let mut has_normals = true;
+
+    for primitive in document.meshes().flat_map(|mesh| mesh.primitives()) {
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+            continue;
+        }
+
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..]));
+        let Some(positions) = reader.read_positions() else {
+            continue;
+        };
+
+        let base = vertices.len() as u32;
+        let positions = positions.collect::<Vec<_>>();
+        let normals = reader
+            .read_normals()
+            .map(Iterator::collect::<Vec<_>>)
+            .unwrap_or_default();
+        let uvs = reader
+            .read_tex_coords(0)
+            .map(|uvs| uvs.into_f32().collect::<Vec<_>>())
+            .unwrap_or_default();
+        has_normals &= !normals.is_empty();
+
+        vertices.extend(positions.iter().enumerate().map(|(i, position)| Vertex {
+            position: *position,
+            normal: normals.get(i).copied().unwrap_or_default(),
+            uv: uvs.get(i).copied().unwrap_or_default(),
+        }));
+
+        match reader.read_indices() {
+            Some(read) => indices.extend(read.into_u32().map(|index| base + index)),
+            None => indices.extend(base..base + positions.len() as u32),
+        }
+    }
+
+    if vertices.is_empty() {
+        return Err(Error::Load("No triangle meshes found".to_string()));
+    }
+
+    Ok(Mesh::new(file_name(path), vertices, indices, has_normals))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or("mesh".to_string(), |name| {
+        name.to_string_lossy().to_string()
+    })
+}
+
+/// Computes smooth vertex normals by averaging the normals of every face a vertex is a part of.
+fn compute_normals(vertices: &mut [Vertex], indices: &[u32]) {
+    let mut normals = vec![Vec3::ZERO; vertices.len()];
+
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| triangle[i] as usize);
+        let [pa, pb, pc] = [a, b, c].map(|i| Vec3::from(vertices[i].position));
+        let normal = (pb - pa).cross(pc - pa);
+
+        for i in [a, b, c] {
+            normals[i] += normal;
+        }
+    }
+
+    for (vertex, normal) in vertices.iter_mut().zip(normals) {
+        vertex.normal = normal.normalize_or_zero().into();
+    }
+}
+
+/// Centers the mesh on the origin & scales it to fit within a unit sphere, so that any mesh is
+/// framed by the default camera.
+fn normalize(vertices: &mut [Vertex]) {
+    let (min, max) = vertices.iter().fold(
+        (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+        |(min, max), vertex| {
+            let position = Vec3::from(vertex.position);
+            (min.min(position), max.max(position))
+        },
+    );
+
+    let center = (min + max) / 2.0;
+    let radius = vertices
+        .iter()
+        .map(|vertex| Vec3::from(vertex.position).distance(center))
+        .fold(0.0, f32::max);
+    let scale = if radius > 0.0 { 1.0 / radius } else { 1.0 };
+
+    for vertex in vertices {
+        vertex.position = ((Vec3::from(vertex.position) - center) * scale).into();
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    DialogueClosed,
+    UnsupportedFormat,
+    Load(String),
+}
@@ -1,22 +1,86 @@
-use crate::viewer::uniforms;
+use crate::viewer::audio;
+use crate::viewer::keyboard;
+use crate::viewer::mesh::{self, Mesh};
+use crate::viewer::offscreen::{self, Offscreen};
+use crate::viewer::probe;
+use crate::viewer::resources::{self, Resources};
+use crate::viewer::settings::Settings;
+use crate::viewer::shader::{Binding, Reflection, Shader};
+use crate::viewer::texture::Texture;
+use crate::viewer::timer::GpuTimer;
+use crate::viewer::{camera, uniforms};
 use iced::widget::shader::wgpu;
-use iced::Rectangle;
+use iced::widget::shader::wgpu::util::DeviceExt;
+use iced::Size;
 use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 
 pub struct Pipeline {
-    pub version: usize,
+    /// Hash of the shader & the settings the pipeline was built with.
+    pub id: u64,
     uniforms: wgpu::Buffer,
+    camera: wgpu::Buffer,
+    pan: wgpu::Buffer,
     bind_group: wgpu::BindGroup,
     pipeline: wgpu::RenderPipeline,
+    /// Whether this pipeline renders a mesh with the user's vertex shader, or a full-screen quad.
+    has_vertex_shader: bool,
+    user_layout: wgpu::BindGroupLayout,
+    render_bindings: Vec<Binding>,
+    compute_passes: Vec<ComputePass>,
+    /// Bound to halo's bind group when the shader has `halo_probe` calls.
+    probes: Option<probe::Buffer>,
+    /// `halo_audio` & `halo_keyboard`, rewritten every frame.
+    audio: Texture,
+    keyboard: Texture,
+}
+
+struct ComputePass {
+    pipeline: wgpu::ComputePipeline,
+    layout: wgpu::BindGroupLayout,
+    bindings: Vec<Binding>,
+    workgroup_size: [u32; 3],
+    workgroups: Option<[u32; 3]>,
+}
+
+/// The user's resources, bound to the layouts of a [`Pipeline`].
+///
+/// They're kept apart from the cached pipeline, so switching back to a cached shader starts its
+/// compute passes afresh instead of resuming the state they left behind.
+pub struct Bindings {
+    /// The id of the pipeline they're bound to.
+    pub pipeline: u64,
+    resources: Resources,
+    render: wgpu::BindGroup,
+    compute: Vec<wgpu::BindGroup>,
+}
+
+impl Bindings {
+    /// The size of the frame the textures were created for.
+    pub fn size(&self) -> Size<u32> {
+        self.resources.size()
+    }
 }
 
 impl Pipeline {
-    pub fn new(
-        device: &wgpu::Device,
-        format: wgpu::TextureFormat,
-        shader: &str,
-        version: usize,
-    ) -> Self {
+    /// Pipelines are cached by their shader & the settings they're built with.
+    pub fn id(shader: &Shader, settings: &Settings) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (shader.id, settings.msaa, settings.blend).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn new(device: &wgpu::Device, shader: &Shader, settings: &Settings) -> Self {
+        let Reflection {
+            vertex_entry_point,
+            compute_passes,
+            render_bindings,
+            ..
+        } = &shader.reflection;
+
         let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("halo.pipeline.uniforms"),
             size: std::mem::size_of::<uniforms::Raw>() as u64,
@@ -24,111 +88,372 @@ impl Pipeline {
             mapped_at_creation: false,
         });
 
+        let camera = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("halo.pipeline.camera"),
+            size: std::mem::size_of::<camera::Raw>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let pan = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("halo.pipeline.pan"),
+            size: std::mem::size_of::<camera::PanRaw>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let uniform_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let probes =
+            (!shader.probes.is_empty()).then(|| probe::Buffer::new(device, shader.probes.len()));
+
+        let audio = Texture::new(device, "halo.pipeline.audio", audio::BINDING, audio::SIZE);
+        let keyboard = Texture::new(
+            device,
+            "halo.pipeline.keyboard",
+            keyboard::BINDING,
+            keyboard::SIZE,
+        );
+
+        let mut layout_entries = vec![
+            uniform_entry(0),
+            uniform_entry(1),
+            audio.layout_entry(),
+            keyboard.layout_entry(),
+            uniform_entry(5),
+        ];
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(uniforms.as_entire_buffer_binding()),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Buffer(camera.as_entire_buffer_binding()),
+            },
+            audio.bind_group_entry(),
+            keyboard.bind_group_entry(),
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: wgpu::BindingResource::Buffer(pan.as_entire_buffer_binding()),
+            },
+        ];
+
+        if let Some(probes) = &probes {
+            layout_entries.push(probe::Buffer::layout_entry());
+            entries.push(probes.bind_group_entry());
+        }
+
         let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("halo.pipeline.uniform_bind_group_layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
+            entries: &layout_entries,
         });
 
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("halo.pipeline.uniform_bind_group"),
             layout: &layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::Buffer(uniforms.as_entire_buffer_binding()),
-            }],
+            entries: &entries,
         });
 
+        let user_layout = resources::bind_group_layout(
+            device,
+            render_bindings,
+            wgpu::ShaderStages::VERTEX_FRAGMENT,
+        );
+
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("halo.pipeline.layout"),
-            bind_group_layouts: &[&layout],
+            bind_group_layouts: &[&layout, &user_layout],
             push_constant_ranges: &[],
         });
 
-        let vertex_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("halo.pipeline.shader"),
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(concat!(
-                include_str!("shaders/uniforms.wgsl"),
-                "\n",
-                include_str!("shaders/vertex_shader.wgsl"),
-            ))),
-        });
-
         let fragment_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("halo.pipeline.fragment_shader"),
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(
-                format!("{}\n{}", include_str!("shaders/uniforms.wgsl"), shader).as_str(),
-            )),
+            source: match &shader.module {
+                Some(module) => wgpu::ShaderSource::Naga(Cow::Owned(module.clone())),
+                None => wgpu::ShaderSource::Wgsl(Cow::Borrowed(&shader.source)),
+            },
         });
 
+        let vertex_buffers = [wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<mesh::Vertex>() as u64,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &mesh::Vertex::ATTRIBUTES,
+        }];
+
+        let built_in_vertex_shader;
+        let (vertex, depth_stencil) = if let Some(entry_point) = vertex_entry_point {
+            (
+                wgpu::VertexState {
+                    module: &fragment_shader,
+                    entry_point,
+                    buffers: &vertex_buffers,
+                },
+                Some(wgpu::DepthStencilState {
+                    format: DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: Default::default(),
+                    bias: Default::default(),
+                }),
+            )
+        } else {
+            built_in_vertex_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("halo.pipeline.shader"),
+                source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(concat!(
+                    include_str!("shaders/uniforms.wgsl"),
+                    "\n",
+                    include_str!("shaders/vertex_shader.wgsl"),
+                ))),
+            });
+
+            (
+                wgpu::VertexState {
+                    module: &built_in_vertex_shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                None,
+            )
+        };
+
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("halo.pipeline.pipeline"),
             layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &vertex_shader,
-                entry_point: "vs_main",
-                buffers: &[],
-            },
+            vertex,
             primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: Default::default(),
+            depth_stencil,
+            multisample: wgpu::MultisampleState {
+                count: settings.msaa.sample_count(),
+                ..Default::default()
+            },
             fragment: Some(wgpu::FragmentState {
                 module: &fragment_shader,
                 entry_point: "fs_main",
                 targets: &[Some(wgpu::ColorTargetState {
-                    format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    format: offscreen::FRAME_FORMAT,
+                    blend: Some(settings.blend.state()),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
             multiview: None,
         });
 
+        let compute_passes = compute_passes
+            .iter()
+            .map(|pass| {
+                let pass_layout = resources::bind_group_layout(
+                    device,
+                    &pass.bindings,
+                    wgpu::ShaderStages::COMPUTE,
+                );
+
+                let pipeline_layout =
+                    device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                        label: Some("halo.pipeline.compute.layout"),
+                        bind_group_layouts: &[&layout, &pass_layout],
+                        push_constant_ranges: &[],
+                    });
+
+                ComputePass {
+                    pipeline: device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                        label: Some("halo.pipeline.compute"),
+                        layout: Some(&pipeline_layout),
+                        module: &fragment_shader,
+                        entry_point: &pass.entry_point,
+                    }),
+                    layout: pass_layout,
+                    bindings: pass.bindings.clone(),
+                    workgroup_size: pass.workgroup_size,
+                    workgroups: pass.workgroups,
+                }
+            })
+            .collect();
+
         Self {
-            version,
+            id: Self::id(shader, settings),
             uniforms,
+            camera,
+            pan,
             bind_group,
             pipeline,
+            has_vertex_shader: vertex_entry_point.is_some(),
+            user_layout,
+            render_bindings: render_bindings.clone(),
+            compute_passes,
+            probes,
+            audio,
+            keyboard,
         }
     }
 
-    pub fn prepare(&mut self, queue: &wgpu::Queue, uniforms: &uniforms::Raw) {
+    pub fn probes(&self) -> Option<&probe::Buffer> {
+        self.probes.as_ref()
+    }
+
+    pub fn audio(&self) -> &Texture {
+        &self.audio
+    }
+
+    pub fn keyboard(&self) -> &Texture {
+        &self.keyboard
+    }
+
+    pub fn prepare(
+        &self,
+        queue: &wgpu::Queue,
+        uniforms: &uniforms::Raw,
+        camera: &camera::Raw,
+        pan: &camera::PanRaw,
+    ) {
         queue.write_buffer(&self.uniforms, 0, bytemuck::bytes_of(uniforms));
+        queue.write_buffer(&self.camera, 0, bytemuck::bytes_of(camera));
+        queue.write_buffer(&self.pan, 0, bytemuck::bytes_of(pan));
+    }
+
+    /// Creates the user's resources for a frame of `size`, zeroed, & binds them to this pipeline.
+    pub fn bindings(
+        &self,
+        device: &wgpu::Device,
+        reflection: &Reflection,
+        size: Size<u32>,
+    ) -> Bindings {
+        let resources = Resources::new(device, reflection, size);
+
+        Bindings {
+            pipeline: self.id,
+            render: resources.bind_group(device, &self.user_layout, &self.render_bindings),
+            compute: self.compute_bind_groups(device, &resources),
+            resources,
+        }
+    }
+
+    /// Resizes the textures of `bindings` to a frame of `size`, rebinding them to this pipeline.
+    /// Buffers keep their contents, so a simulation carries on through the resize.
+    pub fn resize(
+        &self,
+        device: &wgpu::Device,
+        bindings: &mut Bindings,
+        reflection: &Reflection,
+        size: Size<u32>,
+    ) {
+        bindings.resources.resize(device, reflection, size);
+
+        bindings.render =
+            bindings
+                .resources
+                .bind_group(device, &self.user_layout, &self.render_bindings);
+        bindings.compute = self.compute_bind_groups(device, &bindings.resources);
+    }
+
+    fn compute_bind_groups(
+        &self,
+        device: &wgpu::Device,
+        resources: &Resources,
+    ) -> Vec<wgpu::BindGroup> {
+        self.compute_passes
+            .iter()
+            .map(|pass| resources.bind_group(device, &pass.layout, &pass.bindings))
+            .collect()
     }
 
     pub fn render(
         &self,
         encoder: &mut wgpu::CommandEncoder,
-        target: &wgpu::TextureView,
-        bounds: Rectangle<u32>,
+        offscreen: &Offscreen,
+        clear: wgpu::Color,
+        mesh: &MeshBuffers,
+        bindings: &Bindings,
+        timer: Option<&GpuTimer>,
     ) {
+        if !self.compute_passes.is_empty() {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("halo.compute_pass"),
+                timestamp_writes: timer.map(GpuTimer::compute_pass_writes),
+            });
+
+            pass.set_bind_group(0, &self.bind_group, &[]);
+
+            for (compute, bind_group) in self.compute_passes.iter().zip(&bindings.compute) {
+                let size = bindings.size();
+                let [x, y, z] = compute.workgroups.unwrap_or([
+                    size.width.div_ceil(compute.workgroup_size[0]),
+                    size.height.div_ceil(compute.workgroup_size[1]),
+                    1,
+                ]);
+
+                pass.set_pipeline(&compute.pipeline);
+                pass.set_bind_group(1, bind_group, &[]);
+                pass.dispatch_workgroups(x, y, z);
+            }
+        }
+
+        let depth_stencil_attachment =
+            self.has_vertex_shader
+                .then(|| wgpu::RenderPassDepthStencilAttachment {
+                    view: &offscreen.depth,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                });
+
         let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("halo.render_pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: target,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Load,
-                    store: wgpu::StoreOp::Store,
-                },
-            })],
-            depth_stencil_attachment: None,
-            timestamp_writes: None,
+            color_attachments: &[Some(offscreen.color_attachment(clear))],
+            depth_stencil_attachment,
+            timestamp_writes: timer
+                .map(|timer| timer.render_pass_writes(self.compute_passes.is_empty())),
             occlusion_query_set: None,
         });
 
-        pass.set_scissor_rect(bounds.x, bounds.y, bounds.width, bounds.height);
-
         pass.set_pipeline(&self.pipeline);
         pass.set_bind_group(0, &self.bind_group, &[]);
-        pass.draw(0..6, 0..1);
+        pass.set_bind_group(1, &bindings.render, &[]);
+
+        if self.has_vertex_shader {
+            pass.set_vertex_buffer(0, mesh.vertices.slice(..));
+            pass.set_index_buffer(mesh.indices.slice(..), wgpu::IndexFormat::Uint32);
+            pass.draw_indexed(0..mesh.count, 0, 0..1);
+        } else {
+            pass.draw(0..6, 0..1);
+        }
+    }
+}
+
+/// The vertex & index buffers of a [`Mesh`].
+pub struct MeshBuffers {
+    pub id: u64,
+    vertices: wgpu::Buffer,
+    indices: wgpu::Buffer,
+    count: u32,
+}
+
+impl MeshBuffers {
+    pub fn new(device: &wgpu::Device, mesh: &Mesh) -> Self {
+        Self {
+            id: mesh.id,
+            vertices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("halo.pipeline.mesh.vertices"),
+                contents: bytemuck::cast_slice(&mesh.vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            }),
+            indices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("halo.pipeline.mesh.indices"),
+                contents: bytemuck::cast_slice(&mesh.indices),
+                usage: wgpu::BufferUsages::INDEX,
+            }),
+            count: mesh.indices.len() as u32,
+        }
     }
 }
@@ -1,14 +1,111 @@
-use crate::viewer::pipeline::Pipeline;
+use crate::viewer::audio::{self, Track};
+use crate::viewer::cache::Cache;
+use crate::viewer::camera;
+use crate::viewer::device::Device;
+use crate::viewer::inspector::{Inspector, SharedSample};
+use crate::viewer::mesh::Mesh;
+use crate::viewer::offscreen::{self, Blit, Offscreen};
+use crate::viewer::pipeline::{Bindings, MeshBuffers, Pipeline};
+use crate::viewer::probe;
+use crate::viewer::range::{self, RangeCheck, SharedCounts};
+use crate::viewer::settings::Settings;
+use crate::viewer::shader::Shader;
+use crate::viewer::stats::SharedTimings;
+use crate::viewer::timer::GpuTimer;
 use crate::viewer::uniforms::Uniforms;
 use iced::widget::shader::{Storage, Transformation};
-use iced::{Rectangle, Size};
-use std::sync::Arc;
+use iced::{Point, Rectangle, Size};
+use once_cell::sync::OnceCell;
+use std::collections::HashMap;
+use std::sync::atomic::{self, AtomicU64};
+use std::sync::{Arc, Weak};
+use std::time::Instant;
+
+/// Identifies a viewer's render state, which is shared by every viewer in iced's [`Storage`].
+///
+/// A viewer's state is dropped once the viewer & its primitives are.
+#[derive(Debug, Clone)]
+pub struct Id(Arc<u64>);
+
+impl Default for Id {
+    fn default() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+
+        Self(Arc::new(NEXT.fetch_add(1, atomic::Ordering::Relaxed)))
+    }
+}
 
 #[derive(Debug)]
 pub struct Primitive {
+    pub id: Id,
     pub uniforms: Uniforms,
-    pub shader: Arc<String>,
-    pub version: usize,
+    pub camera: camera::Raw,
+    pub pan: camera::Pan,
+    pub shader: Arc<Shader>,
+    pub settings: Settings,
+    pub mesh: Arc<Mesh>,
+    pub audio: Option<Arc<Track>>,
+    /// The contents of `halo_keyboard`.
+    pub keyboard: Vec<u8>,
+    pub device: Arc<OnceCell<Device>>,
+    pub timings: SharedTimings,
+    /// The position of the cursor within the viewer, when inspecting pixels.
+    pub inspect: Option<Point>,
+    pub sample: SharedSample,
+    /// Written with the values of the shader's probes at the inspected pixel.
+    pub probes: probe::SharedValues,
+    /// Whether to show the range check's false colors instead of the frame.
+    pub check_range: bool,
+    pub counts: SharedCounts,
+}
+
+impl Primitive {
+    /// The pixel of a frame of `size` which is being inspected, if any.
+    fn inspected_pixel(&self, size: Size<u32>) -> Option<[u32; 2]> {
+        let position = self.inspect?;
+        let bounds = self.uniforms.bounds;
+
+        Some([
+            (position.x / bounds.width * size.width as f32) as u32,
+            (position.y / bounds.height * size.height as f32) as u32,
+        ])
+    }
+}
+
+/// The render state of every viewer.
+struct State {
+    blit: Blit,
+    viewers: HashMap<u64, Instance>,
+}
+
+/// The render state of a single viewer.
+struct Instance {
+    viewer: Weak<u64>,
+    offscreen: Option<Offscreen>,
+    range_check: Option<RangeCheck>,
+    /// Binds the offscreen frame, so it's recreated along with it.
+    range_target: Option<range::Target>,
+    pipelines: Cache<Pipeline>,
+    bindings: Option<Bindings>,
+    mesh: Option<MeshBuffers>,
+    timer: Option<GpuTimer>,
+    inspector: Option<Inspector>,
+}
+
+impl Instance {
+    fn new(id: &Id) -> Self {
+        Self {
+            viewer: Arc::downgrade(&id.0),
+            offscreen: None,
+            range_check: None,
+            range_target: None,
+            pipelines: Cache::default(),
+            bindings: None,
+            mesh: None,
+            timer: None,
+            inspector: None,
+        }
+    }
 }
 
 impl iced::widget::shader::Primitive for Primitive {
@@ -19,21 +116,128 @@ impl iced::widget::shader::Primitive for Primitive {
         queue: &iced::widget::shader::wgpu::Queue,
         _target_size: Size<u32>,
         scale_factor: f32,
-        transform: Transformation,
+        _transform: Transformation,
         storage: &mut Storage,
     ) {
-        let should_store = storage
-            .get::<Pipeline>()
-            .map(|pipeline| pipeline.version < self.version)
-            .unwrap_or(true);
+        let start = Instant::now();
+
+        self.device.get_or_init(|| Device::new(device));
+
+        if !storage.has::<State>() {
+            storage.store(State {
+                blit: Blit::new(device, format),
+                viewers: HashMap::new(),
+            });
+        }
+
+        let State { blit, viewers } = storage.get_mut::<State>().unwrap();
+
+        viewers.retain(|_, instance| instance.viewer.strong_count() > 0);
+
+        let instance = viewers
+            .entry(*self.id.0)
+            .or_insert_with(|| Instance::new(&self.id));
+
+        let scale = scale_factor * self.settings.scale.factor();
+        let size = offscreen::frame_size(self.uniforms.bounds.size(), scale);
+        let sample_count = self.settings.msaa.sample_count();
+
+        let is_resized = instance.offscreen.as_ref().map_or(true, |offscreen| {
+            offscreen.size() != size || offscreen.sample_count() != sample_count
+        });
+
+        if is_resized {
+            instance.offscreen = Some(Offscreen::new(device, size, sample_count, blit));
+        }
 
-        if should_store {
-            storage.store(Pipeline::new(device, format, &self.shader, self.version));
+        let offscreen = instance.offscreen.as_ref().unwrap();
+
+        if self.check_range && instance.range_check.is_none() {
+            instance.range_check = Some(RangeCheck::new(device));
+        }
+
+        if let Some(range_check) = &instance.range_check {
+            if is_resized || instance.range_target.is_none() {
+                instance.range_target = Some(range_check.target(device, offscreen, blit));
+            }
+
+            if let Some(counts) = range_check.poll() {
+                if let Ok(mut shared) = self.counts.lock() {
+                    *shared = Some(counts);
+                }
+            }
+        }
+
+        let id = Pipeline::id(&self.shader, &self.settings);
+
+        let pipeline = instance
+            .pipelines
+            .get_or_insert_with(id, || Pipeline::new(device, &self.shader, &self.settings));
+
+        // a shader's resources start out zeroed each time it's switched to
+        match &mut instance.bindings {
+            Some(bindings) if bindings.pipeline == id => {
+                if bindings.size() != size {
+                    pipeline.resize(device, bindings, &self.shader.reflection, size);
+                }
+            }
+            bindings => {
+                *bindings = Some(pipeline.bindings(device, &self.shader.reflection, size));
+            }
         }
 
-        let pipeline = storage.get_mut::<Pipeline>().unwrap();
+        pipeline.prepare(
+            queue,
+            &self.uniforms.to_raw(size, scale),
+            &self.camera,
+            &self.pan.to_raw(self.uniforms.bounds.size(), scale),
+        );
+
+        pipeline.audio().write(
+            queue,
+            &audio::texture(self.audio.as_deref(), self.uniforms.time),
+        );
+        pipeline.keyboard().write(queue, &self.keyboard);
+
+        if let Some(probes) = pipeline.probes() {
+            probes.prepare(queue, self.inspected_pixel(size));
+
+            if let Some(values) = probes.poll() {
+                if let Ok(mut shared) = self.probes.lock() {
+                    *shared = Some(values);
+                }
+            }
+        }
+
+        if instance
+            .mesh
+            .as_ref()
+            .map_or(true, |buffers| buffers.id != self.mesh.id)
+        {
+            instance.mesh = Some(MeshBuffers::new(device, &self.mesh));
+        }
 
-        pipeline.prepare(queue, &self.uniforms.to_raw(scale_factor, transform));
+        if instance.timer.is_none() {
+            instance.timer = GpuTimer::new(device, queue);
+        }
+
+        let gpu = instance.timer.as_ref().and_then(GpuTimer::poll);
+
+        if self.inspect.is_some() && instance.inspector.is_none() {
+            instance.inspector = Some(Inspector::new(device));
+        }
+
+        if let Some(sample) = instance.inspector.as_ref().and_then(Inspector::poll) {
+            if let Ok(mut shared) = self.sample.lock() {
+                *shared = Some(sample);
+            }
+        }
+
+        if let Ok(mut timings) = self.timings.lock() {
+            timings.cpu = start.elapsed();
+            // frames aren't timed while the last measurement is read back
+            timings.gpu = gpu.or(timings.gpu);
+        }
     }
 
     fn render(
@@ -44,8 +248,262 @@ impl iced::widget::shader::Primitive for Primitive {
         _target_size: Size<u32>,
         encoder: &mut iced::widget::shader::wgpu::CommandEncoder,
     ) {
-        let pipeline = storage.get::<Pipeline>().unwrap();
+        let start = Instant::now();
+
+        let State { blit, viewers } = storage.get::<State>().unwrap();
+        let instance = &viewers[&*self.id.0];
+
+        let pipeline = instance
+            .pipelines
+            .get(Pipeline::id(&self.shader, &self.settings))
+            .unwrap();
+        let offscreen = instance.offscreen.as_ref().unwrap();
+        let timer = instance.timer.as_ref().filter(|timer| timer.is_idle());
+
+        pipeline.render(
+            encoder,
+            offscreen,
+            self.settings.clear.to_wgpu(),
+            instance.mesh.as_ref().unwrap(),
+            instance.bindings.as_ref().unwrap(),
+            timer,
+        );
+
+        if let Some(timer) = timer {
+            timer.resolve(encoder);
+        }
+
+        if let Some(pixel) = self.inspected_pixel(offscreen.size()) {
+            if let Some(inspector) = &instance.inspector {
+                inspector.copy(encoder, offscreen, pixel);
+            }
+
+            if let Some(probes) = pipeline.probes() {
+                probes.copy(encoder);
+            }
+        }
+
+        let mut bind_group = &offscreen.bind_group;
+
+        if self.check_range {
+            if let (Some(range_check), Some(range_target)) =
+                (&instance.range_check, &instance.range_target)
+            {
+                range_check.check(encoder, range_target);
+                bind_group = &range_target.blit;
+            }
+        }
+
+        blit.render(encoder, target, bounds, bind_group);
+
+        if let Ok(mut timings) = self.timings.lock() {
+            timings.cpu += start.elapsed();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::viewer::headless::{self, Image};
+    use crate::viewer::keyboard;
+    use crate::viewer::uniforms::Mouse;
+    use iced::futures::executor::block_on;
+    use iced::widget::shader::wgpu;
+    use iced::widget::shader::Primitive as _;
+    use std::time::Duration;
+
+    /// Both color pixels by their position in the frame, so a frame rendered at another viewer's
+    /// size or with its uniforms comes out differently.
+    const GRADIENT: &str = "
+        @fragment
+        fn fs_main(@builtin(position) clip_pos: vec4<f32>) -> @location(0) vec4<f32> {
+            return vec4<f32>(clip_pos.xy / uniforms.scale, 0.0, 1.0);
+        }
+    ";
+
+    const STRIPES: &str = "
+        @fragment
+        fn fs_main(@builtin(position) clip_pos: vec4<f32>) -> @location(0) vec4<f32> {
+            return vec4<f32>(fract(clip_pos.x / 8.0), 0.0, clip_pos.y / uniforms.scale.y, 1.0);
+        }
+    ";
+
+    /// Counts the frames it's rendered in a storage buffer.
+    const COUNTER: &str = "//#dispatch cs_main 1 1 1
+        @group(1) @binding(0) var<storage, read_write> frames: array<f32, 1>;
+        @group(1) @binding(0) var<storage, read> shown: array<f32, 1>;
+
+        @compute @workgroup_size(1)
+        fn cs_main() {
+            frames[0] += 1.0;
+        }
+
+        @fragment
+        fn fs_main(@builtin(position) clip_pos: vec4<f32>) -> @location(0) vec4<f32> {
+            return vec4<f32>(shown[0] / 255.0, 0.0, 0.0, 1.0);
+        }
+    ";
+
+    fn primitive(id: &Id, shader: &str, width: u32, height: u32) -> Primitive {
+        let bounds = Rectangle::new(Point::ORIGIN, Size::new(width as f32, height as f32));
+
+        Primitive {
+            id: id.clone(),
+            uniforms: Uniforms {
+                time: Duration::ZERO,
+                mouse: Mouse::default(),
+                bounds,
+            },
+            camera: camera::Orbit::default().to_raw(bounds.width / bounds.height),
+            pan: camera::Pan::default(),
+            shader: Arc::new(Shader::new(shader)),
+            settings: Settings::from_source(shader),
+            mesh: Arc::new(Mesh::cube()),
+            audio: None,
+            keyboard: vec![0; (keyboard::SIZE.width * keyboard::SIZE.height) as usize],
+            device: Arc::default(),
+            timings: SharedTimings::default(),
+            inspect: None,
+            sample: SharedSample::default(),
+            probes: probe::SharedValues::default(),
+            check_range: false,
+            counts: SharedCounts::default(),
+        }
+    }
+
+    /// Renders a frame of `primitives` the way iced does, preparing them all before rendering any.
+    fn render(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        storage: &mut Storage,
+        primitives: &[&Primitive],
+    ) -> Vec<Image> {
+        let sizes: Vec<_> = primitives
+            .iter()
+            .map(|primitive| {
+                let bounds = primitive.uniforms.bounds;
+
+                Size::new(bounds.width as u32, bounds.height as u32)
+            })
+            .collect();
+
+        for (primitive, size) in primitives.iter().zip(&sizes) {
+            primitive.prepare(
+                headless::FORMAT,
+                device,
+                queue,
+                *size,
+                1.0,
+                Transformation::orthographic(size.width, size.height),
+                storage,
+            );
+        }
+
+        let targets: Vec<_> = sizes
+            .iter()
+            .map(|size| headless::target(device, size.width, size.height))
+            .collect();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("halo.primitive.test.encoder"),
+        });
+
+        for ((primitive, size), target) in primitives.iter().zip(&sizes).zip(&targets) {
+            primitive.render(
+                storage,
+                Rectangle {
+                    x: 0,
+                    y: 0,
+                    width: size.width,
+                    height: size.height,
+                },
+                &target.create_view(&wgpu::TextureViewDescriptor::default()),
+                *size,
+                &mut encoder,
+            );
+        }
+
+        queue.submit([encoder.finish()]);
+
+        targets
+            .iter()
+            .map(|target| headless::read(device, queue, target).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn viewers_render_apart() {
+        let (device, queue) =
+            block_on(headless::device()).unwrap_or_else(|error| panic!("{error}"));
+
+        let gradient = primitive(&Id::default(), GRADIENT, 64, 32);
+        let stripes = primitive(&Id::default(), STRIPES, 32, 64);
+
+        let together = render(
+            &device,
+            &queue,
+            &mut Storage::default(),
+            &[&gradient, &stripes],
+        );
+
+        let apart: Vec<_> = [&gradient, &stripes]
+            .into_iter()
+            .flat_map(|primitive| render(&device, &queue, &mut Storage::default(), &[primitive]))
+            .collect();
+
+        assert!(
+            together == apart,
+            "viewers rendered together differ from rendered apart"
+        );
+    }
+
+    #[test]
+    fn drops_the_state_of_dropped_viewers() {
+        let (device, queue) =
+            block_on(headless::device()).unwrap_or_else(|error| panic!("{error}"));
+
+        let mut storage = Storage::default();
+        let kept = primitive(&Id::default(), GRADIENT, 16, 16);
+
+        render(
+            &device,
+            &queue,
+            &mut storage,
+            &[&kept, &primitive(&Id::default(), STRIPES, 16, 16)],
+        );
+        render(&device, &queue, &mut storage, &[&kept]);
+
+        let state = storage.get::<State>().unwrap();
+
+        assert_eq!(state.viewers.len(), 1);
+        assert!(state.viewers.contains_key(&*kept.id.0));
+    }
+
+    #[test]
+    fn storage_buffers_are_kept_across_resizes() {
+        let (device, queue) =
+            block_on(headless::device()).unwrap_or_else(|error| panic!("{error}"));
+
+        let mut storage = Storage::default();
+        let id = Id::default();
+
+        let first = render(
+            &device,
+            &queue,
+            &mut storage,
+            &[&primitive(&id, COUNTER, 16, 16)],
+        );
+        let resized = render(
+            &device,
+            &queue,
+            &mut storage,
+            &[&primitive(&id, COUNTER, 32, 32)],
+        );
 
-        pipeline.render(encoder, target, bounds);
+        assert!(
+            resized[0].pixels[0] > first[0].pixels[0],
+            "the frame count was reset by the resize"
+        );
     }
 }
@@ -0,0 +1,874 @@
+use crate::viewer::readback::Readback;
+use iced::widget::shader::wgpu;
+use std::fmt::Write;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+/// How many `halo_probe` calls a shader can have, i.e. the length of `halo_probes.values`.
+pub const MAX_PROBES: usize = 32;
+
+/// The binding of the probe buffer in halo's bind group.
+pub const BINDING: u32 = 2;
+
+const NAME: &str = "halo_probe";
+
+/// `halo_probe(` is replaced with this when erasing probes, & the closing parenthesis with
+/// `)[0]`. The spans of the erased shader move with them; [`Erased::to_shader`] maps them back.
+const ERASED: &str = "array(";
+
+/// Probes called as statements are assigned to `_` instead, since only calls can be statements.
+const ERASED_STATEMENT: &str = "_=array(";
+
+/// Offset of the user's shader in the source parsed by validation, after the uniform prelude.
+const OFFSET: usize = include_str!("shaders/uniforms.wgsl").len() + 1;
+
+/// Size of the header of `HaloProbes` in `probe.wgsl`, before `values`.
+const HEADER_SIZE: u64 = 16;
+
+const BUFFER_SIZE: u64 = HEADER_SIZE + MAX_PROBES as u64 * 16;
+
+/// A `halo_probe(expression)` call in the user's shader, which records the value of
+/// `expression` at the inspected pixel & passes it through unchanged.
+#[derive(Debug, Clone)]
+pub struct Probe {
+    /// The line of the call, starting at 1.
+    pub line: usize,
+    /// The probed expression, as written.
+    pub expression: String,
+    ty: Type,
+}
+
+/// The types a probed value can have: scalars & vectors of 32-bit numbers or bools.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Type {
+    kind: naga::ScalarKind,
+    components: u32,
+}
+
+/// The bits of the value each probe recorded at the inspected pixel, or `None` if its call
+/// wasn't reached.
+pub type Values = Vec<Option<[u32; 4]>>;
+
+pub type SharedValues = Arc<Mutex<Option<Values>>>;
+
+/// The value of a probe at the inspected pixel, formatted to be shown next to its line.
+#[derive(Debug, Clone)]
+pub struct Reading {
+    pub line: usize,
+    pub expression: String,
+    /// `None` if the probe wasn't reached at the inspected pixel.
+    pub value: Option<String>,
+}
+
+/// A shader with its probe calls replaced by calls which record their values.
+#[derive(Debug, Default)]
+pub struct Instrumented {
+    /// The probe prelude & the rewritten shader, or the shader unchanged if it has no probes.
+    pub source: String,
+    pub probes: Vec<Probe>,
+}
+
+/// A shader with its probe calls erased by [`erase`].
+#[derive(Debug, Clone, Default)]
+pub struct Erased {
+    pub source: String,
+    edits: Vec<Edit>,
+}
+
+/// A part of a shader which was rewritten, & what it was rewritten to.
+#[derive(Debug, Clone)]
+struct Edit {
+    shader: Range<usize>,
+    rewritten: Range<usize>,
+}
+
+impl Erased {
+    /// The offset in the shader of an `offset` in the erased source. Offsets within a rewritten
+    /// part of a call are clamped to what it was rewritten from.
+    pub fn to_shader(&self, offset: usize) -> usize {
+        let mut shifted = (0, 0);
+
+        for edit in &self.edits {
+            if offset < edit.rewritten.start {
+                break;
+            }
+
+            if offset < edit.rewritten.end {
+                return edit.shader.start + (offset - edit.rewritten.start).min(edit.shader.len());
+            }
+
+            shifted = (edit.shader.end, edit.rewritten.end);
+        }
+
+        offset - shifted.1 + shifted.0
+    }
+}
+
+/// The span of a `halo_probe(..)` call.
+#[derive(Debug, Clone)]
+struct Call {
+    range: Range<usize>,
+    argument: Range<usize>,
+    /// Whether the call is a statement of its own, e.g. `halo_probe(x);`.
+    is_statement: bool,
+}
+
+impl Call {
+    /// What `halo_probe(` is replaced with when erasing the call.
+    fn erased(&self) -> &'static str {
+        if self.is_statement {
+            ERASED_STATEMENT
+        } else {
+            ERASED
+        }
+    }
+}
+
+/// Replaces each `halo_probe(e)` with `array(e)[0]`, an expression of the same type, so the
+/// shader can be parsed & validated without the probe prelude. The type of each probed value is
+/// then found from the type of its array in the validated module.
+pub fn erase(shader: &str) -> Erased {
+    let (source, _, edits) = rewrite(
+        shader,
+        &calls(shader),
+        |call| call.erased().to_string(),
+        ")[0]",
+    );
+
+    Erased { source, edits }
+}
+
+/// Finds the probe calls of `shader` & rewrites them to record their values, using the module
+/// which was validated from the [`erase`]d shader.
+pub fn instrument(
+    shader: &str,
+    module: &naga::Module,
+    info: &naga::valid::ModuleInfo,
+) -> Result<Instrumented, String> {
+    let calls = calls(shader);
+
+    if calls.is_empty() {
+        return Ok(Instrumented {
+            source: shader.to_string(),
+            probes: vec![],
+        });
+    }
+
+    if calls.len() > MAX_PROBES {
+        return Err(format!(
+            "Shader has {} probes, but at most {MAX_PROBES} are supported",
+            calls.len()
+        ));
+    }
+
+    // nested calls are moved by the calls around them when erased
+    let (_, erased, _) = rewrite(shader, &calls, |call| call.erased().to_string(), ")[0]");
+
+    let probes = calls
+        .iter()
+        .zip(erased)
+        .map(|(call, erased)| {
+            let line = shader[..call.range.start].matches('\n').count() + 1;
+            let expression = shader[call.argument.clone()].trim().to_string();
+
+            let offset = OFFSET + erased + call.erased().find("array").unwrap_or_default();
+
+            let ty = probed_type(module, info, offset).ok_or_else(|| {
+                format!(
+                    "`{expression}` on line {line} can't be probed; only scalars & vectors \
+                    can be"
+                )
+            })?;
+
+            Ok(Probe {
+                line,
+                expression,
+                ty,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut source = include_str!("shaders/probe.wgsl").to_string();
+
+    for (index, probe) in probes.iter().enumerate() {
+        let ty = probe.ty.wgsl();
+
+        let _ = writeln!(
+            source,
+            "fn {NAME}_{index}(value: {ty}) -> {ty} {{ halo_record({index}u, {}); return value; }}",
+            probe.ty.bits("value"),
+        );
+    }
+
+    let (mut rewritten, ..) = rewrite(
+        shader,
+        &calls,
+        |call| {
+            let index = calls
+                .iter()
+                .position(|other| other.range == call.range)
+                .unwrap_or_default();
+
+            if call.is_statement {
+                format!("_ = {NAME}_{index}(")
+            } else {
+                format!("{NAME}_{index}(")
+            }
+        },
+        ")",
+    );
+
+    probe_fragment_position(&mut rewritten, module)?;
+
+    source.push_str(&rewritten);
+
+    Ok(Instrumented { source, probes })
+}
+
+/// Validates the instrumented shader, which adds a writable storage buffer to the fragment
+/// shader, against `capabilities`.
+pub fn validate(source: &str, capabilities: naga::valid::Capabilities) -> Result<(), String> {
+    let source = format!("{}\n{source}", include_str!("shaders/uniforms.wgsl"));
+
+    let module = naga::front::wgsl::parse_str(&source).map_err(|error| error.to_string())?;
+    let info = naga::valid::Validator::new(naga::valid::ValidationFlags::default(), capabilities)
+        .validate(&module)
+        .map_err(|error| format!("Probes can't be added to this shader: {error}"))?;
+
+    let Some((probes, _)) = module
+        .global_variables
+        .iter()
+        .find(|(_, global)| global.name.as_deref() == Some("halo_probes"))
+    else {
+        return Ok(());
+    };
+
+    for (i, entry_point) in module.entry_points.iter().enumerate() {
+        if entry_point.stage != naga::ShaderStage::Fragment
+            && !info.get_entry_point(i)[probes].is_empty()
+        {
+            return Err(format!(
+                "`{}` uses `{NAME}`, but probes can only be used by the fragment shader",
+                entry_point.name
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Formats the values recorded by each probe.
+pub fn read(probes: &[Probe], values: &Values) -> Vec<Reading> {
+    probes
+        .iter()
+        .zip(values)
+        .map(|(probe, bits)| Reading {
+            line: probe.line,
+            expression: probe.expression.clone(),
+            value: bits.map(|bits| probe.ty.format(bits)),
+        })
+        .collect()
+}
+
+impl Type {
+    fn wgsl(&self) -> String {
+        let scalar = match self.kind {
+            naga::ScalarKind::Float => "f32",
+            naga::ScalarKind::Sint => "i32",
+            naga::ScalarKind::Uint => "u32",
+            naga::ScalarKind::Bool => "bool",
+        };
+
+        match self.components {
+            1 => scalar.to_string(),
+            n => format!("vec{n}<{scalar}>"),
+        }
+    }
+
+    /// The WGSL expression which converts `value` to the `vec4<u32>` it's recorded as.
+    fn bits(&self, value: &str) -> String {
+        let uint = match self.components {
+            1 => "u32".to_string(),
+            n => format!("vec{n}<u32>"),
+        };
+
+        let bits = match self.kind {
+            naga::ScalarKind::Float | naga::ScalarKind::Sint => format!("bitcast<{uint}>({value})"),
+            naga::ScalarKind::Uint => value.to_string(),
+            naga::ScalarKind::Bool => format!("{uint}({value})"),
+        };
+
+        let padding = ", 0u".repeat(4 - self.components as usize);
+
+        format!("vec4<u32>({bits}{padding})")
+    }
+
+    fn format(&self, bits: [u32; 4]) -> String {
+        let components = bits[..self.components as usize]
+            .iter()
+            .map(|&bits| match self.kind {
+                naga::ScalarKind::Float => format!("{:.4}", f32::from_bits(bits)),
+                naga::ScalarKind::Sint => (bits as i32).to_string(),
+                naga::ScalarKind::Uint => bits.to_string(),
+                naga::ScalarKind::Bool => (bits != 0).to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        match components.as_slice() {
+            [scalar] => scalar.clone(),
+            vector => format!("({})", vector.join(", ")),
+        }
+    }
+}
+
+/// The type of the probe whose erased `array(..)` starts at `offset` in the validated source.
+fn probed_type(
+    module: &naga::Module,
+    info: &naga::valid::ModuleInfo,
+    offset: usize,
+) -> Option<Type> {
+    let functions = module
+        .functions
+        .iter()
+        .map(|(handle, function)| (function, &info[handle]))
+        .chain(
+            module
+                .entry_points
+                .iter()
+                .enumerate()
+                .map(|(i, entry_point)| (&entry_point.function, info.get_entry_point(i))),
+        );
+
+    for (function, function_info) in functions {
+        for (handle, expression) in function.expressions.iter() {
+            if !matches!(expression, naga::Expression::Compose { .. })
+                || function
+                    .expressions
+                    .get_span(handle)
+                    .to_range()
+                    .map(|r| r.start)
+                    != Some(offset)
+            {
+                continue;
+            }
+
+            let naga::TypeInner::Array { base, .. } =
+                function_info[handle].ty.inner_with(&module.types)
+            else {
+                return None;
+            };
+
+            return match module.types[*base].inner {
+                naga::TypeInner::Scalar { kind, width }
+                    if width == 4 || kind == naga::ScalarKind::Bool =>
+                {
+                    Some(Type {
+                        kind,
+                        components: 1,
+                    })
+                }
+                naga::TypeInner::Vector { size, kind, width }
+                    if width == 4 || kind == naga::ScalarKind::Bool =>
+                {
+                    Some(Type {
+                        kind,
+                        components: size as u32,
+                    })
+                }
+                _ => None,
+            };
+        }
+    }
+
+    None
+}
+
+/// Sets `halo_probing` at the start of `fs_main`, adding a `@builtin(position)` argument to
+/// compare with the probed pixel if it doesn't have one.
+fn probe_fragment_position(shader: &mut String, module: &naga::Module) -> Result<(), String> {
+    let no_entry_point = || "Shader must have a `@fragment fn fs_main`".to_string();
+
+    let entry_point = module
+        .entry_points
+        .iter()
+        .find(|ep| ep.stage == naga::ShaderStage::Fragment && ep.name == "fs_main")
+        .ok_or_else(no_entry_point)?;
+
+    let is_position = |binding: &Option<naga::Binding>| {
+        matches!(
+            binding,
+            Some(naga::Binding::BuiltIn(naga::BuiltIn::Position { .. }))
+        )
+    };
+
+    let position = entry_point.function.arguments.iter().find_map(|argument| {
+        let name = argument.name.as_deref()?;
+
+        if is_position(&argument.binding) {
+            return Some(name.to_string());
+        }
+
+        match &module.types[argument.ty].inner {
+            naga::TypeInner::Struct { members, .. } => members
+                .iter()
+                .find(|member| is_position(&member.binding))
+                .and_then(|member| Some(format!("{name}.{}", member.name.as_deref()?))),
+            _ => None,
+        }
+    });
+
+    let code = strip_comments(shader);
+    let signature = find_function(&code, "fs_main").ok_or_else(no_entry_point)?;
+    let arguments_end = matching_parenthesis(&code, signature).ok_or_else(no_entry_point)?;
+    let body = arguments_end + code[arguments_end..].find('{').ok_or_else(no_entry_point)?;
+
+    let position = position.unwrap_or_else(|| "halo_position".to_string());
+
+    shader.insert_str(
+        body + 1,
+        &format!("\n    halo_probing = all(vec2<u32>({position}.xy) == halo_probes.pixel);"),
+    );
+
+    if position == "halo_position" {
+        shader.insert_str(
+            signature + 1,
+            "@builtin(position) halo_position: vec4<f32>, ",
+        );
+    }
+
+    Ok(())
+}
+
+/// Finds the `halo_probe(..)` calls outside of comments, in the order they're written.
+fn calls(shader: &str) -> Vec<Call> {
+    let code = strip_comments(shader);
+    let mut calls = vec![];
+
+    for (start, _) in code.match_indices(NAME) {
+        let end = start + NAME.len();
+
+        if code[..start].ends_with(is_identifier) || !code[end..].starts_with('(') {
+            continue;
+        }
+
+        if let Some(close) = matching_parenthesis(&code, end) {
+            let before = code[..start].trim_end();
+            let after = code[close + 1..].trim_start();
+
+            calls.push(Call {
+                range: start..close + 1,
+                argument: end + 1..close,
+                is_statement: (before.is_empty() || before.ends_with(['{', '}', ';']))
+                    && after.starts_with(';'),
+            });
+        }
+    }
+
+    calls
+}
+
+/// Rewrites each call's `halo_probe(` with `open(call)`, & its closing parenthesis with `close`.
+///
+/// Returns the rewritten shader, the offset each call was rewritten to & the edits in order.
+fn rewrite(
+    shader: &str,
+    calls: &[Call],
+    open: impl Fn(&Call) -> String,
+    close: &str,
+) -> (String, Vec<usize>, Vec<Edit>) {
+    let mut edits = calls
+        .iter()
+        .enumerate()
+        .flat_map(|(i, call)| {
+            [
+                (call.range.start..call.argument.start, open(call), Some(i)),
+                (call.argument.end..call.range.end, close.to_string(), None),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    edits.sort_by_key(|(range, ..)| range.start);
+
+    let mut rewritten = String::with_capacity(shader.len());
+    let mut offsets = vec![0; calls.len()];
+    let mut done = Vec::with_capacity(edits.len());
+    let mut last = 0;
+
+    for (range, replacement, call) in edits {
+        rewritten.push_str(&shader[last..range.start]);
+
+        if let Some(i) = call {
+            offsets[i] = rewritten.len();
+        }
+
+        let start = rewritten.len();
+        rewritten.push_str(&replacement);
+        last = range.end;
+
+        done.push(Edit {
+            shader: range,
+            rewritten: start..rewritten.len(),
+        });
+    }
+
+    rewritten.push_str(&shader[last..]);
+    (rewritten, offsets, done)
+}
+
+/// The offset of the opening parenthesis of `fn name(`.
+fn find_function(code: &str, name: &str) -> Option<usize> {
+    code.match_indices("fn").find_map(|(start, _)| {
+        if code[..start].ends_with(is_identifier) {
+            return None;
+        }
+
+        let rest = code[start + 2..]
+            .strip_prefix(char::is_whitespace)?
+            .trim_start();
+        let rest = rest.strip_prefix(name)?.trim_start();
+
+        rest.starts_with('(').then(|| code.len() - rest.len())
+    })
+}
+
+/// The offset of the parenthesis closing the one at `open`.
+fn matching_parenthesis(code: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, char) in code[open..].char_indices() {
+        match char {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Replaces comments with spaces, keeping every offset the same.
+fn strip_comments(shader: &str) -> String {
+    let mut bytes = shader.as_bytes().to_vec();
+    let mut i = 0;
+    let mut depth = 0;
+
+    while i < bytes.len() {
+        let next = bytes.get(i + 1).copied();
+
+        if depth > 0 {
+            match (bytes[i], next) {
+                (b'*', Some(b'/')) => {
+                    depth -= 1;
+                    bytes[i..i + 2].fill(b' ');
+                    i += 2;
+                }
+                (b'/', Some(b'*')) => {
+                    depth += 1;
+                    bytes[i..i + 2].fill(b' ');
+                    i += 2;
+                }
+                (b'\n', _) => i += 1,
+                _ => {
+                    bytes[i] = b' ';
+                    i += 1;
+                }
+            }
+        } else {
+            match (bytes[i], next) {
+                (b'/', Some(b'/')) => {
+                    while i < bytes.len() && bytes[i] != b'\n' {
+                        bytes[i] = b' ';
+                        i += 1;
+                    }
+                }
+                (b'/', Some(b'*')) => {
+                    depth += 1;
+                    bytes[i..i + 2].fill(b' ');
+                    i += 2;
+                }
+                _ => i += 1,
+            }
+        }
+    }
+
+    // only whole comments were replaced, which start & end on ASCII characters
+    String::from_utf8(bytes).unwrap_or_else(|_| shader.to_string())
+}
+
+fn is_identifier(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
+
+/// The storage buffer probes are recorded to, & read back from while inspecting.
+pub struct Buffer {
+    buffer: wgpu::Buffer,
+    readback: Readback<usize>,
+    count: usize,
+}
+
+impl Buffer {
+    pub fn new(device: &wgpu::Device, count: usize) -> Self {
+        Self {
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("halo.probe.buffer"),
+                size: BUFFER_SIZE,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback: Readback::new(device, "halo.probe.readback", BUFFER_SIZE),
+            count,
+        }
+    }
+
+    pub fn layout_entry() -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding: BINDING,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }
+    }
+
+    pub fn bind_group_entry(&self) -> wgpu::BindGroupEntry<'_> {
+        wgpu::BindGroupEntry {
+            binding: BINDING,
+            resource: self.buffer.as_entire_binding(),
+        }
+    }
+
+    /// Sets the pixel to record probes at this frame, or none, & clears the last frame's hits.
+    pub fn prepare(&self, queue: &wgpu::Queue, pixel: Option<[u32; 2]>) {
+        let [x, y] = pixel.unwrap_or([u32::MAX; 2]);
+
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[x, y, 0, 0]));
+    }
+
+    /// Copies the values recorded this frame to be read back, unless the last ones still are.
+    pub fn copy(&self, encoder: &mut wgpu::CommandEncoder) {
+        if self.readback.is_idle() {
+            encoder.copy_buffer_to_buffer(&self.buffer, 0, self.readback.buffer(), 0, BUFFER_SIZE);
+            self.readback.copied(self.count);
+        }
+    }
+
+    /// Returns the values of an earlier frame, once they've been read back.
+    pub fn poll(&self) -> Option<Values> {
+        self.readback.poll(|data, count| {
+            let words: &[u32] = bytemuck::cast_slice(data);
+            let hits = words[2];
+
+            words[HEADER_SIZE as usize / 4..]
+                .chunks_exact(4)
+                .take(count)
+                .enumerate()
+                .map(|(i, value)| {
+                    (hits & (1 << i) != 0).then(|| [value[0], value[1], value[2], value[3]])
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHADER: &str = "\
+fn colour(uv: vec2<f32>) -> vec3<f32> {
+    return vec3(halo_probe(uv.x) * 2.0, uv.y, 0.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) clip_pos: vec4<f32>) -> @location(0) vec4<f32> {
+    halo_probe(clip_pos.xy);
+    return vec4<f32>(colour(clip_pos.xy), 1.0);
+}
+";
+
+    fn instrumented(shader: &str) -> Instrumented {
+        let (module, _) = crate::validation::parse(shader).unwrap();
+        let info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::default(),
+            naga::valid::Capabilities::default(),
+        )
+        .validate(&module)
+        .unwrap();
+
+        let instrumented = instrument(shader, &module, &info).unwrap();
+        validate(&instrumented.source, naga::valid::Capabilities::default()).unwrap();
+
+        instrumented
+    }
+
+    #[test]
+    fn erases_expression_probes() {
+        let erased = erase("let x = halo_probe(uv.x) * 2.0;");
+
+        assert_eq!(erased.source, "let x = array(uv.x)[0] * 2.0;");
+    }
+
+    #[test]
+    fn erases_statement_probes_to_assignments() {
+        let erased = erase("{ halo_probe(uv); }");
+
+        assert_eq!(erased.source, "{ _=array(uv)[0]; }");
+    }
+
+    #[test]
+    fn erases_nested_probes() {
+        let erased = erase("halo_probe(halo_probe(x) + 1.0)");
+
+        assert_eq!(erased.source, "array(array(x)[0] + 1.0)[0]");
+    }
+
+    #[test]
+    fn ignores_probes_in_comments_and_other_names() {
+        let shader = "// halo_probe(x)\nmy_halo_probe(x); /* halo_probe(y) */";
+
+        assert_eq!(erase(shader).source, shader);
+    }
+
+    #[test]
+    fn maps_erased_offsets_back_to_the_shader() {
+        let shader = "let x = halo_probe(uv.x) * 2.0;";
+        let erased = erase(shader);
+
+        let argument = erased.source.find("uv.x").unwrap();
+        let after = erased.source.find('*').unwrap();
+        let end = erased.source.len();
+
+        assert_eq!(erased.to_shader(argument), shader.find("uv.x").unwrap());
+        assert_eq!(erased.to_shader(after), shader.find('*').unwrap());
+        assert_eq!(erased.to_shader(end), shader.len());
+        // within `[0]`, clamped to the closing parenthesis it replaced
+        assert_eq!(erased.to_shader(after - 2), shader.find(')').unwrap() + 1);
+    }
+
+    #[test]
+    fn instruments_statement_and_expression_probes() {
+        let instrumented = instrumented(SHADER);
+
+        let probes = instrumented
+            .probes
+            .iter()
+            .map(|probe| (probe.line, probe.expression.as_str(), probe.ty.wgsl()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            probes,
+            [
+                (2, "uv.x", "f32".to_string()),
+                (7, "clip_pos.xy", "vec2<f32>".to_string())
+            ]
+        );
+        assert!(instrumented
+            .source
+            .contains("vec3(halo_probe_0(uv.x) * 2.0, uv.y, 0.0)"));
+        assert!(instrumented
+            .source
+            .contains("_ = halo_probe_1(clip_pos.xy);"));
+    }
+
+    #[test]
+    fn leaves_shaders_without_probes_unchanged() {
+        let shader = SHADER
+            .replace("halo_probe(uv.x)", "uv.x")
+            .replace("    halo_probe(clip_pos.xy);\n", "");
+
+        let instrumented = instrumented(&shader);
+
+        assert!(instrumented.probes.is_empty());
+        assert_eq!(instrumented.source, shader);
+    }
+
+    #[test]
+    fn rejects_probes_of_other_types() {
+        let shader = SHADER.replace("halo_probe(clip_pos.xy)", "halo_probe(mat2x2<f32>())");
+        let (module, _) = crate::validation::parse(&shader).unwrap();
+        let info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::default(),
+            naga::valid::Capabilities::default(),
+        )
+        .validate(&module)
+        .unwrap();
+
+        let error = instrument(&shader, &module, &info).unwrap_err();
+
+        assert!(error.contains("line 7"), "{error}");
+    }
+
+    #[test]
+    fn probes_the_position_argument() {
+        let source = instrumented(SHADER).source;
+
+        assert!(source.contains("halo_probing = all(vec2<u32>(clip_pos.xy) == halo_probes.pixel);"));
+        assert!(!source.contains("halo_position"));
+    }
+
+    #[test]
+    fn adds_a_position_to_fs_main_without_arguments() {
+        let shader = "\
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(halo_probe(0.5), 0.0, 0.0, 1.0);
+}
+";
+        let source = instrumented(shader).source;
+
+        assert!(source.contains(
+            "fn fs_main(@builtin(position) halo_position: vec4<f32>, ) -> @location(0) vec4<f32> {\
+            \n    halo_probing = all(vec2<u32>(halo_position.xy) == halo_probes.pixel);"
+        ));
+    }
+
+    #[test]
+    fn probes_the_position_member_of_struct_inputs() {
+        let shader = "\
+struct Input {
+    @location(0) uv: vec2<f32>,
+    @builtin(position) pixel: vec4<f32>,
+}
+
+@fragment
+fn fs_main(input: Input) -> @location(0) vec4<f32> {
+    return vec4<f32>(halo_probe(input.uv), 0.0, 1.0);
+}
+";
+        let source = instrumented(shader).source;
+
+        assert!(
+            source.contains("halo_probing = all(vec2<u32>(input.pixel.xy) == halo_probes.pixel);")
+        );
+        assert!(!source.contains("halo_position"));
+    }
+
+    #[test]
+    fn adds_a_position_beside_struct_inputs_without_one() {
+        let shader = "\
+struct Input {
+    @location(0) uv: vec2<f32>,
+}
+
+@fragment
+fn fs_main(input: Input) -> @location(0) vec4<f32> {
+    return vec4<f32>(halo_probe(input.uv), 0.0, 1.0);
+}
+";
+        let source = instrumented(shader).source;
+
+        assert!(source
+            .contains("fn fs_main(@builtin(position) halo_position: vec4<f32>, input: Input)"));
+    }
+}
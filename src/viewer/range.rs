@@ -0,0 +1,231 @@
+use crate::viewer::offscreen::{Blit, Offscreen};
+use crate::viewer::readback::Readback;
+use iced::widget::shader::wgpu;
+use iced::{Color, Size};
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+
+const WORKGROUP_SIZE: u32 = 8;
+
+const COUNTS_SIZE: u64 = 4 * std::mem::size_of::<u32>() as u64;
+
+/// How many pixels of a frame had a NaN, infinite, negative or >1.0 component.
+///
+/// Each pixel is only counted once, in the first of those it falls into.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Counts {
+    pub nan: u32,
+    pub inf: u32,
+    pub negative: u32,
+    pub over: u32,
+    /// The number of pixels in the frame.
+    pub total: u32,
+}
+
+pub type SharedCounts = Arc<Mutex<Option<Counts>>>;
+
+impl Counts {
+    /// The percentage of the frame's pixels `count` makes up.
+    pub fn percent(&self, count: u32) -> f32 {
+        count as f32 / self.total.max(1) as f32 * 100.0
+    }
+
+    /// Each count with its label & the false color it's flagged with in `range_check.wgsl`.
+    pub fn legend(&self) -> [(&'static str, u32, Color); 4] {
+        [
+            ("NaN", self.nan, Color::from_linear_rgba(1.0, 0.0, 1.0, 1.0)),
+            ("Inf", self.inf, Color::from_linear_rgba(0.0, 1.0, 1.0, 1.0)),
+            (
+                "< 0",
+                self.negative,
+                Color::from_linear_rgba(0.0, 0.2, 1.0, 1.0),
+            ),
+            (
+                "> 1",
+                self.over,
+                Color::from_linear_rgba(1.0, 0.1, 0.0, 1.0),
+            ),
+        ]
+    }
+}
+
+/// A post pass over the offscreen frame which replaces its pixels with false colors flagging
+/// NaN, infinite, negative & >1.0 values, & counts them.
+pub struct RangeCheck {
+    pipeline: wgpu::ComputePipeline,
+    layout: wgpu::BindGroupLayout,
+    counts: wgpu::Buffer,
+    readback: Readback<Size<u32>>,
+}
+
+impl RangeCheck {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("halo.range_check.bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba8Unorm,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("halo.range_check.shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
+                "shaders/range_check.wgsl"
+            ))),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("halo.range_check.layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("halo.range_check.pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "cs_main",
+        });
+
+        let counts = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("halo.range_check.counts"),
+            size: COUNTS_SIZE,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            layout,
+            counts,
+            readback: Readback::new(device, "halo.range_check.readback", COUNTS_SIZE),
+        }
+    }
+
+    /// Creates the texture the false colors of `offscreen` are written to.
+    pub fn target(&self, device: &wgpu::Device, offscreen: &Offscreen, blit: &Blit) -> Target {
+        let size = offscreen.size();
+
+        let output = device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("halo.range_check.output"),
+                size: wgpu::Extent3d {
+                    width: size.width,
+                    height: size.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("halo.range_check.bind_group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&offscreen.frame_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&output),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.counts.as_entire_binding(),
+                },
+            ],
+        });
+
+        Target {
+            size,
+            bind_group,
+            blit: blit.bind_group(device, &output),
+        }
+    }
+
+    /// Flags the pixels of the frame `target` was created for, & copies their counts to be read
+    /// back unless the last counts still are.
+    pub fn check(&self, encoder: &mut wgpu::CommandEncoder, target: &Target) {
+        encoder.clear_buffer(&self.counts, 0, None);
+
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("halo.range_check.compute_pass"),
+                timestamp_writes: None,
+            });
+
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &target.bind_group, &[]);
+            pass.dispatch_workgroups(
+                target.size.width.div_ceil(WORKGROUP_SIZE),
+                target.size.height.div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+        }
+
+        if self.readback.is_idle() {
+            encoder.copy_buffer_to_buffer(&self.counts, 0, self.readback.buffer(), 0, COUNTS_SIZE);
+            self.readback.copied(target.size);
+        }
+    }
+
+    /// Returns the counts of an earlier frame, once they've been read back.
+    pub fn poll(&self) -> Option<Counts> {
+        self.readback.poll(|data, size| {
+            let counts: &[u32] = bytemuck::cast_slice(data);
+
+            Counts {
+                nan: counts[0],
+                inf: counts[1],
+                negative: counts[2],
+                over: counts[3],
+                total: size.width * size.height,
+            }
+        })
+    }
+}
+
+/// The false colors of an [`Offscreen`] frame; recreated along with it.
+pub struct Target {
+    size: Size<u32>,
+    bind_group: wgpu::BindGroup,
+    /// Binds the false colors for the [`Blit`].
+    pub blit: wgpu::BindGroup,
+}
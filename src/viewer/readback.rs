@@ -0,0 +1,151 @@
+use iced::widget::shader::wgpu;
+use std::cell::Cell;
+use std::sync::{Arc, Mutex};
+
+/// A buffer which is copied to while rendering a frame, & read on the CPU a few frames later.
+///
+/// The buffer can only be mapped once the copy has been submitted, so it's mapped on the next
+/// [`Readback::poll`], & read on whichever poll comes after the mapping has completed. Only one
+/// copy is in flight at a time; [`Readback::is_idle`] should be checked before copying.
+pub struct Readback<T> {
+    buffer: wgpu::Buffer,
+    state: Cell<State<T>>,
+    /// The result of mapping the buffer, once it's completed.
+    mapped: Arc<Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State<T> {
+    Idle,
+    /// The buffer was copied to, with what's needed to interpret its contents.
+    Copied(T),
+    Mapping(T),
+}
+
+impl<T: Copy> Readback<T> {
+    pub fn new(device: &wgpu::Device, label: &'static str, size: u64) -> Self {
+        Self {
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            state: Cell::new(State::Idle),
+            mapped: Arc::default(),
+        }
+    }
+
+    /// Whether the buffer can be copied to.
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state.get(), State::Idle)
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    /// Marks the buffer as copied to this frame.
+    pub fn copied(&self, context: T) {
+        self.state.set(State::Copied(context));
+    }
+
+    /// Maps the buffer if it was copied to last frame, & reads it with `read` once it's mapped.
+    ///
+    /// If the mapping fails, the copy is dropped so the buffer can be copied to again.
+    pub fn poll<R>(&self, read: impl FnOnce(&[u8], T) -> R) -> Option<R> {
+        match self.state.get() {
+            State::Idle => None,
+            State::Copied(context) => {
+                let mapped = self.mapped.clone();
+
+                self.buffer
+                    .slice(..)
+                    .map_async(wgpu::MapMode::Read, move |result| {
+                        if let Ok(mut mapped) = mapped.lock() {
+                            *mapped = Some(result);
+                        }
+                    });
+
+                self.state.set(State::Mapping(context));
+                None
+            }
+            State::Mapping(context) => {
+                let result = self.mapped.lock().ok()?.take()?;
+
+                if result.is_err() {
+                    self.state.set(State::Idle);
+                    return None;
+                }
+
+                let result = read(&self.buffer.slice(..).get_mapped_range(), context);
+
+                self.buffer.unmap();
+                self.state.set(State::Idle);
+
+                Some(result)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::viewer::headless;
+    use iced::futures::executor::block_on;
+
+    const SIZE: u64 = 16;
+
+    fn readback() -> (wgpu::Device, wgpu::Queue, Readback<()>) {
+        let (device, queue) =
+            block_on(headless::device()).unwrap_or_else(|error| panic!("{error}"));
+        let readback = Readback::new(&device, "halo.readback.test", SIZE);
+
+        (device, queue, readback)
+    }
+
+    /// Polls until the mapping has completed, returning what was read.
+    fn read(device: &wgpu::Device, readback: &Readback<()>) -> Option<Vec<u8>> {
+        let _ = readback.poll(|data, ()| data.to_vec());
+        device.poll(wgpu::Maintain::Wait);
+
+        readback.poll(|data, ()| data.to_vec())
+    }
+
+    #[test]
+    fn reads_what_was_copied() {
+        let (device, queue, readback) = readback();
+        let source = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("halo.readback.test.source"),
+            size: SIZE,
+            usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        queue.write_buffer(&source, 0, &[7; SIZE as usize]);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(&source, 0, readback.buffer(), 0, SIZE);
+        queue.submit([encoder.finish()]);
+        readback.copied(());
+
+        assert_eq!(read(&device, &readback), Some(vec![7; SIZE as usize]));
+        assert!(readback.is_idle());
+    }
+
+    #[test]
+    fn failed_mappings_return_to_idle() {
+        let (device, _queue, readback) = readback();
+
+        readback.copied(());
+        let _ = readback.poll(|_, ()| ());
+
+        // destroying the buffer aborts the mapping in flight
+        readback.buffer().destroy();
+        device.poll(wgpu::Maintain::Wait);
+
+        assert_eq!(readback.poll(|_, ()| ()), None);
+        assert!(readback.is_idle());
+    }
+}
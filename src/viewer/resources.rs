@@ -0,0 +1,144 @@
+use crate::viewer::shader::{Binding, Reflection, Resource};
+use iced::widget::shader::wgpu;
+use iced::Size;
+use std::collections::BTreeMap;
+
+/// The GPU side of the resources declared in the user's bind group.
+///
+/// Buffers are zeroed when created & persist across frames, so compute passes can accumulate
+/// state. Textures are the size of the rendered frame & are recreated when it's resized.
+pub struct Resources {
+    size: Size<u32>,
+    resources: BTreeMap<u32, Gpu>,
+}
+
+enum Gpu {
+    Buffer(wgpu::Buffer),
+    Texture(wgpu::TextureView),
+    Sampler(wgpu::Sampler),
+}
+
+impl Resources {
+    pub fn new(device: &wgpu::Device, reflection: &Reflection, size: Size<u32>) -> Self {
+        let resources = reflection
+            .resources
+            .iter()
+            .map(|(binding, resource)| {
+                let gpu = match resource {
+                    Resource::Buffer { size } => {
+                        Gpu::Buffer(device.create_buffer(&wgpu::BufferDescriptor {
+                            label: Some("halo.resources.buffer"),
+                            size: *size,
+                            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                            mapped_at_creation: false,
+                        }))
+                    }
+                    Resource::Texture { format } => texture(device, *format, size),
+                    Resource::Sampler => {
+                        Gpu::Sampler(device.create_sampler(&wgpu::SamplerDescriptor {
+                            label: Some("halo.resources.sampler"),
+                            address_mode_u: wgpu::AddressMode::Repeat,
+                            address_mode_v: wgpu::AddressMode::Repeat,
+                            mag_filter: wgpu::FilterMode::Linear,
+                            min_filter: wgpu::FilterMode::Linear,
+                            ..Default::default()
+                        }))
+                    }
+                };
+
+                (*binding, gpu)
+            })
+            .collect();
+
+        Self { size, resources }
+    }
+
+    /// Recreates the textures for a frame of `size`, keeping the buffers & what's been written
+    /// to them.
+    pub fn resize(&mut self, device: &wgpu::Device, reflection: &Reflection, size: Size<u32>) {
+        for (binding, resource) in &reflection.resources {
+            if let Resource::Texture { format } = resource {
+                self.resources
+                    .insert(*binding, texture(device, *format, size));
+            }
+        }
+
+        self.size = size;
+    }
+
+    /// The size of the viewer the textures were created for.
+    pub fn size(&self) -> Size<u32> {
+        self.size
+    }
+
+    pub fn bind_group(
+        &self,
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        bindings: &[Binding],
+    ) -> wgpu::BindGroup {
+        let entries = bindings
+            .iter()
+            .filter_map(|binding| {
+                let resource = match self.resources.get(&binding.binding)? {
+                    Gpu::Buffer(buffer) => buffer.as_entire_binding(),
+                    Gpu::Texture(view) => wgpu::BindingResource::TextureView(view),
+                    Gpu::Sampler(sampler) => wgpu::BindingResource::Sampler(sampler),
+                };
+
+                Some(wgpu::BindGroupEntry {
+                    binding: binding.binding,
+                    resource,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("halo.resources.bind_group"),
+            layout,
+            entries: &entries,
+        })
+    }
+}
+
+fn texture(device: &wgpu::Device, format: wgpu::TextureFormat, size: Size<u32>) -> Gpu {
+    Gpu::Texture(
+        device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("halo.resources.texture"),
+                size: wgpu::Extent3d {
+                    width: size.width.max(1),
+                    height: size.height.max(1),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default()),
+    )
+}
+
+pub fn bind_group_layout(
+    device: &wgpu::Device,
+    bindings: &[Binding],
+    visibility: wgpu::ShaderStages,
+) -> wgpu::BindGroupLayout {
+    let entries = bindings
+        .iter()
+        .map(|binding| wgpu::BindGroupLayoutEntry {
+            binding: binding.binding,
+            visibility,
+            ty: binding.ty,
+            count: None,
+        })
+        .collect::<Vec<_>>();
+
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("halo.resources.bind_group_layout"),
+        entries: &entries,
+    })
+}
@@ -0,0 +1,326 @@
+use crate::viewer::directive::{self, Directive};
+use iced::widget::shader::wgpu;
+use std::fmt::{Display, Formatter};
+
+/// How a shader is rendered, persisted in the shader itself with a `//#render` directive:
+///
+/// ```wgsl
+/// //#render scale=0.5 msaa=4 blend=additive clear=#000000ff
+/// ```
+///
+/// Options which are left out use their defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Settings {
+    pub scale: Scale,
+    pub msaa: Msaa,
+    pub blend: Blend,
+    pub clear: Clear,
+}
+
+impl Settings {
+    /// The settings of the last `//#render` directive in `source`, or the defaults if it has none.
+    pub fn from_source(source: &str) -> Self {
+        directive::parse(source)
+            .into_iter()
+            .filter_map(|(_, directive)| match directive {
+                Ok(Directive::Render(settings)) => Some(settings),
+                _ => None,
+            })
+            .last()
+            .unwrap_or_default()
+    }
+
+    pub fn parse(args: &[&str]) -> Result<Self, String> {
+        let mut settings = Self::default();
+
+        for arg in args {
+            let (key, value) = arg
+                .split_once('=')
+                .ok_or_else(|| format!("Expected `<option>=<value>`, found `{arg}`"))?;
+
+            match key {
+                "scale" => settings.scale = Scale::parse(value)?,
+                "msaa" => settings.msaa = Msaa::parse(value)?,
+                "blend" => settings.blend = Blend::parse(value)?,
+                "clear" => settings.clear = Clear::parse(value)?,
+                key => return Err(format!("Unknown render option `{key}`")),
+            }
+        }
+
+        Ok(settings)
+    }
+
+    /// Writes these settings into `source`, replacing its `//#render` directive if it has one or
+    /// adding one to the top if not. Default settings remove the directive entirely.
+    pub fn write(&self, source: &str) -> String {
+        let existing = directive::parse(source)
+            .into_iter()
+            .find(|(_, directive)| matches!(directive, Ok(Directive::Render(_))))
+            .map(|(range, _)| range);
+
+        match (existing, self.directive()) {
+            (Some(range), Some(directive)) => {
+                format!(
+                    "{}{directive}{}",
+                    &source[..range.start],
+                    &source[range.end..]
+                )
+            }
+            (Some(range), None) => {
+                // remove the line break following the directive too
+                let end = source[range.end..]
+                    .find('\n')
+                    .map_or(source.len(), |i| range.end + i + 1);
+
+                format!("{}{}", &source[..range.start], &source[end..])
+            }
+            (None, Some(directive)) => format!("{directive}\n{source}"),
+            (None, None) => source.to_string(),
+        }
+    }
+
+    /// The `//#render` directive for these settings, if they aren't the defaults.
+    fn directive(&self) -> Option<String> {
+        let defaults = Self::default();
+        let mut options = vec![];
+
+        if self.scale != defaults.scale {
+            options.push(format!("scale={}", self.scale.factor()));
+        }
+        if self.msaa != defaults.msaa {
+            options.push(format!("msaa={}", self.msaa.sample_count()));
+        }
+        if self.blend != defaults.blend {
+            options.push(format!("blend={}", self.blend.name()));
+        }
+        if self.clear != defaults.clear {
+            options.push(format!("clear={}", self.clear.name()));
+        }
+
+        (!options.is_empty()).then(|| format!("//#render {}", options.join(" ")))
+    }
+}
+
+/// The resolution the shader is rendered at, relative to the size of the viewer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Scale {
+    Quarter,
+    Half,
+    #[default]
+    Full,
+    Double,
+}
+
+impl Scale {
+    pub const ALL: [Scale; 4] = [Scale::Quarter, Scale::Half, Scale::Full, Scale::Double];
+
+    pub fn factor(&self) -> f32 {
+        match self {
+            Scale::Quarter => 0.25,
+            Scale::Half => 0.5,
+            Scale::Full => 1.0,
+            Scale::Double => 2.0,
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|scale| value.parse() == Ok(scale.factor()))
+            .ok_or_else(|| format!("Scale must be one of 0.25, 0.5, 1 or 2, found `{value}`"))
+    }
+}
+
+impl Display for Scale {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}% res", self.factor() * 100.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Msaa {
+    #[default]
+    Off,
+    /// 4x is the only sample count wgpu guarantees to be supported besides 1.
+    X4,
+}
+
+impl Msaa {
+    pub const ALL: [Msaa; 2] = [Msaa::Off, Msaa::X4];
+
+    pub fn sample_count(&self) -> u32 {
+        match self {
+            Msaa::Off => 1,
+            Msaa::X4 => 4,
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "1" => Ok(Msaa::Off),
+            "4" => Ok(Msaa::X4),
+            _ => Err(format!("MSAA must be either 1 or 4, found `{value}`")),
+        }
+    }
+}
+
+impl Display for Msaa {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Msaa::Off => write!(f, "No MSAA"),
+            Msaa::X4 => write!(f, "4x MSAA"),
+        }
+    }
+}
+
+/// How the output of the fragment shader is blended with the render target.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Blend {
+    Replace,
+    #[default]
+    Alpha,
+    Premultiplied,
+    Additive,
+}
+
+impl Blend {
+    pub const ALL: [Blend; 4] = [
+        Blend::Replace,
+        Blend::Alpha,
+        Blend::Premultiplied,
+        Blend::Additive,
+    ];
+
+    pub fn state(&self) -> wgpu::BlendState {
+        match self {
+            Blend::Replace => wgpu::BlendState::REPLACE,
+            Blend::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            Blend::Premultiplied => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+            Blend::Additive => {
+                let additive = wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                };
+
+                wgpu::BlendState {
+                    color: additive,
+                    alpha: additive,
+                }
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Blend::Replace => "replace",
+            Blend::Alpha => "alpha",
+            Blend::Premultiplied => "premultiplied",
+            Blend::Additive => "additive",
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|blend| blend.name() == value)
+            .ok_or_else(|| {
+                format!("Blend must be one of replace, alpha, premultiplied or additive, found `{value}`")
+            })
+    }
+}
+
+impl Display for Blend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Blend::Replace => "No blending",
+            Blend::Alpha => "Alpha blending",
+            Blend::Premultiplied => "Premultiplied alpha",
+            Blend::Additive => "Additive blending",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// What the render target is cleared to before the shader is drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Clear {
+    /// Cleared to transparent, so the viewer's background shows through.
+    #[default]
+    None,
+    /// An sRGB color, as RGBA.
+    Color([u8; 4]),
+}
+
+impl Clear {
+    pub const ALL: [Clear; 3] = [
+        Clear::None,
+        Clear::Color([0, 0, 0, 255]),
+        Clear::Color([255, 255, 255, 255]),
+    ];
+
+    pub fn to_wgpu(self) -> wgpu::Color {
+        match self {
+            Clear::None => wgpu::Color::TRANSPARENT,
+            Clear::Color(rgba) => {
+                let [r, g, b, a] = rgba.map(|c| f64::from(c) / 255.0);
+
+                // the frame is linear, but clear colors are given in sRGB
+                let linear = |c: f64| {
+                    if c <= 0.04045 {
+                        c / 12.92
+                    } else {
+                        ((c + 0.055) / 1.055).powf(2.4)
+                    }
+                };
+
+                wgpu::Color {
+                    r: linear(r),
+                    g: linear(g),
+                    b: linear(b),
+                    a,
+                }
+            }
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Clear::None => "none".to_string(),
+            Clear::Color([r, g, b, a]) => format!("#{r:02x}{g:02x}{b:02x}{a:02x}"),
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self, String> {
+        if value == "none" {
+            return Ok(Clear::None);
+        }
+
+        let error = || format!("Clear must be `none` or a `#rrggbb(aa)` color, found `{value}`");
+
+        let hex = value
+            .strip_prefix('#')
+            .filter(|hex| matches!(hex.len(), 6 | 8))
+            .ok_or_else(error)?;
+
+        let mut rgba = [255; 4];
+        for (i, channel) in rgba.iter_mut().enumerate().take(hex.len() / 2) {
+            *channel = hex
+                .get(i * 2..i * 2 + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(error)?;
+        }
+
+        Ok(Clear::Color(rgba))
+    }
+}
+
+impl Display for Clear {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Clear::None => write!(f, "No clear"),
+            Clear::Color(_) => write!(f, "Clear to {}", self.name()),
+        }
+    }
+}
@@ -0,0 +1,436 @@
+use crate::viewer::directive::{self, Directive};
+use crate::viewer::library;
+use crate::viewer::probe::{self, Instrumented, Probe};
+use iced::widget::shader::wgpu;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+
+/// The bind group that user-declared resources (storage buffers, textures & samplers) live in.
+/// Group 0 is reserved for halo's uniforms.
+pub const USER_GROUP: u32 = 1;
+
+/// The globals halo's prelude binds to group 0.
+const PRELUDE_GLOBALS: [&str; 5] = [
+    "uniforms",
+    "camera",
+    "halo_audio",
+    "halo_keyboard",
+    "halo_view",
+];
+
+/// How many elements a runtime-sized storage array has without a `//#length` directive.
+const DEFAULT_LENGTH: u32 = 1024;
+
+/// A validated shader with halo's prelude prepended & its imported libraries appended, ready to be
+/// built into a pipeline.
+///
+/// The shader is assembled & parsed here on the UI thread so the render thread only has to
+/// translate it for its backend when it builds a pipeline.
+#[derive(Debug)]
+pub struct Shader {
+    /// Hash of the shader's source; identical shaders share a cached pipeline.
+    pub id: u64,
+    pub source: String,
+    /// The parsed `source`, if it parses. Pipelines are built from it instead of the source.
+    pub module: Option<naga::Module>,
+    pub reflection: Reflection,
+    /// The shader's `halo_probe` calls, in the order they're recorded.
+    pub probes: Vec<Probe>,
+}
+
+impl Shader {
+    pub fn new(shader: &str) -> Self {
+        let mut hasher = DefaultHasher::new();
+        shader.hash(&mut hasher);
+
+        let prelude = include_str!("shaders/uniforms.wgsl");
+        let erased = probe::erase(shader).source;
+        let imports = library::imports(shader);
+
+        // the shader has already been validated against the target, so this only fails if
+        // the built-in default shader is broken
+        let (reflection, instrumented) =
+            naga::front::wgsl::parse_str(&format!("{prelude}\n{erased}{imports}"))
+                .ok()
+                .and_then(|module| {
+                    let info = naga::valid::Validator::new(
+                        naga::valid::ValidationFlags::default(),
+                        naga::valid::Capabilities::all(),
+                    )
+                    .validate(&module)
+                    .ok()?;
+
+                    Some((
+                        Reflection::new(shader, &module, &info).ok()?,
+                        probe::instrument(shader, &module, &info).ok()?,
+                    ))
+                })
+                .unwrap_or_else(|| {
+                    (
+                        Reflection::default(),
+                        Instrumented {
+                            source: erased,
+                            probes: vec![],
+                        },
+                    )
+                });
+
+        let source = format!("{prelude}\n{}{imports}", instrumented.source);
+
+        Self {
+            id: hasher.finish(),
+            module: naga::front::wgsl::parse_str(&source).ok(),
+            source,
+            reflection,
+            probes: instrumented.probes,
+        }
+    }
+}
+
+/// What halo needs to know about a shader's entry points & resources to build its pipeline.
+#[derive(Debug, Default)]
+pub struct Reflection {
+    /// The user's `@vertex` entry point, if any. Without one, the fragment shader is drawn on a
+    /// full-screen quad.
+    pub vertex_entry_point: Option<String>,
+    /// Dispatched every frame in the order they're declared, before the render pass.
+    pub compute_passes: Vec<ComputePass>,
+    /// Every resource in the user's bind group, by binding. Declarations which share a binding
+    /// share a resource, e.g. a storage texture written by a compute pass can be sampled by the
+    /// fragment shader through a `texture_2d` declared with the same binding.
+    pub resources: BTreeMap<u32, Resource>,
+    /// The bindings of the user's bind group used by the vertex & fragment shaders.
+    pub render_bindings: Vec<Binding>,
+    /// Whether the vertex or fragment shader uses the 2D camera, so dragging pans it instead of
+    /// orbiting the 3D one.
+    pub uses_pan: bool,
+}
+
+#[derive(Debug)]
+pub struct ComputePass {
+    pub entry_point: String,
+    pub workgroup_size: [u32; 3],
+    /// Set with a `//#dispatch` directive. Defaults to enough workgroups to cover the viewer.
+    pub workgroups: Option<[u32; 3]>,
+    pub bindings: Vec<Binding>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resource {
+    Buffer {
+        size: u64,
+    },
+    /// A texture the size of the rendered frame.
+    Texture {
+        format: wgpu::TextureFormat,
+    },
+    Sampler,
+}
+
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub binding: u32,
+    pub ty: wgpu::BindingType,
+}
+
+impl Reflection {
+    pub fn new(
+        shader: &str,
+        module: &naga::Module,
+        info: &naga::valid::ModuleInfo,
+    ) -> Result<Self, String> {
+        let mut workgroups = BTreeMap::new();
+        let mut lengths = BTreeMap::new();
+
+        for (_range, directive) in directive::parse(shader) {
+            match directive? {
+                Directive::Dispatch {
+                    entry_point,
+                    workgroups: count,
+                } => {
+                    workgroups.insert(entry_point, count);
+                }
+                Directive::Length { variable, elements } => {
+                    lengths.insert(variable, elements);
+                }
+                Directive::Render(_) | Directive::Import(_) => {}
+            }
+        }
+
+        let mut resources = BTreeMap::new();
+        let mut bindings = vec![];
+
+        for (handle, global) in module.global_variables.iter() {
+            let Some(binding) = &global.binding else {
+                continue;
+            };
+
+            let name = global.name.as_deref().unwrap_or_default();
+
+            match binding.group {
+                0 if PRELUDE_GLOBALS.contains(&name) => continue,
+                0 => {
+                    return Err(format!(
+                        "`{name}` can't be bound to group 0, which is reserved for halo's \
+                        uniforms. Use `@group({USER_GROUP})` instead."
+                    ))
+                }
+                USER_GROUP => {}
+                group => {
+                    return Err(format!(
+                        "`{name}` is bound to group {group}, but only `@group({USER_GROUP})` \
+                        is available for resources"
+                    ))
+                }
+            }
+
+            let length = lengths.get(name).copied().unwrap_or(DEFAULT_LENGTH);
+            let (resource, ty) = reflect_global(module, global, length)
+                .ok_or_else(|| format!("`{name}` has a resource type halo doesn't support"))?;
+
+            match resources.get(&binding.binding) {
+                None => {
+                    resources.insert(binding.binding, resource);
+                }
+                Some(existing)
+                    if std::mem::discriminant(existing) != std::mem::discriminant(&resource) =>
+                {
+                    return Err(format!(
+                        "`{name}` shares binding {} with a different kind of resource",
+                        binding.binding
+                    ));
+                }
+                // storage textures decide the format of textures which are only sampled
+                Some(_) if matches!(ty, wgpu::BindingType::StorageTexture { .. }) => {
+                    resources.insert(binding.binding, resource);
+                }
+                Some(_) => {}
+            }
+
+            bindings.push((handle, binding.binding, ty));
+        }
+
+        // sampled textures can only be filtered if the format written to them allows it
+        for (_, binding, ty) in &mut bindings {
+            if let (
+                wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable },
+                    ..
+                },
+                Some(Resource::Texture { format }),
+            ) = (ty, resources.get(binding))
+            {
+                *filterable = matches!(
+                    format.sample_type(None),
+                    Some(wgpu::TextureSampleType::Float { filterable: true })
+                );
+            }
+        }
+
+        let used_by = |index: usize| {
+            let info = info.get_entry_point(index);
+
+            bindings
+                .iter()
+                .filter(|(handle, _, _)| !info[*handle].is_empty())
+                .map(|(_, binding, ty)| Binding {
+                    binding: *binding,
+                    ty: *ty,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut reflection = Reflection {
+            resources,
+            ..Default::default()
+        };
+
+        let view = module
+            .global_variables
+            .iter()
+            .find(|(_, global)| global.name.as_deref() == Some("halo_view"))
+            .map(|(handle, _)| handle);
+
+        for (index, entry_point) in module.entry_points.iter().enumerate() {
+            if entry_point.stage != naga::ShaderStage::Compute {
+                reflection.uses_pan |=
+                    view.is_some_and(|view| !info.get_entry_point(index)[view].is_empty());
+            }
+
+            match entry_point.stage {
+                naga::ShaderStage::Vertex => {
+                    reflection.vertex_entry_point = Some(entry_point.name.clone());
+                    reflection.render_bindings.extend(used_by(index));
+                }
+                naga::ShaderStage::Fragment => {
+                    reflection.render_bindings.extend(used_by(index));
+                }
+                naga::ShaderStage::Compute => {
+                    reflection.compute_passes.push(ComputePass {
+                        entry_point: entry_point.name.clone(),
+                        workgroup_size: entry_point.workgroup_size,
+                        workgroups: workgroups.remove(&entry_point.name),
+                        bindings: used_by(index),
+                    });
+                }
+            }
+        }
+
+        if let Some(entry_point) = workgroups.keys().next() {
+            return Err(format!(
+                "`//#dispatch {entry_point}` doesn't refer to a `@compute` entry point"
+            ));
+        }
+
+        reflection
+            .render_bindings
+            .sort_by_key(|binding| binding.binding);
+        reflection
+            .render_bindings
+            .dedup_by_key(|binding| binding.binding);
+
+        Ok(reflection)
+    }
+}
+
+fn reflect_global(
+    module: &naga::Module,
+    global: &naga::GlobalVariable,
+    length: u32,
+) -> Option<(Resource, wgpu::BindingType)> {
+    match global.space {
+        naga::AddressSpace::Storage { access } => Some((
+            Resource::Buffer {
+                size: buffer_size(module, global.ty, length),
+            },
+            wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage {
+                    read_only: !access.contains(naga::StorageAccess::STORE),
+                },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+        )),
+        naga::AddressSpace::Handle => match module.types[global.ty].inner {
+            naga::TypeInner::Image {
+                dim: naga::ImageDimension::D2,
+                arrayed: false,
+                class,
+            } => match class {
+                naga::ImageClass::Storage { format, access } => {
+                    let format = texture_format(format)?;
+                    let access = match (
+                        access.contains(naga::StorageAccess::LOAD),
+                        access.contains(naga::StorageAccess::STORE),
+                    ) {
+                        (true, true) => wgpu::StorageTextureAccess::ReadWrite,
+                        (true, false) => wgpu::StorageTextureAccess::ReadOnly,
+                        _ => wgpu::StorageTextureAccess::WriteOnly,
+                    };
+
+                    Some((
+                        Resource::Texture { format },
+                        wgpu::BindingType::StorageTexture {
+                            access,
+                            format,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                        },
+                    ))
+                }
+                naga::ImageClass::Sampled { kind, multi: false } => {
+                    let (format, sample_type) = match kind {
+                        naga::ScalarKind::Float => (
+                            wgpu::TextureFormat::Rgba8Unorm,
+                            wgpu::TextureSampleType::Float { filterable: true },
+                        ),
+                        naga::ScalarKind::Uint => (
+                            wgpu::TextureFormat::Rgba8Uint,
+                            wgpu::TextureSampleType::Uint,
+                        ),
+                        naga::ScalarKind::Sint => (
+                            wgpu::TextureFormat::Rgba8Sint,
+                            wgpu::TextureSampleType::Sint,
+                        ),
+                        naga::ScalarKind::Bool => return None,
+                    };
+
+                    Some((
+                        Resource::Texture { format },
+                        wgpu::BindingType::Texture {
+                            sample_type,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                    ))
+                }
+                _ => None,
+            },
+            naga::TypeInner::Sampler { comparison: false } => Some((
+                Resource::Sampler,
+                wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            )),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The size of a storage buffer, with `length` elements in its runtime-sized array if it has one.
+///
+/// Sizes too large to represent are [`u64::MAX`], which validation rejects as being over the
+/// target's limits.
+fn buffer_size(module: &naga::Module, ty: naga::Handle<naga::Type>, length: u32) -> u64 {
+    let runtime_array = |ty: naga::Handle<naga::Type>| match module.types[ty].inner {
+        naga::TypeInner::Array {
+            size: naga::ArraySize::Dynamic,
+            stride,
+            ..
+        } => Some(stride),
+        _ => None,
+    };
+    let array_size = |offset: u32, stride: u32| {
+        u64::from(stride)
+            .checked_mul(u64::from(length))
+            .and_then(|size| size.checked_add(u64::from(offset)))
+    };
+
+    let size = match &module.types[ty].inner {
+        naga::TypeInner::Struct { members, span } => members
+            .last()
+            .and_then(|last| runtime_array(last.ty).map(|stride| array_size(last.offset, stride)))
+            .unwrap_or(Some(u64::from(*span))),
+        _ => runtime_array(ty)
+            .map(|stride| array_size(0, stride))
+            .unwrap_or_else(|| Some(u64::from(module.types[ty].inner.size(module.to_ctx())))),
+    };
+
+    // wgpu requires buffer sizes to be a multiple of 4
+    size.and_then(|size| size.max(4).checked_next_multiple_of(4))
+        .unwrap_or(u64::MAX)
+}
+
+fn texture_format(format: naga::StorageFormat) -> Option<wgpu::TextureFormat> {
+    use naga::StorageFormat as S;
+    use wgpu::TextureFormat as T;
+
+    Some(match format {
+        S::R32Uint => T::R32Uint,
+        S::R32Sint => T::R32Sint,
+        S::R32Float => T::R32Float,
+        S::Rg32Uint => T::Rg32Uint,
+        S::Rg32Sint => T::Rg32Sint,
+        S::Rg32Float => T::Rg32Float,
+        S::Rgba8Unorm => T::Rgba8Unorm,
+        S::Rgba8Snorm => T::Rgba8Snorm,
+        S::Rgba8Uint => T::Rgba8Uint,
+        S::Rgba8Sint => T::Rgba8Sint,
+        S::Rgba16Uint => T::Rgba16Uint,
+        S::Rgba16Sint => T::Rgba16Sint,
+        S::Rgba16Float => T::Rgba16Float,
+        S::Rgba32Uint => T::Rgba32Uint,
+        S::Rgba32Sint => T::Rgba32Sint,
+        S::Rgba32Float => T::Rgba32Float,
+        _ => return None,
+    })
+}
@@ -7,15 +7,15 @@ fn heart_sd(in: vec2<f32>) -> f32 {
     let p = vec2<f32>(abs(in.x), in.y);
 
     if (p.y + p.x > 1.0) {
-        return sqrt(dot2(p - vec2<f32>(0.25, 0.75))) - sqrt(2.0)/4.0;
+        return sqrt(dot2(p - vec2<f32>(0.25, 0.75))) - sqrt(2.0) / 4.0;
     }
 
     return sqrt(min(dot2(p - vec2<f32>(0.0, 1.0)), dot2(p - 0.5 * max(p.x + p.y, 0.0)))) * sign(p.x - p.y);
 }
 
 fn palette(t: f32) -> vec3<f32> {
-    let a = vec3<f32>(0.5, 0.5, 0.5	);
-    let b = vec3<f32>(0.5, 0.5, 0.5	);
+    let a = vec3<f32>(0.5, 0.5, 0.5);
+    let b = vec3<f32>(0.5, 0.5, 0.5);
     let c = vec3<f32>(1.0, 1.0, 1.0);
     let d = vec3<f32>(0.00, 0.33, 0.67);
 
@@ -25,7 +25,7 @@ fn palette(t: f32) -> vec3<f32> {
 //modified from https://www.youtube.com/watch?v=f4s1h2YETNY!
 @fragment
 fn fs_main(@builtin(position) clip_pos: vec4<f32>) -> @location(0) vec4<f32> {
-    var uv = (clip_pos.xy * 2.0 - uniforms.scale ) / uniforms.scale.y;
+    var uv = (clip_pos.xy * 2.0 - uniforms.scale) / uniforms.scale.y;
     uv.y = -uv.y + 0.65; //flip coords & center heart
     var uv0 = uv;
     var final_color = vec3<f32>(0.0);
@@ -0,0 +1,20 @@
+struct HaloProbes {
+    //the pixel probes are recorded at, in frame pixels
+    pixel: vec2<u32>,
+    //a bit per probe, set once it's been recorded
+    hits: u32,
+    //the bits of each probed value, padded to a vec4
+    values: array<vec4<u32>, 32>,
+}
+
+@group(0) @binding(2) var<storage, read_write> halo_probes: HaloProbes;
+
+//whether this invocation is shading the probed pixel, set at the start of fs_main
+var<private> halo_probing: bool;
+
+fn halo_record(index: u32, value: vec4<u32>) {
+    if (halo_probing) {
+        halo_probes.hits |= 1u << index;
+        halo_probes.values[index] = value;
+    }
+}
@@ -0,0 +1,66 @@
+@group(0) @binding(0) var frame: texture_2d<f32>;
+@group(0) @binding(1) var output: texture_storage_2d<rgba8unorm, write>;
+//nan, inf, negative & over 1.0
+@group(0) @binding(2) var<storage, read_write> counts: array<atomic<u32>, 4>;
+
+const EXPONENT: u32 = 0x7f800000u;
+const MANTISSA: u32 = 0x007fffffu;
+
+//compared by bits, since the compiler may assume floats are never nan or inf
+fn is_nan(value: f32) -> bool {
+    let bits = bitcast<u32>(value);
+    return (bits & EXPONENT) == EXPONENT && (bits & MANTISSA) != 0u;
+}
+
+fn is_inf(value: f32) -> bool {
+    let bits = bitcast<u32>(value);
+    return (bits & EXPONENT) == EXPONENT && (bits & MANTISSA) == 0u;
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(frame);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+
+    let color = textureLoad(frame, vec2<i32>(id.xy), 0);
+
+    var nan = false;
+    var inf = false;
+    var negative = false;
+    var over = false;
+    for (var i = 0; i < 4; i++) {
+        let value = color[i];
+        if (is_nan(value)) {
+            nan = true;
+        } else if (is_inf(value)) {
+            inf = true;
+        } else if (value < 0.0) {
+            negative = true;
+        } else if (value > 1.0) {
+            over = true;
+        }
+    }
+
+    var flagged: vec3<f32>;
+    if (nan) {
+        atomicAdd(&counts[0], 1u);
+        flagged = vec3<f32>(1.0, 0.0, 1.0);
+    } else if (inf) {
+        atomicAdd(&counts[1], 1u);
+        flagged = vec3<f32>(0.0, 1.0, 1.0);
+    } else if (negative) {
+        atomicAdd(&counts[2], 1u);
+        flagged = vec3<f32>(0.0, 0.2, 1.0);
+    } else if (over) {
+        atomicAdd(&counts[3], 1u);
+        flagged = vec3<f32>(1.0, 0.1, 0.0);
+    } else {
+        //dimmed so flagged pixels stand out
+        let luma = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+        flagged = vec3<f32>(luma * 0.25);
+    }
+
+    textureStore(output, vec2<i32>(id.xy), vec4<f32>(flagged, 1.0));
+}
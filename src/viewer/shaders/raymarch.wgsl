@@ -0,0 +1,83 @@
+//sphere tracing through the orbit camera, imported with `//#import raymarch`
+//the scene is the distance to its nearest surface, which the shader defines as
+//`fn halo_scene(p: vec3<f32>) -> f32`
+
+const HALO_MAX_STEPS: i32 = 256;
+const HALO_MAX_DISTANCE: f32 = 100.0;
+//how close to a surface a ray has to get to hit it
+const HALO_EPSILON: f32 = 0.0005;
+
+struct HaloRay {
+    origin: vec3<f32>,
+    direction: vec3<f32>,
+}
+
+//the ray from the orbit camera's eye through a frame pixel, e.g. `@builtin(position).xy`
+fn halo_camera_ray(pixel: vec2<f32>) -> HaloRay {
+    let ndc = vec2<f32>(2.0, -2.0) * pixel / uniforms.scale + vec2<f32>(-1.0, 1.0);
+    let far = camera.inverse_view_projection * vec4<f32>(ndc, 1.0, 1.0);
+    return HaloRay(camera.eye, normalize(far.xyz / far.w - camera.eye));
+}
+
+//the distance along `ray` to the scene, or a negative distance if it misses
+fn halo_raymarch(ray: HaloRay) -> f32 {
+    var t = 0.0;
+
+    for (var i = 0; i < HALO_MAX_STEPS; i++) {
+        let distance = halo_scene(ray.origin + ray.direction * t);
+
+        if distance < HALO_EPSILON * t {
+            return t;
+        }
+
+        t += distance;
+
+        if t > HALO_MAX_DISTANCE {
+            break;
+        }
+    }
+
+    return -1.0;
+}
+
+//the scene's surface normal at `p`, from its gradient
+fn halo_normal(p: vec3<f32>) -> vec3<f32> {
+    let e = vec2<f32>(1.0, -1.0) * 0.0005;
+    return normalize(
+        e.xyy * halo_scene(p + e.xyy) + e.yyx * halo_scene(p + e.yyx)
+        + e.yxy * halo_scene(p + e.yxy) + e.xxx * halo_scene(p + e.xxx)
+    );
+}
+
+//how lit `p` is from 0 to 1 by a light in `direction`, with penumbras softer for smaller `k`
+fn halo_soft_shadow(p: vec3<f32>, direction: vec3<f32>, k: f32) -> f32 {
+    var light = 1.0;
+    var t = 0.01;
+
+    for (var i = 0; i < 64 && t < HALO_MAX_DISTANCE; i++) {
+        let distance = halo_scene(p + direction * t);
+
+        if distance < HALO_EPSILON {
+            return 0.0;
+        }
+
+        light = min(light, k * distance / t);
+        t += clamp(distance, 0.01, 0.5);
+    }
+
+    return clamp(light, 0.0, 1.0);
+}
+
+//how unoccluded `p` on a surface facing `normal` is, from 0 to 1
+fn halo_ambient_occlusion(p: vec3<f32>, normal: vec3<f32>) -> f32 {
+    var occlusion = 0.0;
+    var weight = 1.0;
+
+    for (var i = 1; i <= 5; i++) {
+        let h = 0.03 * f32(i);
+        occlusion += (h - halo_scene(p + normal * h)) * weight;
+        weight *= 0.8;
+    }
+
+    return clamp(1.0 - 3.0 * occlusion, 0.0, 1.0);
+}
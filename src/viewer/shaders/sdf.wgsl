@@ -0,0 +1,63 @@
+//signed distance functions & operators, imported with `//#import sdf`
+//every shape is centered on the origin, so move `p` into its space first, e.g. `p - center`
+
+fn halo_sd_sphere(p: vec3<f32>, radius: f32) -> f32 {
+    return length(p) - radius;
+}
+
+//`half_size` is the distance from the box's center to its faces along each axis
+fn halo_sd_box(p: vec3<f32>, half_size: vec3<f32>) -> f32 {
+    let q = abs(p) - half_size;
+    return length(max(q, vec3<f32>(0.0))) + min(max(q.x, max(q.y, q.z)), 0.0);
+}
+
+fn halo_sd_round_box(p: vec3<f32>, half_size: vec3<f32>, radius: f32) -> f32 {
+    return halo_sd_box(p, half_size - radius) - radius;
+}
+
+//a torus lying in the xz plane
+fn halo_sd_torus(p: vec3<f32>, major_radius: f32, minor_radius: f32) -> f32 {
+    let q = vec2<f32>(length(p.xz) - major_radius, p.y);
+    return length(q) - minor_radius;
+}
+
+//a capsule from `a` to `b`
+fn halo_sd_capsule(p: vec3<f32>, a: vec3<f32>, b: vec3<f32>, radius: f32) -> f32 {
+    let pa = p - a;
+    let ba = b - a;
+    let h = clamp(dot(pa, ba) / dot(ba, ba), 0.0, 1.0);
+    return length(pa - ba * h) - radius;
+}
+
+//a plane through the origin facing `normal`, which must be normalized
+fn halo_sd_plane(p: vec3<f32>, normal: vec3<f32>) -> f32 {
+    return dot(p, normal);
+}
+
+fn halo_op_union(a: f32, b: f32) -> f32 {
+    return min(a, b);
+}
+
+fn halo_op_subtract(a: f32, b: f32) -> f32 {
+    return max(a, -b);
+}
+
+fn halo_op_intersect(a: f32, b: f32) -> f32 {
+    return max(a, b);
+}
+
+//a union blending the shapes together over a distance of `k`
+fn halo_op_smooth_union(a: f32, b: f32, k: f32) -> f32 {
+    let h = clamp(0.5 + 0.5 * (b - a) / k, 0.0, 1.0);
+    return mix(b, a, h) - k * h * (1.0 - h);
+}
+
+fn halo_op_smooth_subtract(a: f32, b: f32, k: f32) -> f32 {
+    let h = clamp(0.5 - 0.5 * (a + b) / k, 0.0, 1.0);
+    return mix(a, -b, h) + k * h * (1.0 - h);
+}
+
+fn halo_op_smooth_intersect(a: f32, b: f32, k: f32) -> f32 {
+    let h = clamp(0.5 - 0.5 * (b - a) / k, 0.0, 1.0);
+    return mix(b, a, h) + k * h * (1.0 - h);
+}
@@ -2,8 +2,53 @@ struct Uniforms {
     transform: mat4x4<f32>,
     position: vec2<f32>,
     scale: vec2<f32>,
+    //the cursor over the viewer in frame pixels, or -1 outside it unless a button is held
     mouse: vec2<f32>,
     time: f32,
+    //the buttons held after being pressed over the viewer: 1 for left, 2 for right & 4 for middle
+    buttons: u32,
+    //`mouse` from 0 to 1 across the viewer
+    mouse_uv: vec2<f32>,
+    //where a button was last pressed over the viewer in frame pixels, or -1 before the first press
+    drag_start: vec2<f32>,
+    drag_start_uv: vec2<f32>,
+    //the lines scrolled over the viewer
+    scroll: vec2<f32>,
 }
 
-@group(0) @binding(0) var<uniform> uniforms: Uniforms;
\ No newline at end of file
+//the orbit camera, rotated by dragging, dollied by scrolling & panned by dragging with the right
+//button when the shader doesn't use the 2D camera
+struct Camera {
+    view_projection: mat4x4<f32>,
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    eye: vec3<f32>,
+    //maps clip space back to world space, e.g. to cast rays from the eye through each pixel
+    inverse_view_projection: mat4x4<f32>,
+}
+
+//the 2D camera, panned by dragging & zoomed by scrolling when the shader uses it
+struct View {
+    //the point at the center of the viewer, which is `center + center_low` for deep zooms
+    center: vec2<f32>,
+    center_low: vec2<f32>,
+    //the size of a frame pixel in view coordinates
+    pixel_size: f32,
+    //1 when the viewer's height spans from -1 to 1
+    zoom: f32,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+@group(0) @binding(1) var<uniform> camera: Camera;
+//the loaded audio at `uniforms.time`, 512 texels wide: its spectrum in row 0 & its waveform in row 1
+@group(0) @binding(3) var halo_audio: texture_2d<f32>;
+//the keys held while the viewer is focused, by JavaScript key code: row 0 is whether each is held,
+//row 1 whether it was pressed since the last frame & row 2 whether it's toggled
+@group(0) @binding(4) var halo_keyboard: texture_2d<f32>;
+@group(0) @binding(5) var<uniform> halo_view: View;
+
+//maps a frame pixel, e.g. `@builtin(position).xy`, to the 2D camera's view coordinates with y up
+fn halo_view_position(pixel: vec2<f32>) -> vec2<f32> {
+    let offset = (pixel - uniforms.scale * 0.5) * vec2<f32>(1.0, -1.0) * halo_view.pixel_size;
+    return halo_view.center + (halo_view.center_low + offset);
+}
\ No newline at end of file
@@ -0,0 +1,225 @@
+use crate::theme::Theme;
+use crate::widget::Renderer;
+use iced::widget::canvas;
+use iced::{mouse, Color, Point, Rectangle, Size};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How many frames are kept for the graph & CSV export.
+const HISTORY: usize = 3600;
+
+/// How many of the most recent frames are shown in the graph.
+const GRAPH_FRAMES: usize = 240;
+
+/// The frame time the graph is scaled to; slower frames are clipped.
+const GRAPH_MAX: Duration = Duration::from_millis(50);
+
+/// How long the render thread spent on the last frame, written by the primitive.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Timings {
+    /// Time spent preparing & encoding the shader's passes.
+    pub cpu: Duration,
+    /// Time the shader's passes took on the GPU, if the device supports timestamp queries.
+    pub gpu: Option<Duration>,
+}
+
+pub type SharedTimings = Arc<Mutex<Timings>>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    /// When the frame was presented, relative to when recording started.
+    pub at: Duration,
+    /// Time since the previous frame.
+    pub frame_time: Duration,
+    pub timings: Timings,
+}
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    start: Option<Instant>,
+    last: Option<Instant>,
+    frames: VecDeque<Frame>,
+}
+
+impl Stats {
+    pub fn push(&mut self, now: Instant, timings: Timings) {
+        let start = *self.start.get_or_insert(now);
+
+        if let Some(last) = self.last.replace(now) {
+            if self.frames.len() == HISTORY {
+                let _ = self.frames.pop_front();
+            }
+
+            self.frames.push_back(Frame {
+                at: now - start,
+                frame_time: now - last,
+                timings,
+            });
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Frames per second, averaged over the last second.
+    pub fn fps(&self) -> f32 {
+        let Some(latest) = self.frames.back() else {
+            return 0.0;
+        };
+
+        let (count, total) = self
+            .frames
+            .iter()
+            .rev()
+            .take_while(|frame| latest.at - frame.at < Duration::from_secs(1))
+            .fold((0, Duration::ZERO), |(count, total), frame| {
+                (count + 1, total + frame.frame_time)
+            });
+
+        count as f32 / total.as_secs_f32().max(f32::EPSILON)
+    }
+
+    pub fn latest(&self) -> Option<&Frame> {
+        self.frames.back()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let ms = |duration: Duration| format!("{:.3}", duration.as_secs_f64() * 1000.0);
+
+        let mut csv = "time_ms,frame_time_ms,cpu_ms,gpu_ms\n".to_string();
+
+        for frame in &self.frames {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                ms(frame.at),
+                ms(frame.frame_time),
+                ms(frame.timings.cpu),
+                frame.timings.gpu.map(ms).unwrap_or_default(),
+            ));
+        }
+
+        csv
+    }
+
+    /// A rolling graph of the most recent frame times, with GPU time overlaid when available.
+    pub fn graph(&self) -> Graph<'_> {
+        Graph(self)
+    }
+}
+
+pub struct Graph<'a>(&'a Stats);
+
+impl<'a, Message> canvas::Program<Message, Renderer> for Graph<'a> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let palette = theme.palette();
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+
+        let bar_width = bounds.width / GRAPH_FRAMES as f32;
+        let height = |duration: Duration| {
+            (duration.as_secs_f32() / GRAPH_MAX.as_secs_f32()).min(1.0) * bounds.height
+        };
+
+        let frames = self.0.frames.iter().rev().take(GRAPH_FRAMES);
+
+        for (i, stats) in frames.enumerate() {
+            let x = bounds.width - (i + 1) as f32 * bar_width;
+
+            let mut bar = |duration, color| {
+                let height = height(duration);
+
+                frame.fill_rectangle(
+                    Point::new(x, bounds.height - height),
+                    Size::new(bar_width, height),
+                    color,
+                );
+            };
+
+            bar(stats.frame_time, palette.base);
+
+            if let Some(gpu) = stats.timings.gpu {
+                bar(gpu, palette.accent_secondary);
+            }
+        }
+
+        // 60 fps target
+        frame.fill_rectangle(
+            Point::new(0.0, bounds.height - height(Duration::from_micros(16_667))),
+            Size::new(bounds.width, 1.0),
+            Color {
+                a: 0.5,
+                ..palette.text
+            },
+        );
+
+        vec![frame.into_geometry()]
+    }
+}
+
+pub async fn export(csv: String) -> Result<PathBuf, Error> {
+    let path = rfd::AsyncFileDialog::new()
+        .add_filter("CSV", &["csv"])
+        .set_file_name("halo_stats.csv")
+        .save_file()
+        .await
+        .as_ref()
+        .map(rfd::FileHandle::path)
+        .map(Path::to_owned)
+        .ok_or(Error::DialogueClosed)?;
+
+    tokio::fs::write(&path, csv)
+        .await
+        .map_err(|error| Error::Io(error.kind()))?;
+
+    Ok(path)
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    DialogueClosed,
+    Io(std::io::ErrorKind),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded(gpu: Option<Duration>) -> Stats {
+        let start = Instant::now();
+        let mut stats = Stats::default();
+        let timings = Timings {
+            cpu: Duration::from_millis(1),
+            gpu,
+        };
+
+        stats.push(start, timings);
+        stats.push(start + Duration::from_millis(16), timings);
+
+        stats
+    }
+
+    #[test]
+    fn csv_has_gpu_time_when_measured() {
+        let csv = recorded(Some(Duration::from_millis(2))).to_csv();
+
+        assert_eq!(csv.lines().nth(1), Some("16.000,16.000,1.000,2.000"));
+    }
+
+    #[test]
+    fn csv_leaves_gpu_time_empty_when_unavailable() {
+        let csv = recorded(None).to_csv();
+
+        assert_eq!(csv.lines().nth(1), Some("16.000,16.000,1.000,"));
+    }
+}
@@ -0,0 +1,73 @@
+use iced::widget::shader::wgpu;
+use iced::Size;
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+
+/// A small single-channel texture in halo's bind group, rewritten by the CPU every frame, e.g.
+/// `halo_audio` & `halo_keyboard`.
+pub struct Texture {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    binding: u32,
+}
+
+impl Texture {
+    pub fn new(device: &wgpu::Device, label: &str, binding: u32, size: Size<u32>) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        Self {
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            texture,
+            binding,
+        }
+    }
+
+    pub fn layout_entry(&self) -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding: self.binding,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        }
+    }
+
+    pub fn bind_group_entry(&self) -> wgpu::BindGroupEntry<'_> {
+        wgpu::BindGroupEntry {
+            binding: self.binding,
+            resource: wgpu::BindingResource::TextureView(&self.view),
+        }
+    }
+
+    /// Replaces the texture's contents with `data`, a byte per texel row by row.
+    pub fn write(&self, queue: &wgpu::Queue, data: &[u8]) {
+        let size = self.texture.size();
+
+        queue.write_texture(
+            self.texture.as_image_copy(),
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(size.width),
+                rows_per_image: None,
+            },
+            size,
+        );
+    }
+}
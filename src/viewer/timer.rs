@@ -0,0 +1,84 @@
+use crate::viewer::readback::Readback;
+use iced::widget::shader::wgpu;
+use std::time::Duration;
+
+/// Measures how long the shader's passes take on the GPU with timestamp queries.
+///
+/// Only one measurement is in flight at a time; while it's being read back, frames aren't timed.
+pub struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve: wgpu::Buffer,
+    readback: Readback<()>,
+    /// Nanoseconds per timestamp tick.
+    period: f32,
+}
+
+const SIZE: u64 = 2 * std::mem::size_of::<u64>() as u64;
+
+impl GpuTimer {
+    /// Returns `None` if the device wasn't created with [`wgpu::Features::TIMESTAMP_QUERY`].
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+
+        Some(Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("halo.timer.query_set"),
+                ty: wgpu::QueryType::Timestamp,
+                count: 2,
+            }),
+            resolve: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("halo.timer.resolve"),
+                size: SIZE,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback: Readback::new(device, "halo.timer.readback", SIZE),
+            period: queue.get_timestamp_period(),
+        })
+    }
+
+    /// Whether the next frame can be timed.
+    pub fn is_idle(&self) -> bool {
+        self.readback.is_idle()
+    }
+
+    /// Writes the start timestamp at the beginning of the compute passes.
+    pub fn compute_pass_writes(&self) -> wgpu::ComputePassTimestampWrites<'_> {
+        wgpu::ComputePassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(0),
+            end_of_pass_write_index: None,
+        }
+    }
+
+    /// Writes the end timestamp at the end of the render pass, & the start timestamp too if no
+    /// compute pass wrote it.
+    pub fn render_pass_writes(&self, is_first_pass: bool) -> wgpu::RenderPassTimestampWrites<'_> {
+        wgpu::RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: is_first_pass.then_some(0),
+            end_of_pass_write_index: Some(1),
+        }
+    }
+
+    /// Copies the timestamps written this frame to the readback buffer.
+    pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve, 0, self.readback.buffer(), 0, SIZE);
+
+        self.readback.copied(());
+    }
+
+    /// Returns the duration between the timestamps of the last timed frame, once they've been
+    /// read back.
+    pub fn poll(&self) -> Option<Duration> {
+        self.readback.poll(|data, ()| {
+            let timestamps: &[u64] = bytemuck::cast_slice(data);
+            let ticks = timestamps[1].saturating_sub(timestamps[0]);
+
+            Duration::from_nanos((ticks as f64 * f64::from(self.period)) as u64)
+        })
+    }
+}
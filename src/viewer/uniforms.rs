@@ -1,25 +1,124 @@
 use iced::widget::shader::Transformation;
-use iced::{Point, Rectangle};
+use iced::{mouse, Point, Rectangle, Size, Vector};
 use std::time::Duration;
 
+/// How many pixels of a precise scroll make up a line of a mouse wheel's scroll.
+const PIXELS_PER_LINE: f32 = 20.0;
+
 #[derive(Debug)]
 pub struct Uniforms {
     pub time: Duration,
-    pub mouse: Point,
+    pub mouse: Mouse,
     pub bounds: Rectangle,
 }
+
+/// The mouse as the viewer's shader sees it, tracked from the events the viewer receives.
+/// Positions are in logical pixels relative to the viewer's top left corner.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mouse {
+    /// The cursor's position over the viewer, or anywhere while a button pressed over it is held.
+    pub position: Option<Point>,
+    /// Where a button was last pressed over the viewer.
+    pub drag_start: Option<Point>,
+    /// The buttons held after being pressed over the viewer.
+    pub buttons: Buttons,
+    /// The lines scrolled over the viewer.
+    pub scroll: Vector,
+}
+
+/// A bit for each mouse button: 1 for left, 2 for right & 4 for middle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Buttons(u32);
+
+impl Buttons {
+    fn bit(button: mouse::Button) -> u32 {
+        match button {
+            mouse::Button::Left => 1,
+            mouse::Button::Right => 2,
+            mouse::Button::Middle => 4,
+            mouse::Button::Other(_) => 0,
+        }
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl Mouse {
+    /// Tracks a mouse event, returning whether it was over the viewer.
+    pub fn update(
+        &mut self,
+        event: mouse::Event,
+        cursor: mouse::Cursor,
+        bounds: Rectangle,
+    ) -> bool {
+        let position = cursor.position_in(bounds);
+
+        match event {
+            mouse::Event::ButtonPressed(button) => {
+                if let Some(position) = position {
+                    self.buttons.0 |= Buttons::bit(button);
+                    self.drag_start = Some(position);
+                }
+            }
+            mouse::Event::ButtonReleased(button) => {
+                self.buttons.0 &= !Buttons::bit(button);
+            }
+            mouse::Event::WheelScrolled { delta } if position.is_some() => {
+                let lines = lines(delta);
+
+                self.scroll = Vector::new(self.scroll.x + lines.x, self.scroll.y + lines.y);
+            }
+            _ => {}
+        }
+
+        self.position = if self.buttons.is_empty() {
+            position
+        } else {
+            // keep following the cursor while dragging, even past the viewer's edges
+            cursor
+                .position()
+                .map(|cursor| Point::new(cursor.x - bounds.x, cursor.y - bounds.y))
+        };
+
+        position.is_some()
+    }
+}
+
+/// The lines a scroll of `delta` covers.
+pub fn lines(delta: mouse::ScrollDelta) -> Vector {
+    match delta {
+        mouse::ScrollDelta::Lines { x, y } => Vector::new(x, y),
+        mouse::ScrollDelta::Pixels { x, y } => {
+            Vector::new(x / PIXELS_PER_LINE, y / PIXELS_PER_LINE)
+        }
+    }
+}
+
 impl Uniforms {
-    pub fn to_raw(&self, scale_factor: f32, transform: Transformation) -> Raw {
+    /// The uniforms for rendering to a frame of `size`, which covers the viewer's bounds at
+    /// `scale` physical pixels per logical pixel.
+    pub fn to_raw(&self, size: Size<u32>, scale: f32) -> Raw {
+        let pixels =
+            |point: Option<Point>| point.map_or([-1.0, -1.0], |p| [p.x * scale, p.y * scale]);
+        let uv = |point: Option<Point>| {
+            point.map_or([-1.0, -1.0], |p| {
+                [p.x / self.bounds.width, p.y / self.bounds.height]
+            })
+        };
+
         Raw {
-            transform: transform.into(),
-            position: [self.bounds.x * scale_factor, self.bounds.y * scale_factor],
-            scale: [
-                self.bounds.width * scale_factor,
-                self.bounds.height * scale_factor,
-            ],
-            mouse: self.mouse.into(),
+            transform: Transformation::orthographic(size.width, size.height).into(),
+            position: [0.0, 0.0],
+            scale: [size.width as f32, size.height as f32],
+            mouse: pixels(self.mouse.position),
             time: self.time.as_secs_f32(),
-            _padding: 0.0,
+            buttons: self.mouse.buttons.0,
+            mouse_uv: uv(self.mouse.position),
+            drag_start: pixels(self.mouse.drag_start),
+            drag_start_uv: uv(self.mouse.drag_start),
+            scroll: [self.mouse.scroll.x, self.mouse.scroll.y],
         }
     }
 }
@@ -32,5 +131,9 @@ pub struct Raw {
     pub scale: [f32; 2],
     pub mouse: [f32; 2],
     pub time: f32,
-    pub _padding: f32,
+    pub buttons: u32,
+    pub mouse_uv: [f32; 2],
+    pub drag_start: [f32; 2],
+    pub drag_start_uv: [f32; 2],
+    pub scroll: [f32; 2],
 }
@@ -1,3 +1,7 @@
+mod shortcuts;
+
+pub use shortcuts::Shortcuts;
+
 use crate::theme::Theme;
 
 // theme type aliases
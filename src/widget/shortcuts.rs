@@ -0,0 +1,176 @@
+use crate::widget::{Element, Renderer};
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::widget::{tree, Operation, Tree};
+use iced::advanced::{overlay, renderer, Clipboard, Shell, Widget};
+use iced::keyboard::{KeyCode, Modifiers};
+use iced::{event, keyboard, mouse, Event, Length, Rectangle};
+
+/// Publishes the message of a key press before its content sees it, so shortcuts work while a
+/// widget which handles key presses itself, like the text editor, is focused.
+pub struct Shortcuts<'a, Message> {
+    content: Element<'a, Message>,
+    on_key_press: Box<dyn Fn(KeyCode, Modifiers) -> Option<Message> + 'a>,
+    on_modifiers_change: Option<Box<dyn Fn(Modifiers) -> Message + 'a>>,
+}
+
+impl<'a, Message> Shortcuts<'a, Message> {
+    pub fn new(
+        content: impl Into<Element<'a, Message>>,
+        on_key_press: impl Fn(KeyCode, Modifiers) -> Option<Message> + 'a,
+    ) -> Self {
+        Self {
+            content: content.into(),
+            on_key_press: Box::new(on_key_press),
+            on_modifiers_change: None,
+        }
+    }
+
+    /// Publishes the held modifiers whenever they change, for modified clicks. The content still
+    /// sees the change.
+    pub fn on_modifiers_change(mut self, on_change: impl Fn(Modifiers) -> Message + 'a) -> Self {
+        self.on_modifiers_change = Some(Box::new(on_change));
+        self
+    }
+}
+
+impl<'a, Message> Widget<Message, Renderer> for Shortcuts<'a, Message> {
+    fn width(&self) -> Length {
+        self.content.as_widget().width()
+    }
+
+    fn height(&self) -> Length {
+        self.content.as_widget().height()
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::stateless()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        match event {
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code,
+                modifiers,
+            }) => {
+                if let Some(message) = (self.on_key_press)(key_code, modifiers) {
+                    shell.publish(message);
+
+                    return event::Status::Captured;
+                }
+            }
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                if let Some(on_change) = &self.on_modifiers_change {
+                    shell.publish(on_change(modifiers));
+                }
+            }
+            _ => {}
+        }
+
+        self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &crate::theme::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+    ) -> Option<overlay::Element<'b, Message, Renderer>> {
+        self.content
+            .as_widget_mut()
+            .overlay(&mut tree.children[0], layout, renderer)
+    }
+}
+
+impl<'a, Message: 'a> From<Shortcuts<'a, Message>> for Element<'a, Message> {
+    fn from(shortcuts: Shortcuts<'a, Message>) -> Self {
+        Element::new(shortcuts)
+    }
+}
@@ -0,0 +1,137 @@
+//! Formats WGSL & checks the result against hand-formatted shaders.
+
+use halo::format::format;
+
+const INDENT: &str = "    ";
+
+#[test]
+fn bundled_shaders_are_formatted() {
+    for shader in [
+        include_str!("../src/viewer/shaders/default_frag.wgsl"),
+        include_str!("../src/viewer/shaders/sdf.wgsl"),
+        include_str!("../src/viewer/shaders/raymarch.wgsl"),
+    ] {
+        assert_eq!(format(shader, INDENT), shader);
+    }
+}
+
+#[test]
+fn indentation_and_line_breaks() {
+    let shader = "struct Particle { position: vec2<f32>, velocity: vec2<f32> }
+fn f(a: f32) -> f32
+{
+let b = a; if (b > 0.0) { return b; }
+else { return -b; }
+}";
+
+    let expected = "struct Particle {
+    position: vec2<f32>,
+    velocity: vec2<f32>
+}
+fn f(a: f32) -> f32 {
+    let b = a;
+    if (b > 0.0) {
+        return b;
+    } else {
+        return -b;
+    }
+}
+";
+
+    assert_eq!(format(shader, INDENT), expected);
+    assert_eq!(format(shader, "\t"), expected.replace(INDENT, "\t"));
+}
+
+#[test]
+fn spacing() {
+    let shader = "@group(0)@binding(0) var<storage,read_write> a:array<vec2<f32>>;
+fn f(p:ptr<function,f32>)->f32{
+    for(var i=0;i<4;i++){*p+=-f32(i)*2e-3/(1.0-a[i].x);}
+    return select(1.0,0.0,!(*p>=0.5&&*p<1.0));
+}";
+
+    let expected = "@group(0) @binding(0) var<storage, read_write> a: array<vec2<f32>>;
+fn f(p: ptr<function, f32>) -> f32 {
+    for (var i = 0; i < 4; i++) {
+        *p += -f32(i) * 2e-3 / (1.0 - a[i].x);
+    }
+    return select(1.0, 0.0, !(*p >= 0.5 && *p < 1.0));
+}
+";
+
+    assert_eq!(format(shader, INDENT), expected);
+}
+
+#[test]
+fn comments_and_blank_lines_are_kept() {
+    let shader = "//#import sdf
+/* the scene,
+   as a distance */
+fn halo_scene(p: vec3<f32>) -> f32 {
+
+
+    // a sphere
+    return length(p)  -  1.0; //of radius 1
+
+
+
+}";
+
+    let expected = "//#import sdf
+/* the scene,
+   as a distance */
+fn halo_scene(p: vec3<f32>) -> f32 {
+    // a sphere
+    return length(p) - 1.0; //of radius 1
+}
+";
+
+    assert_eq!(format(shader, INDENT), expected);
+}
+
+#[test]
+fn continued_lines_are_indented() {
+    let shader = "fn f() -> f32 {
+return max(1.0,
+2.0);
+}";
+
+    let expected = "fn f() -> f32 {
+    return max(1.0,
+        2.0);
+}
+";
+
+    assert_eq!(format(shader, INDENT), expected);
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    let shader = "fn f(){switch 1{case 1,2:{}default:{loop{break;}}}}";
+    let formatted = format(shader, INDENT);
+
+    assert_eq!(
+        formatted,
+        "fn f() {
+    switch 1 {
+        case 1, 2: {}
+        default: {
+            loop {
+                break;
+            }
+        }
+    }
+}
+"
+    );
+    assert_eq!(format(&formatted, INDENT), formatted);
+}
+
+#[test]
+fn formatting_a_bundled_shader_is_idempotent() {
+    let shader = include_str!("../src/viewer/shaders/raymarch.wgsl");
+    let formatted = format(&shader.replace(INDENT, "\t"), INDENT);
+
+    assert_eq!(format(&formatted, INDENT), formatted);
+    assert_eq!(format(&formatted, "\t"), shader.replace(INDENT, "\t"));
+}
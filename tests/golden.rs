@@ -0,0 +1,107 @@
+//! Renders halo's shaders headlessly & compares them to stored golden images.
+//!
+//! They need an adapter, but a software one like lavapipe will do; without any they fail.
+//!
+//! Set `HALO_UPDATE_GOLDEN=1` to write the golden images from the current renders instead.
+
+use halo::headless::{self, Image, Renderer};
+use iced::futures::executor::block_on;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 256;
+
+/// The largest difference allowed in any channel of any pixel, to absorb the differences in
+/// precision between adapters.
+const TOLERANCE: u8 = 4;
+
+#[test]
+fn default_frag() {
+    compare(
+        "default_frag",
+        include_str!("../src/viewer/shaders/default_frag.wgsl"),
+        Duration::from_secs(1),
+    );
+}
+
+fn compare(name: &str, shader: &str, time: Duration) {
+    let renderer = match block_on(Renderer::new()) {
+        Ok(renderer) => renderer,
+        Err(headless::Error::NoAdapter) => panic!(
+            "`{name}` can't be rendered: no adapter was found, not even a software one. \
+            Install a software Vulkan driver like lavapipe (Mesa) or SwiftShader to run the \
+            golden tests"
+        ),
+        Err(error) => panic!("`{name}` can't be rendered: {error}"),
+    };
+
+    let image = block_on(renderer.render(shader, WIDTH, HEIGHT, time)).unwrap();
+    let path = golden_path(name);
+
+    if std::env::var_os("HALO_UPDATE_GOLDEN").is_some() {
+        write(&path, &image);
+        return;
+    }
+
+    let golden = read(&path);
+
+    assert_eq!(
+        (image.width, image.height),
+        (golden.width, golden.height),
+        "`{name}` was rendered at a different size than its golden image"
+    );
+
+    let mismatches = image
+        .pixels
+        .chunks(4)
+        .zip(golden.pixels.chunks(4))
+        .filter(|(pixel, expected)| {
+            pixel
+                .iter()
+                .zip(expected.iter())
+                .any(|(a, b)| a.abs_diff(*b) > TOLERANCE)
+        })
+        .count();
+
+    assert_eq!(
+        mismatches,
+        0,
+        "{mismatches} pixels of `{name}` differ from {}",
+        path.display()
+    );
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"))
+}
+
+fn read(path: &Path) -> Image {
+    let decoder = png::Decoder::new(File::open(path).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+
+    assert_eq!(info.color_type, png::ColorType::Rgba);
+    pixels.truncate(info.buffer_size());
+
+    Image {
+        width: info.width,
+        height: info.height,
+        pixels,
+    }
+}
+
+fn write(path: &Path, image: &Image) {
+    let file = BufWriter::new(File::create(path).unwrap());
+    let mut encoder = png::Encoder::new(file, image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(&image.pixels).unwrap();
+}
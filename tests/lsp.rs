@@ -0,0 +1,329 @@
+//! Runs `halo --lsp` & talks to it over stdio like an editor would.
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+const URI: &str = "file:///shader.wgsl";
+
+/// A client which sends scripted messages to a language server & reads its replies.
+struct Client {
+    server: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+    /// The notifications received while waiting for responses.
+    notifications: Vec<Value>,
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut server = Command::new(env!("CARGO_BIN_EXE_halo"))
+            .arg("--lsp")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Couldn't start the language server");
+
+        let stdin = server.stdin.take().unwrap();
+        let stdout = BufReader::new(server.stdout.take().unwrap());
+
+        let mut client = Self {
+            server,
+            stdin,
+            stdout,
+            next_id: 0,
+            notifications: vec![],
+        };
+
+        let capabilities = client.request("initialize", json!({ "capabilities": {} }));
+        assert_eq!(capabilities["capabilities"]["textDocumentSync"], 1);
+        client.notify("initialized", json!({}));
+
+        client
+    }
+
+    fn send(&mut self, message: Value) {
+        let content = message.to_string();
+
+        write!(
+            self.stdin,
+            "Content-Length: {}\r\n\r\n{content}",
+            content.len()
+        )
+        .unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = None;
+
+        loop {
+            let mut header = String::new();
+            self.stdout.read_line(&mut header).unwrap();
+
+            match header.trim_end().split_once(": ") {
+                Some(("Content-Length", value)) => length = Some(value.parse().unwrap()),
+                Some(_) => {}
+                None => break,
+            }
+        }
+
+        let mut content = vec![0; length.expect("Message has no Content-Length")];
+        self.stdout.read_exact(&mut content).unwrap();
+
+        serde_json::from_slice(&content).unwrap()
+    }
+
+    /// Sends a request & returns its result, panicking if it failed.
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        let response = self.request_raw(method, params);
+
+        assert!(
+            response.get("error").is_none(),
+            "{method} failed: {response}"
+        );
+
+        response["result"].clone()
+    }
+
+    fn request_raw(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+
+        loop {
+            let message = self.receive();
+
+            if message["id"] == id {
+                return message;
+            }
+
+            self.notifications.push(message);
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    /// The diagnostics published after opening or changing the document.
+    fn diagnostics(&mut self) -> Vec<Value> {
+        let message = match self.notifications.pop() {
+            Some(message) => message,
+            None => self.receive(),
+        };
+
+        assert_eq!(message["method"], "textDocument/publishDiagnostics");
+        assert_eq!(message["params"]["uri"], URI);
+
+        message["params"]["diagnostics"].as_array().unwrap().clone()
+    }
+
+    fn open(&mut self, text: &str) -> Vec<Value> {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": URI, "languageId": "wgsl", "version": 1, "text": text },
+            }),
+        );
+
+        self.diagnostics()
+    }
+
+    fn change(&mut self, text: &str) -> Vec<Value> {
+        self.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": URI, "version": 2 },
+                "contentChanges": [{ "text": text }],
+            }),
+        );
+
+        self.diagnostics()
+    }
+
+    /// Sends a request about the position of the end of the first `needle` in the document.
+    fn request_at(&mut self, method: &str, text: &str, needle: &str) -> Value {
+        let offset = text.find(needle).unwrap() + needle.len();
+        let before = &text[..offset];
+        let line = before.matches('\n').count();
+        let character = before.len() - before.rfind('\n').map_or(0, |i| i + 1);
+
+        self.request(
+            method,
+            json!({
+                "textDocument": { "uri": URI },
+                "position": { "line": line, "character": character },
+            }),
+        )
+    }
+
+    /// Shuts the server down & checks it exits cleanly.
+    fn exit(mut self) {
+        assert_eq!(self.request("shutdown", Value::Null), Value::Null);
+        self.notify("exit", Value::Null);
+
+        assert!(self.server.wait().unwrap().success());
+    }
+}
+
+const SHADER: &str = "//the colour of the frame
+fn colour(uv: vec2<f32>) -> vec3<f32> {
+    return vec3<f32>(uv, sin(uniforms.time));
+}
+
+@fragment
+fn fs_main(@builtin(position) clip_pos: vec4<f32>) -> @location(0) vec4<f32> {
+    let uv = clip_pos.xy / uniforms.scale;
+    return vec4<f32>(colour(uv), 1.0);
+}
+";
+
+#[test]
+fn diagnostics_include_the_prelude() {
+    let mut client = Client::start();
+
+    assert_eq!(client.open(SHADER), Vec::<Value>::new());
+
+    let diagnostics = client.change(&SHADER.replace("uniforms.time", "uniforms.tiem"));
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0]["range"],
+        json!({
+            "start": { "line": 2, "character": 38 },
+            "end": { "line": 2, "character": 42 },
+        })
+    );
+    assert_eq!(diagnostics[0]["severity"], 1);
+
+    let diagnostics = client.change(&SHADER.replace("fs_main", "main"));
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("fs_main"));
+
+    assert_eq!(client.change(SHADER), Vec::<Value>::new());
+
+    client.exit();
+}
+
+#[test]
+fn imported_libraries_are_resolved() {
+    let mut client = Client::start();
+
+    let shader = "//#import sdf
+@fragment
+fn fs_main(@builtin(position) clip_pos: vec4<f32>) -> @location(0) vec4<f32> {
+    return vec4<f32>(halo_sd_sphere(vec3<f32>(clip_pos.xy, 0.0), 1.0));
+}
+";
+
+    assert_eq!(client.open(shader), Vec::<Value>::new());
+    assert_eq!(
+        client.change(&shader.replace("//#import sdf\n", "")).len(),
+        1
+    );
+
+    client.exit();
+}
+
+#[test]
+fn completion_hover_and_navigation() {
+    let mut client = Client::start();
+    client.open(SHADER);
+
+    let completions = client.request_at("textDocument/completion", SHADER, "uniforms.ti");
+    let labels = completions
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|completion| completion["label"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(labels, ["time"]);
+
+    let hover = client.request_at("textDocument/hover", SHADER, "vec4<f32>(colo");
+    assert_eq!(
+        hover["contents"]["value"],
+        "```wgsl\nfn colour(uv: vec2<f32>) -> vec3<f32>\n```\n\nthe colour of the frame"
+    );
+
+    let definition = client.request_at("textDocument/definition", SHADER, "vec4<f32>(colo");
+    assert_eq!(definition["uri"], URI);
+    assert_eq!(
+        definition["range"]["start"],
+        json!({ "line": 1, "character": 3 })
+    );
+
+    let references = client.request_at("textDocument/references", SHADER, "let u");
+    assert_eq!(references.as_array().unwrap().len(), 2);
+
+    let help = client.request_at(
+        "textDocument/signatureHelp",
+        SHADER,
+        "vec4<f32>(colour(uv), ",
+    );
+    assert_eq!(
+        help["signatures"][0]["label"],
+        "fn vec4<T>(x: T, y: T, z: T, w: T) -> vec4<T>"
+    );
+    assert_eq!(help["activeParameter"], 1);
+
+    let help = client.request_at("textDocument/signatureHelp", SHADER, "vec4<f32>(colour(");
+    assert_eq!(
+        help["signatures"][0]["label"],
+        "fn colour(uv: vec2<f32>) -> vec3<f32>"
+    );
+    assert_eq!(help["activeParameter"], 0);
+
+    let symbols = client.request(
+        "textDocument/documentSymbol",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    let names = symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| symbol["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["colour", "fs_main"]);
+
+    client.exit();
+}
+
+#[test]
+fn formatting() {
+    let mut client = Client::start();
+    client.open("fn f()->f32{return 1.0;}");
+
+    let edits = client.request(
+        "textDocument/formatting",
+        json!({
+            "textDocument": { "uri": URI },
+            "options": { "tabSize": 2, "insertSpaces": true },
+        }),
+    );
+
+    assert_eq!(edits[0]["newText"], "fn f() -> f32 {\n  return 1.0;\n}\n");
+    assert_eq!(
+        edits[0]["range"],
+        json!({
+            "start": { "line": 0, "character": 0 },
+            "end": { "line": 0, "character": 24 },
+        })
+    );
+
+    client.exit();
+}
+
+#[test]
+fn unsupported_methods_are_errors() {
+    let mut client = Client::start();
+
+    let response = client.request_raw("textDocument/codeLens", json!({}));
+    assert_eq!(response["error"]["code"], -32601);
+
+    client.exit();
+}
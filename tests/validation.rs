@@ -0,0 +1,54 @@
+//! Validates shaders the way the editor does before they're rendered.
+
+use halo::validation::{self, Error};
+use halo::viewer::Target;
+use iced::futures::executor::block_on;
+use std::sync::Arc;
+
+const PARTICLES: &str = "//#length particles LENGTH
+@group(1) @binding(0) var<storage, read_write> particles: array<vec4<f32>>;
+
+@fragment
+fn fs_main(@builtin(position) clip_pos: vec4<f32>) -> @location(0) vec4<f32> {
+    return particles[0];
+}
+";
+
+fn validate(shader: &str) -> Result<(), Error> {
+    block_on(validation::validate(
+        Arc::new(shader.to_string()),
+        Target::default(),
+    ))
+    .map(|_| ())
+}
+
+#[test]
+fn storage_buffers_within_the_limits_are_accepted() {
+    assert!(validate(&PARTICLES.replace("LENGTH", "4096")).is_ok());
+}
+
+#[test]
+fn storage_buffers_over_the_limits_are_rejected() {
+    let error = validate(&PARTICLES.replace("LENGTH", &u32::MAX.to_string())).unwrap_err();
+
+    assert!(
+        matches!(&error, Error::Validation(message) if message.contains("binding 0")),
+        "{error:?}"
+    );
+}
+
+#[test]
+fn errors_in_probes_point_into_the_shader() {
+    let shader = "@fragment
+fn fs_main(@builtin(position) clip_pos: vec4<f32>) -> @location(0) vec4<f32> {
+    return halo_probe(vec4<f32>(missing));
+}
+";
+    let Err(Error::Parse { errors, .. }) = validate(shader) else {
+        panic!("`missing` should fail to parse");
+    };
+
+    let (range, _) = &errors[0];
+
+    assert_eq!(&shader[range.clone()], "missing");
+}